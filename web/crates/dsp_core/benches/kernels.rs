@@ -0,0 +1,69 @@
+//! Wall-clock comparisons of the crate's hot paths against naive versions.
+//!
+//! # What
+//! Times each optimized public entry point against a straightforward
+//! reimplementation over [`BENCH_RUNS`] calls and prints both per-call
+//! times. Run with `cargo bench --bench kernels`; for the `simd128`
+//! lanes, build for `wasm32-wasip1` with the `simd` feature and run under
+//! `wasmtime`.
+//!
+//! # Why
+//! Timing depends on the machine and its load, so speedups are measured
+//! here rather than asserted in unit tests, which check exact results.
+
+use spectro_dsp::{apply_window, benchmark_simd_speedup};
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Calls timed per measurement.
+const BENCH_RUNS: usize = 2000;
+
+/// Frame length of the benchmark signals.
+const FRAME_SIZE: usize = 4096;
+
+/// Average microseconds per call of `f` over [`BENCH_RUNS`] calls, after
+/// one untimed warm-up call.
+fn time_us(mut f: impl FnMut()) -> f64 {
+    f();
+    let start = Instant::now();
+    for _ in 0..BENCH_RUNS {
+        f();
+    }
+    start.elapsed().as_secs_f64() * 1e6 / BENCH_RUNS as f64
+}
+
+/// Print one comparison line.
+fn report(name: &str, optimized_us: f64, naive_us: f64) {
+    println!(
+        "{name:<24} {optimized_us:>9.2} us  vs {naive_us:>9.2} us naive  ({:.2}x)",
+        naive_us / optimized_us
+    );
+}
+
+/// Blackman window from its formula, recomputing every coefficient.
+fn blackman_by_formula(input: &[f32]) -> Vec<f32> {
+    let denom = (input.len() as f32 - 1.0).max(1.0);
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let phase = 2.0 * std::f32::consts::PI * i as f32 / denom;
+            x * (0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos())
+        })
+        .collect()
+}
+
+fn main() {
+    let signal: Vec<f32> = (0..FRAME_SIZE).map(|i| (i as f32 * 0.01).sin()).collect();
+
+    report(
+        "cached blackman window",
+        time_us(|| drop(black_box(apply_window(black_box(&signal), "blackman")))),
+        time_us(|| drop(black_box(blackman_by_formula(black_box(&signal))))),
+    );
+    println!(
+        "{:<24} {:>9.2}x  (window, magnitude and dB kernels vs scalar loops)",
+        "simd kernels",
+        benchmark_simd_speedup(FRAME_SIZE, BENCH_RUNS)
+    );
+}
@@ -0,0 +1,1358 @@
+//! Higher-level spectral analysis built on the core FFT routines.
+//!
+//! # What
+//! Estimators that derive a spectrum from a time-domain block through more
+//! than a single window + FFT pass, such as envelope analysis.
+//!
+//! # Why
+//! These pipelines chain several transforms. Keeping them in WASM avoids
+//! shipping intermediate buffers across the JS boundary.
+
+use crate::filters::{complementary_highpass, fir_filter_centered, lowpass_taps};
+use crate::spectrogram::{frame_count, half_spectrum_frames};
+use crate::{
+    apply_window_unchecked, half_spectrum_magnitudes, parse_window, plan_forward, plan_inverse,
+    validate_finite, window_coefficients, window_samples, WindowType, EPSILON, POWER_DB_SCALE,
+};
+use rustfft::num_complex::Complex32;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Diagonal loading added to the covariance matrix, relative to the
+/// zero-lag autocorrelation, so that inverting it stays well conditioned
+/// even for noise-free (rank-deficient) signals.
+const CAPON_DIAGONAL_LOADING: f64 = 1e-3;
+
+/// Fraction of the band below Nyquist that [`aliasing_metric`] inspects.
+const ALIASING_BAND_FRACTION: f32 = 0.1;
+
+/// Number of taps of the band-split crossover filter.
+const CROSSOVER_TAPS: usize = 127;
+
+/// Milliseconds per second, for reporting latency.
+const MILLIS_PER_SECOND: f32 = 1000.0;
+
+/// Highest fundamental in Hz that [`detect_pitch`] searches for.
+const PITCH_MAX_HZ: f32 = 2000.0;
+
+/// Cumulative-mean-normalized difference below which YIN accepts a lag.
+const YIN_THRESHOLD: f32 = 0.15;
+
+/// Normalized autocorrelation a peak needs to count as voiced.
+const ACF_VOICED_THRESHOLD: f32 = 0.5;
+
+/// Fraction of the highest autocorrelation peak that an earlier peak must
+/// reach to be preferred, so multiples of the period do not win.
+const ACF_PEAK_FRACTION: f32 = 0.9;
+
+/// Biased autocorrelation estimate `r[k] = sum(x[n] * x[n + k]) / len` for
+/// lags `0..lags`.
+///
+/// # Why
+/// The biased estimator guarantees a positive semi-definite Toeplitz matrix,
+/// which keeps the Levinson-Durbin recursion stable. Accumulation happens in
+/// `f64` so that high-order recursions do not lose precision.
+fn autocorrelation(input: &[f32], lags: usize) -> Vec<f64> {
+    let n = input.len();
+    (0..lags)
+        .map(|k| {
+            if k >= n {
+                return 0.0;
+            }
+            let sum: f64 = input[..n - k]
+                .iter()
+                .zip(&input[k..])
+                .map(|(&a, &b)| a as f64 * b as f64)
+                .sum();
+            sum / n as f64
+        })
+        .collect()
+}
+
+/// Solve for linear-prediction coefficients with the Levinson-Durbin
+/// recursion.
+///
+/// # What
+/// Returns `(a, error)` where `a = [1, a1, ..., a_order]` is the prediction
+/// error filter `A(z)` and `error` the final prediction error power.
+fn levinson_durbin(r: &[f64], order: usize) -> (Vec<f64>, f64) {
+    let mut a = vec![0.0f64; order + 1];
+    a[0] = 1.0;
+    let mut error = r[0];
+    for i in 1..=order {
+        if error <= 0.0 {
+            break;
+        }
+        let acc: f64 = (1..i).map(|j| a[j] * r[i - j]).sum::<f64>() + r[i];
+        let k = -acc / error;
+        let previous = a.clone();
+        for j in 1..i {
+            a[j] = previous[j] + k * previous[i - j];
+        }
+        a[i] = k;
+        error *= 1.0 - k * k;
+    }
+    (a, error.max(0.0))
+}
+
+/// Angular step between `n_freqs` evenly spaced points covering `[0, pi]`.
+fn half_band_step(n_freqs: usize) -> f64 {
+    if n_freqs > 1 {
+        std::f64::consts::PI / (n_freqs - 1) as f64
+    } else {
+        0.0
+    }
+}
+
+/// Forward-backward averaged sample covariance of length-`order` snapshots.
+///
+/// # Why
+/// A Toeplitz matrix built from the biased autocorrelation inherits the
+/// FFT's resolution limit. Averaging the outer products of overlapping
+/// snapshots (and their time-reversed copies) estimates the same
+/// autocorrelation matrix without that taper, which is what lets the Capon
+/// estimator resolve closely spaced tones.
+fn sample_covariance(input: &[f32], order: usize) -> Vec<f64> {
+    let snapshots = input.len() + 1 - order;
+    let mut covariance = vec![0.0f64; order * order];
+    for start in 0..snapshots {
+        let snap = &input[start..start + order];
+        for i in 0..order {
+            let xi = snap[i] as f64;
+            for j in i..order {
+                covariance[i * order + j] += xi * snap[j] as f64;
+            }
+        }
+    }
+    // Mirror the upper triangle and apply the backward (exchange) average.
+    let norm = 1.0 / (2 * snapshots) as f64;
+    let mut averaged = vec![0.0f64; order * order];
+    let at = |i: usize, j: usize| covariance[i.min(j) * order + i.max(j)];
+    for i in 0..order {
+        for j in 0..order {
+            let flipped = at(order - 1 - i, order - 1 - j);
+            averaged[i * order + j] = (at(i, j) + flipped) * norm;
+        }
+    }
+    averaged
+}
+
+/// Invert a dense row-major `n x n` matrix with Gauss-Jordan elimination.
+///
+/// Returns `None` when the matrix is numerically singular.
+fn invert_matrix(mut a: Vec<f64>, n: usize) -> Option<Vec<f64>> {
+    let mut inv = vec![0.0f64; n * n];
+    for i in 0..n {
+        inv[i * n + i] = 1.0;
+    }
+    for col in 0..n {
+        // Partial pivoting keeps the elimination numerically stable.
+        let pivot =
+            (col..n).max_by(|&x, &y| a[x * n + col].abs().total_cmp(&a[y * n + col].abs()))?;
+        if a[pivot * n + col].abs() < f64::EPSILON {
+            return None;
+        }
+        if pivot != col {
+            for k in 0..n {
+                a.swap(pivot * n + k, col * n + k);
+                inv.swap(pivot * n + k, col * n + k);
+            }
+        }
+        let scale = 1.0 / a[col * n + col];
+        for k in 0..n {
+            a[col * n + k] *= scale;
+            inv[col * n + k] *= scale;
+        }
+        for row in 0..n {
+            if row == col {
+                continue;
+            }
+            let factor = a[row * n + col];
+            if factor == 0.0 {
+                continue;
+            }
+            for k in 0..n {
+                a[row * n + k] -= factor * a[col * n + k];
+                inv[row * n + k] -= factor * inv[col * n + k];
+            }
+        }
+    }
+    Some(inv)
+}
+
+/// Compute the analytic signal of a real block via the FFT.
+///
+/// # How
+/// Transforms the block, zeroes the negative frequencies, doubles the
+/// positive ones (leaving DC and Nyquist untouched) and inverse transforms.
+/// The magnitude of the result is the instantaneous amplitude envelope.
+fn analytic_signal(input: &[f32]) -> Vec<Complex32> {
+    let n = input.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut buffer: Vec<Complex32> = input.iter().map(|&x| Complex32::new(x, 0.0)).collect();
+    plan_forward(n).process(&mut buffer);
+
+    // Bins strictly between DC and Nyquist carry the positive frequencies.
+    let positive_end = n.div_ceil(2);
+    for c in &mut buffer[1..positive_end] {
+        *c *= 2.0;
+    }
+    let negative_start = n / 2 + 1;
+    for c in &mut buffer[negative_start..] {
+        *c = Complex32::new(0.0, 0.0);
+    }
+
+    plan_inverse(n).process(&mut buffer);
+    let scale = 1.0 / n as f32;
+    for c in &mut buffer {
+        *c *= scale;
+    }
+    buffer
+}
+
+/// Compute the envelope spectrum of `input`.
+///
+/// # What
+/// Returns the linear magnitudes of bins `0..=n/2` of the DC-free amplitude
+/// envelope. Bin `k` corresponds to `k * sample_rate / n` Hz.
+///
+/// # Why
+/// Amplitude modulation, such as the periodic impacts of a damaged bearing,
+/// is buried around the carrier in the plain spectrum but shows up as a
+/// clean line at the modulation rate in the envelope spectrum.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `sample_rate` is not a
+/// positive finite number.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn envelope_spectrum(input: &[f32], sample_rate: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    if input.is_empty() {
+        return Vec::new();
+    }
+
+    let mut envelope: Vec<f32> = analytic_signal(input).iter().map(|c| c.norm()).collect();
+    let mean = envelope.iter().sum::<f32>() / envelope.len() as f32;
+    for v in &mut envelope {
+        *v -= mean;
+    }
+    half_spectrum_magnitudes(&envelope)
+}
+
+/// Estimate the minimum-variance distortionless (Capon/MVDR) spectrum.
+///
+/// # What
+/// Returns `n_freqs` power values evaluated on an evenly spaced grid from
+/// DC to Nyquist, i.e. point `k` lies at `k * sample_rate / (2 * (n_freqs - 1))`
+/// Hz. `order` is the size of the covariance matrix estimated from `input`
+/// and is clamped to the input length.
+///
+/// # Why
+/// For short records the FFT cannot separate tones closer than roughly
+/// `sample_rate / len`. The Capon estimator adapts its filter to the data
+/// and resolves such tones without a longer capture.
+///
+/// # How
+/// Estimates the autocorrelation matrix `R` as the forward-backward
+/// averaged sample covariance of `order`-sample snapshots, inverts it
+/// (with light diagonal loading) and evaluates
+/// `P(w) = 1 / (a(w)^H R^-1 a(w))` for the steering vector
+/// `a(w) = [1, e^{jw}, ..., e^{jw(order-1)}]`.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `sample_rate` is not a
+/// positive finite number.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn capon_spectrum(input: &[f32], order: usize, n_freqs: usize, sample_rate: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    if input.is_empty() || order == 0 || n_freqs == 0 {
+        return Vec::new();
+    }
+    let order = order.min(input.len());
+
+    let mut covariance = sample_covariance(input, order);
+    let loading = CAPON_DIAGONAL_LOADING * covariance[0].max(f64::MIN_POSITIVE);
+    for i in 0..order {
+        covariance[i * order + i] += loading;
+    }
+    let Some(inverse) = invert_matrix(covariance, order) else {
+        return vec![0.0; n_freqs];
+    };
+
+    // Collapse R^-1 along its diagonals: for a symmetric inverse the
+    // quadratic form only depends on sums over constant lag `n - m`.
+    let mut lag_sums = vec![0.0f64; order];
+    for m in 0..order {
+        for n in 0..order {
+            lag_sums[m.abs_diff(n)] += inverse[m * order + n];
+        }
+    }
+
+    let step = half_band_step(n_freqs);
+    (0..n_freqs)
+        .map(|k| {
+            let omega = k as f64 * step;
+            let denom: f64 = lag_sums
+                .iter()
+                .enumerate()
+                .map(|(lag, &v)| v * (omega * lag as f64).cos())
+                .sum();
+            if denom > 0.0 {
+                (1.0 / denom) as f32
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Compute the LPC spectral envelope of `input`.
+///
+/// # What
+/// Returns `n_freqs` linear magnitudes of the all-pole model
+/// `sqrt(error) / |A(e^{jw})|`, evaluated on an evenly spaced grid from DC
+/// (`w = 0`) to Nyquist (`w = pi`).
+///
+/// # Why
+/// An `order`-pole model fitted by linear prediction follows the vocal
+/// tract resonances (formants) while ignoring the harmonic fine structure
+/// of the excitation, which is what formant trackers need.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn lpc_envelope(input: &[f32], order: usize, n_freqs: usize) -> Vec<f32> {
+    validate_finite(input);
+    if input.is_empty() || order == 0 || n_freqs == 0 {
+        return Vec::new();
+    }
+    let order = order.min(input.len() - 1);
+    let r = autocorrelation(input, order + 1);
+    if r[0] <= 0.0 {
+        return vec![0.0; n_freqs];
+    }
+    let (a, error) = levinson_durbin(&r, order);
+    let gain = error.sqrt();
+
+    let step = half_band_step(n_freqs);
+    (0..n_freqs)
+        .map(|k| {
+            let omega = k as f64 * step;
+            let (mut re, mut im) = (0.0f64, 0.0f64);
+            for (j, &coeff) in a.iter().enumerate() {
+                let angle = omega * j as f64;
+                re += coeff * angle.cos();
+                im -= coeff * angle.sin();
+            }
+            let magnitude = (re * re + im * im).sqrt();
+            if magnitude > 0.0 {
+                (gain / magnitude) as f32
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Fractional offset of a peak from its center bin, in `[-0.5, 0.5]`.
+///
+/// # How
+/// Fits a parabola through three neighboring values and returns the
+/// position of its vertex relative to the middle sample.
+pub(crate) fn parabolic_offset(left: f32, center: f32, right: f32) -> f32 {
+    let denom = left - 2.0 * center + right;
+    if denom == 0.0 {
+        return 0.0;
+    }
+    (0.5 * (left - right) / denom).clamp(-0.5, 0.5)
+}
+
+/// Fractional bin of the strongest non-DC peak of a half spectrum.
+///
+/// # How
+/// Picks the largest bin strictly between DC and Nyquist, so an offset
+/// does not masquerade as a tone, and refines it by parabolic
+/// interpolation of the log magnitudes. Returns `None` for spectra with
+/// fewer than three bins or without any energy.
+pub(crate) fn interpolated_peak_bin(mags: &[f32]) -> Option<f32> {
+    if mags.len() < 3 {
+        return None;
+    }
+    let peak = (1..mags.len() - 1).max_by(|&a, &b| mags[a].total_cmp(&mags[b]))?;
+    if mags[peak] <= 0.0 {
+        return None;
+    }
+    let log = |v: f32| v.max(EPSILON).ln();
+    let offset = parabolic_offset(log(mags[peak - 1]), log(mags[peak]), log(mags[peak + 1]));
+    Some(peak as f32 + offset)
+}
+
+/// Center frequency in Hz of bin `bin` of an `fft_size`-point FFT.
+///
+/// # Panics
+/// Panics when `sample_rate` is not positive or `fft_size` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn bin_to_hz(bin: usize, sample_rate: f32, fft_size: usize) -> f32 {
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    assert!(fft_size > 0, "fft_size must be positive");
+    bin as f32 * sample_rate / fft_size as f32
+}
+
+/// Frequency of the peak at `bin`, refined between bins.
+///
+/// # What
+/// Fits a parabola through the log magnitudes of `bin` and its two
+/// neighbours and returns the frequency of its vertex, at most half a bin
+/// from [`bin_to_hz`]. A peak at the first or last bin of `mags` has only
+/// one neighbour and returns the plain bin frequency.
+///
+/// # Why
+/// Tuners need pitch far finer than one bin; the log-magnitude parabola is
+/// accurate to a few hundredths of a bin for a windowed sinusoid.
+///
+/// # Panics
+/// Panics when `mags` contains non-finite values, `bin` is out of range, or
+/// the [`bin_to_hz`] conditions are not met.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn interpolate_peak(mags: &[f32], bin: usize, sample_rate: f32, fft_size: usize) -> f32 {
+    validate_finite(mags);
+    assert!(bin < mags.len(), "bin must index into mags");
+    let center = bin_to_hz(bin, sample_rate, fft_size);
+    if bin == 0 || bin + 1 == mags.len() {
+        return center;
+    }
+    let log = |v: f32| v.max(EPSILON).ln();
+    let offset = parabolic_offset(log(mags[bin - 1]), log(mags[bin]), log(mags[bin + 1]));
+    center + offset * sample_rate / fft_size as f32
+}
+
+/// Estimate the true sample rate from a recording of a known reference tone.
+///
+/// # What
+/// Locates the tone's apparent frequency (assuming `nominal_rate`) with a
+/// Hann window and parabolic interpolation of the log-magnitude peak, then
+/// returns `nominal_rate * reference_hz / apparent_hz`. Returns
+/// `nominal_rate` unchanged when no peak can be found (e.g. silence).
+///
+/// # Why
+/// Field recorders with drifting clocks stretch every frequency by the
+/// same factor; a tone of known pitch reveals that factor.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or either rate is not a
+/// positive finite number.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn estimate_sample_rate(input: &[f32], nominal_rate: f32, reference_hz: f32) -> f32 {
+    validate_finite(input);
+    assert!(
+        nominal_rate.is_finite() && nominal_rate > 0.0,
+        "nominal_rate must be a positive finite number"
+    );
+    assert!(
+        reference_hz.is_finite() && reference_hz > 0.0,
+        "reference_hz must be a positive finite number"
+    );
+    let mags = half_spectrum_magnitudes(&window_samples(input, WindowType::Hann));
+    let Some(peak) = interpolated_peak_bin(&mags) else {
+        return nominal_rate;
+    };
+    let apparent_hz = peak * nominal_rate / input.len() as f32;
+    nominal_rate * reference_hz / apparent_hz
+}
+
+/// Analyze a block with a large FFT below a crossover and a small FFT above.
+///
+/// # What
+/// Splits `input` at `crossover_hz` with complementary linear-phase FIR
+/// filters, decimates the low band by `floor(sample_rate / (2 *
+/// crossover_hz))`, windows the most recent `low_fft` (decimated) and
+/// `high_fft` samples of each band and returns their linear magnitudes
+/// concatenated: `low_fft/2 + 1` low-band bins followed by `high_fft/2 + 1`
+/// high-band bins. Low-band bin `k` lies at `k * sample_rate / (factor *
+/// low_fft)` Hz, high-band bin `k` at `k * sample_rate / high_fft` Hz.
+/// Bands shorter than their FFT are zero-padded at the front.
+///
+/// # Why
+/// Bass needs long windows for frequency resolution while treble benefits
+/// from short windows for time resolution. Decimating the low band first
+/// gives the long window without a huge FFT.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, `sample_rate` is not
+/// positive, or `crossover_hz` is not strictly between 0 and Nyquist.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn two_band_spectrum(
+    input: &[f32],
+    crossover_hz: f32,
+    sample_rate: f32,
+    low_fft: usize,
+    high_fft: usize,
+    window_type: &str,
+) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    assert!(
+        crossover_hz > 0.0 && crossover_hz < sample_rate / 2.0,
+        "crossover_hz must lie between 0 and Nyquist"
+    );
+    if input.is_empty() {
+        return Vec::new();
+    }
+
+    let taps = lowpass_taps(crossover_hz / sample_rate, CROSSOVER_TAPS);
+    let low = fir_filter_centered(input, &taps);
+    let high = fir_filter_centered(input, &complementary_highpass(&taps));
+
+    let factor = ((sample_rate / (2.0 * crossover_hz)) as usize).max(1);
+    let decimated: Vec<f32> = low.iter().step_by(factor).copied().collect();
+
+    let mut output = band_magnitudes(&decimated, low_fft, window_type);
+    output.extend(band_magnitudes(&high, high_fft, window_type));
+    output
+}
+
+/// Window and transform the last `fft_size` samples of `band`.
+fn band_magnitudes(band: &[f32], fft_size: usize, window_type: &str) -> Vec<f32> {
+    if fft_size == 0 {
+        return Vec::new();
+    }
+    let mut frame = vec![0.0f32; fft_size];
+    let take = band.len().min(fft_size);
+    frame[fft_size - take..].copy_from_slice(&band[band.len() - take..]);
+    half_spectrum_magnitudes(&apply_window_unchecked(&frame, window_type))
+}
+
+/// Magnitude of the DFT of one block at normalized frequency `omega`.
+///
+/// # How
+/// The Goertzel recurrence `s[n] = x[n] + 2 cos(omega) s[n-1] - s[n-2]`
+/// runs in `f64`; `|X|^2 = s1^2 + s2^2 - 2 cos(omega) s1 s2` from its last
+/// two states holds for any `omega`, not just bin centers.
+fn goertzel_magnitude(input: &[f32], omega: f64) -> f32 {
+    let coeff = 2.0 * omega.cos();
+    let (mut s1, mut s2) = (0.0f64, 0.0f64);
+    for &x in input {
+        let s0 = x as f64 + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.0).sqrt() as f32
+}
+
+/// DFT magnitude of `input` at a single frequency.
+///
+/// # What
+/// Runs one Goertzel filter at `target_hz` and returns the same unnormalized
+/// magnitude as [`dft_at_frequencies`]. Targets outside `0..=sample_rate /
+/// 2` return `0`, as does empty input.
+///
+/// # Why
+/// Tone and DTMF detectors watch only a few frequencies; one pass over the
+/// block per frequency is much cheaper than a full FFT.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, `target_hz` is not
+/// finite or `sample_rate` is not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn goertzel(input: &[f32], sample_rate: f32, target_hz: f32) -> f32 {
+    validate_finite(input);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    assert!(target_hz.is_finite(), "target_hz must be finite");
+    if !(0.0..=sample_rate / 2.0).contains(&target_hz) {
+        return 0.0;
+    }
+    let omega = std::f64::consts::TAU * target_hz as f64 / sample_rate as f64;
+    goertzel_magnitude(input, omega)
+}
+
+/// DFT magnitudes of `input` at arbitrary frequencies.
+///
+/// # What
+/// Returns `|sum x[n] e^(-j 2 pi f n / sample_rate)|` for every `f` in
+/// `freqs_hz`, evaluated by a bank of Goertzel filters. The values are
+/// unnormalized like [`crate::fft_real`] and coincide with FFT magnitudes
+/// when `f` is a bin center. Empty input yields an empty result.
+///
+/// # Why
+/// Log-frequency displays need values at exact pixel frequencies;
+/// evaluating the DFT there avoids interpolating between FFT bins. Each
+/// frequency costs one pass over `input`, which beats an FFT when only a
+/// few hundred points are needed.
+///
+/// # Panics
+/// Panics when `input` or `freqs_hz` contain non-finite values or
+/// `sample_rate` is not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn dft_at_frequencies(input: &[f32], freqs_hz: &[f32], sample_rate: f32) -> Vec<f32> {
+    validate_finite(input);
+    validate_finite(freqs_hz);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    if input.is_empty() {
+        return Vec::new();
+    }
+    freqs_hz
+        .iter()
+        .map(|&f| {
+            let omega = std::f64::consts::TAU * f as f64 / sample_rate as f64;
+            goertzel_magnitude(input, omega)
+        })
+        .collect()
+}
+
+/// Modulation spectrum: the spectrum of each band's temporal envelope.
+///
+/// # What
+/// Computes linear magnitude frames of `fft_size` samples every `hop`
+/// samples, splits bins `1..=fft_size/2` into `n_bands` equal-width bands,
+/// and sums each band per frame into an envelope. Each envelope has its
+/// mean removed and is transformed over time. Returns `n_bands` rows of
+/// `n_frames / 2 + 1` linear magnitudes, band-major; modulation bin `m`
+/// lies at `m * sample_rate / (hop * n_frames)` Hz. Inputs shorter than one
+/// frame yield an empty result. Unknown window names panic.
+///
+/// # Why
+/// Speech intelligibility measures weigh how strongly each band is
+/// modulated at syllable rates (roughly 2-16 Hz), which the ordinary
+/// spectrogram does not show directly.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, `hop` is zero,
+/// `sample_rate` is not positive, or `n_bands` is not in
+/// `1..=fft_size/2`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn modulation_spectrum(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    sample_rate: f32,
+    n_bands: usize,
+) -> Vec<f32> {
+    validate_finite(input);
+    assert!(hop > 0, "hop must be positive");
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    assert!(
+        n_bands > 0 && n_bands <= fft_size / 2,
+        "n_bands must be in 1..=fft_size/2"
+    );
+    let window = parse_window(window_type);
+    let usable = fft_size / 2;
+    let mut envelopes: Vec<Vec<f32>> = vec![Vec::new(); n_bands];
+    for mags in half_spectrum_frames(input, fft_size, hop, window) {
+        for (b, env) in envelopes.iter_mut().enumerate() {
+            let lo = 1 + b * usable / n_bands;
+            let hi = 1 + (b + 1) * usable / n_bands;
+            env.push(mags[lo..hi].iter().sum());
+        }
+    }
+
+    let mut output = Vec::new();
+    for mut env in envelopes {
+        if env.is_empty() {
+            break;
+        }
+        let mean = env.iter().sum::<f32>() / env.len() as f32;
+        for v in &mut env {
+            *v -= mean;
+        }
+        output.extend(half_spectrum_magnitudes(&env));
+    }
+    output
+}
+
+/// Share of spectral energy in the top 10% of the band below Nyquist.
+///
+/// # What
+/// Hann-windows `input`, takes its power spectrum and returns the energy of
+/// the highest [`ALIASING_BAND_FRACTION`] of bins divided by the total, in
+/// `0..=1`. Silent or empty input returns `0`. `sample_rate` only labels
+/// the band (`0.9..1.0` of `sample_rate / 2`); the ratio does not depend
+/// on it.
+///
+/// # Why
+/// Content piled up just below Nyquist suggests the signal was not
+/// band-limited before sampling or decimation, so aliases are likely.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `sample_rate` is not
+/// positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn aliasing_metric(input: &[f32], sample_rate: f32) -> f32 {
+    validate_finite(input);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    let power: Vec<f64> = half_spectrum_magnitudes(&window_samples(input, WindowType::Hann))
+        .iter()
+        .map(|&m| (m as f64) * (m as f64))
+        .collect();
+    let total: f64 = power.iter().sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let top = ((power.len() as f32 * ALIASING_BAND_FRACTION).ceil() as usize).max(1);
+    let high: f64 = power[power.len() - top..].iter().sum();
+    (high / total) as f32
+}
+
+/// Delay of `recorded` relative to `reference`, in milliseconds.
+///
+/// # What
+/// Cross-correlates the two signals and returns the lag of the correlation
+/// peak divided by `sample_rate`. The lag is refined to a fraction of a
+/// sample by a parabola through the peak and its neighbours. A negative
+/// result means `recorded` leads `reference`. Empty input returns `0`.
+///
+/// # Why
+/// Playing a known signal through a device and recording it back gives the
+/// round-trip latency needed to align playback with capture.
+///
+/// # How
+/// Both signals are zero-padded to a power of two of at least
+/// `reference.len() + recorded.len() - 1` samples, so the circular
+/// correlation `IFFT(R · conj(X))` equals the linear one for every lag.
+///
+/// # Panics
+/// Panics when either input contains non-finite values or `sample_rate` is
+/// not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn measure_latency(reference: &[f32], recorded: &[f32], sample_rate: f32) -> f32 {
+    validate_finite(reference);
+    validate_finite(recorded);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    if reference.is_empty() || recorded.is_empty() {
+        return 0.0;
+    }
+    let n = (reference.len() + recorded.len() - 1).next_power_of_two();
+    let spectrum = |signal: &[f32]| {
+        let mut buffer: Vec<Complex32> = signal.iter().map(|&x| Complex32::new(x, 0.0)).collect();
+        buffer.resize(n, Complex32::new(0.0, 0.0));
+        plan_forward(n).process(&mut buffer);
+        buffer
+    };
+    let mut corr: Vec<Complex32> = spectrum(recorded)
+        .iter()
+        .zip(&spectrum(reference))
+        .map(|(r, x)| r * x.conj())
+        .collect();
+    plan_inverse(n).process(&mut corr);
+
+    let peak = (0..n)
+        .max_by(|&a, &b| corr[a].re.total_cmp(&corr[b].re))
+        .unwrap_or(0);
+    let offset = parabolic_offset(
+        corr[(peak + n - 1) % n].re,
+        corr[peak].re,
+        corr[(peak + 1) % n].re,
+    );
+    // Indices past the largest positive lag wrap around to negative lags.
+    let lag = if peak < recorded.len() {
+        peak as f32
+    } else {
+        peak as f32 - n as f32
+    };
+    (lag + offset) * MILLIS_PER_SECOND / sample_rate
+}
+
+/// One-sided power spectral density by Welch's method.
+///
+/// # What
+/// Averages the power spectra `|X|^2` of all complete `frame_size` frames
+/// spaced `hop` apart and scales the mean to power per Hz: every bin is
+/// divided by `sample_rate * sum(w^2)`, which folds in the window's
+/// equivalent noise bandwidth, and bins other than DC and Nyquist are
+/// doubled to account for negative frequencies. Summing the result times
+/// the bin width `sample_rate / frame_size` gives the signal's mean power.
+/// Returns `frame_size/2 + 1` bins. Input shorter than one frame is
+/// zero-padded to a single frame; empty input yields an empty result.
+///
+/// # Why
+/// Averaging overlapping frames trades frequency resolution for a much
+/// smoother estimate, which is what noise characterization needs.
+///
+/// # Panics
+/// Panics when `samples` contains non-finite values, `frame_size` or `hop`
+/// is zero, or `sample_rate` is not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn welch_psd(
+    samples: &[f32],
+    frame_size: usize,
+    hop: usize,
+    window_type: &str,
+    sample_rate: f32,
+) -> Vec<f32> {
+    validate_finite(samples);
+    assert!(frame_size > 0, "frame_size must be positive");
+    assert!(hop > 0, "hop must be positive");
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let window = parse_window(window_type);
+    let padded;
+    let signal = if samples.len() < frame_size {
+        padded = [samples, &vec![0.0; frame_size - samples.len()]].concat();
+        &padded[..]
+    } else {
+        samples
+    };
+
+    let bins = frame_size / 2 + 1;
+    let mut sum = vec![0.0f64; bins];
+    for mags in half_spectrum_frames(signal, frame_size, hop, window) {
+        for (acc, &m) in sum.iter_mut().zip(&mags) {
+            *acc += (m as f64) * (m as f64);
+        }
+    }
+    let n_frames = frame_count(signal.len(), frame_size, hop) as f64;
+    let energy: f64 = window_coefficients(window, frame_size)
+        .iter()
+        .map(|&w| (w as f64) * (w as f64))
+        .sum();
+    let scale = 1.0 / (n_frames * sample_rate as f64 * energy.max(EPSILON as f64));
+    sum.iter()
+        .enumerate()
+        .map(|(k, &p)| {
+            let one_sided = k != 0 && !(frame_size.is_multiple_of(2) && k == frame_size / 2);
+            let fold = if one_sided { 2.0 } else { 1.0 };
+            (p * scale * fold) as f32
+        })
+        .collect()
+}
+
+/// Estimate the fundamental frequency of one frame.
+///
+/// # What
+/// Returns `[hz, confidence]` with `confidence` in `0..=1`. `method` is
+/// `"yin"` (de Cheveigné and Kawahara's cumulative-mean-normalized
+/// difference, confidence `1 - d'`) or `"autocorrelation"` (the highest
+/// normalized autocorrelation peak after the zero-lag lobe, or the first
+/// peak within [`ACF_PEAK_FRACTION`] of it, confidence the peak height).
+/// Lags from `sample_rate / PITCH_MAX_HZ` to half the frame are searched,
+/// so the lowest detectable pitch is `2 * sample_rate / frame.len()`.
+/// Unvoiced frames report `hz = 0` with their (low) confidence; silent or
+/// too-short frames report `[0, 0]`.
+///
+/// # Why
+/// A fundamental-frequency track over the spectrogram needs the period,
+/// which a spectral peak picker confuses with the strongest harmonic.
+///
+/// # Panics
+/// Panics when `frame` contains non-finite values, `sample_rate` is not
+/// positive or `method` is unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn detect_pitch(frame: &[f32], sample_rate: f32, method: &str) -> Vec<f32> {
+    validate_finite(frame);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    let detect = match method {
+        "yin" => yin_lag,
+        "autocorrelation" | "acf" => autocorrelation_lag,
+        _ => panic!("unknown pitch method: {method}"),
+    };
+    let min_lag = ((sample_rate / PITCH_MAX_HZ).ceil() as usize).max(2);
+    let max_lag = frame.len() / 2;
+    let energy: f32 = frame.iter().map(|x| x * x).sum();
+    if max_lag < min_lag + 2 || energy <= EPSILON {
+        return vec![0.0, 0.0];
+    }
+    match detect(frame, min_lag, max_lag) {
+        (lag, confidence, true) => vec![sample_rate / lag, confidence],
+        (_, confidence, false) => vec![0.0, confidence],
+    }
+}
+
+/// YIN period estimate: `(fractional lag, confidence, voiced)`.
+fn yin_lag(frame: &[f32], min_lag: usize, max_lag: usize) -> (f32, f32, bool) {
+    let width = frame.len() - max_lag;
+    let mut cmnd = vec![1.0f32; max_lag + 1];
+    let mut running = 0.0f64;
+    for (tau, d) in cmnd.iter_mut().enumerate().skip(1) {
+        let diff: f64 = frame[..width]
+            .iter()
+            .zip(&frame[tau..tau + width])
+            .map(|(&a, &b)| ((a - b) as f64).powi(2))
+            .sum();
+        running += diff;
+        if running > 0.0 {
+            *d = (diff * tau as f64 / running) as f32;
+        }
+    }
+    let mut tau = match (min_lag..max_lag).find(|&t| cmnd[t] < YIN_THRESHOLD) {
+        Some(t) => t,
+        None => (min_lag..max_lag)
+            .min_by(|&a, &b| cmnd[a].total_cmp(&cmnd[b]))
+            .unwrap_or(min_lag),
+    };
+    while tau + 1 < max_lag && cmnd[tau + 1] < cmnd[tau] {
+        tau += 1;
+    }
+    let offset = parabolic_offset(cmnd[tau - 1], cmnd[tau], cmnd[tau + 1]);
+    let confidence = (1.0 - cmnd[tau]).clamp(0.0, 1.0);
+    (tau as f32 + offset, confidence, cmnd[tau] < YIN_THRESHOLD)
+}
+
+/// Autocorrelation period estimate: `(fractional lag, confidence, voiced)`.
+fn autocorrelation_lag(frame: &[f32], min_lag: usize, max_lag: usize) -> (f32, f32, bool) {
+    let width = frame.len() - max_lag;
+    let energy = |s: &[f32]| s.iter().map(|&x| x as f64 * x as f64).sum::<f64>();
+    let head = energy(&frame[..width]);
+    let r: Vec<f32> = (0..=max_lag)
+        .map(|tau| {
+            let tail = &frame[tau..tau + width];
+            let dot: f64 = frame[..width]
+                .iter()
+                .zip(tail)
+                .map(|(&a, &b)| a as f64 * b as f64)
+                .sum();
+            let norm = (head * energy(tail)).sqrt();
+            if norm > 0.0 {
+                (dot / norm) as f32
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let start = (min_lag..max_lag)
+        .find(|&t| r[t + 1] > r[t])
+        .unwrap_or(min_lag);
+    let highest = r[start..max_lag].iter().copied().fold(f32::MIN, f32::max);
+    let peak = (start..max_lag)
+        .find(|&t| r[t] >= ACF_PEAK_FRACTION * highest && r[t] >= r[t - 1] && r[t] >= r[t + 1])
+        .unwrap_or(start);
+    let offset = parabolic_offset(r[peak - 1], r[peak], r[peak + 1]);
+    let confidence = r[peak].clamp(0.0, 1.0);
+    (
+        peak as f32 + offset,
+        confidence,
+        confidence >= ACF_VOICED_THRESHOLD,
+    )
+}
+
+/// [`welch_psd`] in dB/Hz with the segment overlap given as a fraction.
+///
+/// # What
+/// Segments of `segment_size` samples overlap by `overlap` of their length
+/// (`0.5` is the usual choice; the hop is rounded and at least one
+/// sample). Returns `10 * log10` of the one-sided PSD, i.e. dB relative to
+/// one unit squared per Hz, with silent bins clamped at the dB value of
+/// [`EPSILON`]. Empty input yields an empty result.
+///
+/// # Why
+/// Noise measurements are read off in dB/Hz, and overlap as a fraction is
+/// how most analysers configure Welch averaging.
+///
+/// # Panics
+/// Panics when `overlap` is outside `0..1` or under the [`welch_psd`]
+/// conditions.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn psd_welch(
+    input: &[f32],
+    segment_size: usize,
+    overlap: f32,
+    window_type: &str,
+    sample_rate: f32,
+) -> Vec<f32> {
+    assert!(
+        (0.0..1.0).contains(&overlap),
+        "overlap must be in the range 0..1"
+    );
+    let hop = ((segment_size as f32 * (1.0 - overlap)).round() as usize).max(1);
+    welch_psd(input, segment_size, hop, window_type, sample_rate)
+        .iter()
+        .map(|&p| POWER_DB_SCALE * p.max(EPSILON).log10())
+        .collect()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::noise, TWO_PI};
+
+    /// Sample rate chosen so that one FFT bin equals one hertz.
+    const SAMPLE_RATE: f32 = 4096.0;
+
+    /// Block length matching `SAMPLE_RATE` for 1 Hz bin spacing.
+    const BLOCK: usize = 4096;
+
+    /// Return the index of the largest value.
+    fn argmax(values: &[f32]) -> usize {
+        values
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap()
+    }
+
+    /// Count interior local maxima of `values` within `range`.
+    fn count_local_maxima(values: &[f32], range: std::ops::Range<usize>) -> usize {
+        range
+            .filter(|&i| values[i] > values[i - 1] && values[i] > values[i + 1])
+            .count()
+    }
+
+    /// An AM tone shows a line at the modulation rate, not the carrier.
+    #[test]
+    fn envelope_spectrum_peaks_at_modulation_frequency() {
+        let carrier_hz = 1000.0;
+        let modulation_hz = 40.0;
+        let data: Vec<f32> = (0..BLOCK)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE;
+                (1.0 + 0.5 * (TWO_PI * modulation_hz * t).cos()) * (TWO_PI * carrier_hz * t).sin()
+            })
+            .collect();
+        let spectrum = envelope_spectrum(&data, SAMPLE_RATE);
+        assert_eq!(spectrum.len(), BLOCK / 2 + 1);
+        assert_eq!(argmax(&spectrum), modulation_hz as usize);
+    }
+
+    /// Capon separates two tones closer than the FFT resolution limit.
+    #[test]
+    fn capon_resolves_close_tones() {
+        let rate = 1000.0;
+        let len = 64;
+        let data: Vec<f32> = (0..len)
+            .map(|i| {
+                let t = i as f32 / rate;
+                (TWO_PI * 200.0 * t).sin() + (TWO_PI * 210.0 * t).sin()
+            })
+            .collect();
+
+        // 1 Hz grid from 0 to 500 Hz.
+        let capon = capon_spectrum(&data, 24, 501, rate);
+        assert_eq!(capon.len(), 501);
+        assert_eq!(count_local_maxima(&capon, 185..226), 2);
+
+        // A heavily zero-padded FFT of the same record merges the tones.
+        let mut padded = data.clone();
+        padded.resize(1000, 0.0);
+        let fft = half_spectrum_magnitudes(&padded);
+        assert_eq!(count_local_maxima(&fft, 185..226), 1);
+    }
+
+    /// Indices of interior local maxima of `values`.
+    fn local_maxima(values: &[f32]) -> Vec<usize> {
+        (1..values.len() - 1)
+            .filter(|&i| values[i] > values[i - 1] && values[i] > values[i + 1])
+            .collect()
+    }
+
+    /// Pass `input` through a two-pole resonator at `freq` Hz.
+    fn resonate(input: &[f32], freq: f32, bandwidth: f32, rate: f32) -> Vec<f32> {
+        let radius = (-std::f32::consts::PI * bandwidth / rate).exp();
+        let theta = TWO_PI * freq / rate;
+        let (a1, a2) = (2.0 * radius * theta.cos(), -radius * radius);
+        let (mut y1, mut y2) = (0.0f32, 0.0f32);
+        input
+            .iter()
+            .map(|&x| {
+                let y = x + a1 * y1 + a2 * y2;
+                y2 = y1;
+                y1 = y;
+                y
+            })
+            .collect()
+    }
+
+    /// The LPC envelope peaks at the formants, not at every harmonic.
+    #[test]
+    fn lpc_envelope_tracks_formants() {
+        let rate = 8000.0;
+        let pitch_period = 80; // 100 Hz voice
+        let excitation: Vec<f32> = (0..2048)
+            .map(|i| if i % pitch_period == 0 { 1.0 } else { 0.0 })
+            .collect();
+        let voiced = resonate(
+            &resonate(&excitation, 500.0, 80.0, rate),
+            1500.0,
+            120.0,
+            rate,
+        );
+
+        // 10 Hz grid from 0 to 4000 Hz.
+        let envelope = lpc_envelope(&voiced, 10, 401);
+        assert_eq!(envelope.len(), 401);
+        let peaks = local_maxima(&envelope);
+        assert!(peaks.len() <= 5, "envelope follows harmonics: {peaks:?}");
+        for formant_bin in [50usize, 150] {
+            assert!(
+                peaks.iter().any(|&p| p.abs_diff(formant_bin) <= 5),
+                "no peak near bin {formant_bin}: {peaks:?}"
+            );
+        }
+    }
+
+    /// A tone captured by a fast clock reveals the true sample rate.
+    #[test]
+    fn estimate_sample_rate_corrects_clock_drift() {
+        let nominal = 48_000.0;
+        let actual = 48_100.0;
+        let tone_hz = 1000.0;
+        // The recorder actually sampled at `actual` while claiming `nominal`.
+        let data: Vec<f32> = (0..8192)
+            .map(|i| (TWO_PI * tone_hz * i as f32 / actual).sin())
+            .collect();
+        let estimate = estimate_sample_rate(&data, nominal, tone_hz);
+        assert!((estimate - actual).abs() < 10.0, "estimated {estimate}");
+    }
+
+    /// Low tones land in the low-band spectrum, high tones in the high band.
+    #[test]
+    fn two_band_spectrum_routes_content_by_band() {
+        let rate = 8000.0;
+        let (low_fft, high_fft) = (256, 64);
+        let tone = |hz: f32| -> Vec<f32> {
+            (0..4096)
+                .map(|i| (TWO_PI * hz * i as f32 / rate).sin())
+                .collect()
+        };
+        let peak = |v: &[f32]| v.iter().copied().fold(0.0f32, f32::max);
+
+        let low_tone = two_band_spectrum(&tone(300.0), 1000.0, rate, low_fft, high_fft, "hann");
+        assert_eq!(low_tone.len(), low_fft / 2 + 1 + high_fft / 2 + 1);
+        let (low_band, high_band) = low_tone.split_at(low_fft / 2 + 1);
+        // Decimated rate is 2000 Hz, so 300 Hz sits at bin 300 / 2000 * 256.
+        assert!(argmax(low_band).abs_diff(38) <= 1, "{}", argmax(low_band));
+        assert!(peak(low_band) > 100.0 * peak(high_band));
+
+        let high_tone = two_band_spectrum(&tone(3000.0), 1000.0, rate, low_fft, high_fft, "hann");
+        let (low_band, high_band) = high_tone.split_at(low_fft / 2 + 1);
+        assert_eq!(argmax(high_band), 24);
+        assert!(peak(high_band) > 100.0 * peak(low_band));
+    }
+
+    /// The Goertzel bank matches FFT bins at their center frequencies.
+    #[test]
+    fn dft_at_frequencies_matches_fft_bins() {
+        let (n, rate) = (256, 1000.0f32);
+        let input = noise(n, 17);
+        let fft = half_spectrum_magnitudes(&input);
+        let bins = [0usize, 1, 7, 64, 100, 128];
+        let freqs: Vec<f32> = bins.iter().map(|&k| k as f32 * rate / n as f32).collect();
+        let dft = dft_at_frequencies(&input, &freqs, rate);
+        for (&k, &d) in bins.iter().zip(&dft) {
+            assert!(
+                (d - fft[k]).abs() < 1e-3 * fft[k].max(1.0),
+                "bin {k}: {d} vs {}",
+                fft[k]
+            );
+        }
+    }
+
+    /// An amplitude-modulated tone peaks at the modulation rate in its band.
+    #[test]
+    fn modulation_spectrum_finds_am_rate() {
+        let (rate, fft_size, hop, n_bands) = (8000.0f32, 256, 64, 8);
+        let (carrier_hz, mod_hz) = (1000.0f32, 8.0f32);
+        let n_frames = 250;
+        let len = (n_frames - 1) * hop + fft_size;
+        let input: Vec<f32> = (0..len)
+            .map(|i| {
+                let t = i as f32 / rate;
+                (1.0 + 0.8 * (TWO_PI * mod_hz * t).sin()) * (TWO_PI * carrier_hz * t).sin()
+            })
+            .collect();
+
+        let spectrum = modulation_spectrum(&input, fft_size, hop, "hann", rate, n_bands);
+        let n_mod = n_frames / 2 + 1;
+        assert_eq!(spectrum.len(), n_bands * n_mod);
+
+        let bin_hz = rate / fft_size as f32;
+        let carrier_band = ((carrier_hz / bin_hz) as usize - 1) * n_bands / (fft_size / 2);
+        let row = &spectrum[carrier_band * n_mod..(carrier_band + 1) * n_mod];
+        let expected = (mod_hz * hop as f32 * n_frames as f32 / rate).round() as usize;
+        assert_eq!(argmax(row), expected);
+
+        let strongest_band = (0..n_bands)
+            .max_by(|&a, &b| {
+                spectrum[a * n_mod + expected].total_cmp(&spectrum[b * n_mod + expected])
+            })
+            .unwrap();
+        assert_eq!(strongest_band, carrier_band);
+    }
+
+    /// Band-limited content scores low; near-Nyquist content scores high.
+    #[test]
+    fn aliasing_metric_flags_near_nyquist_energy() {
+        let (n, rate) = (1024, 8000.0f32);
+        let tone = |hz: f32| -> Vec<f32> {
+            (0..n)
+                .map(|i| (TWO_PI * hz * i as f32 / rate).sin())
+                .collect()
+        };
+        let low = aliasing_metric(&tone(500.0), rate);
+        let high = aliasing_metric(&tone(3900.0), rate);
+        assert!(low < 1e-3, "{low}");
+        assert!(high > 0.9, "{high}");
+        assert_eq!(aliasing_metric(&[0.0; 64], rate), 0.0);
+    }
+
+    /// A copy delayed by a known fractional number of samples is recovered.
+    #[test]
+    fn measure_latency_recovers_known_delay() {
+        let reference = noise(2048, 3);
+        let delay = 137;
+        let mut recorded = vec![0.0f32; delay];
+        recorded.extend(reference.iter().map(|&x| 0.5 * x));
+        recorded.extend(vec![0.0f32; 500]);
+
+        let latency = measure_latency(&reference, &recorded, 48_000.0);
+        let expected = delay as f32 * MILLIS_PER_SECOND / 48_000.0;
+        assert!((latency - expected).abs() < 0.01, "{latency} vs {expected}");
+        // A band-limited chirp shifted by half a sample lands between bins.
+        let chirp = |t: f32| (TWO_PI * (50.0 + 0.05 * t) * t / 1000.0).sin();
+        let reference: Vec<f32> = (0..1000).map(|i| chirp(i as f32)).collect();
+        let recorded: Vec<f32> = (0..1200).map(|i| chirp(i as f32 - 40.5)).collect();
+        let latency = measure_latency(&reference, &recorded, 1000.0);
+        assert!((latency - 40.5).abs() < 0.2, "{latency}");
+    }
+
+    /// Goertzel matches the FFT bin of an on-target sine and reads far lower
+    /// off target or above Nyquist.
+    #[test]
+    fn goertzel_detects_target_tone() {
+        let (n, rate, bin) = (400, 8000.0f32, 35);
+        let target = bin as f32 * rate / n as f32;
+        let sine: Vec<f32> = (0..n)
+            .map(|i| (TWO_PI * target * i as f32 / rate).sin())
+            .collect();
+        let spectrum = crate::fft_real(&sine);
+        let fft_mag = spectrum[2 * bin].hypot(spectrum[2 * bin + 1]);
+
+        let on = goertzel(&sine, rate, target);
+        assert!((on - fft_mag).abs() < 1e-3 * fft_mag, "{on} vs {fft_mag}");
+        assert!(goertzel(&sine, rate, target + 300.0) < 0.01 * on);
+        assert_eq!(goertzel(&sine, rate, rate), 0.0);
+    }
+
+    /// White noise has a flat PSD at its variance; a sine's peak integrates
+    /// to the sine's power.
+    #[test]
+    fn welch_psd_is_flat_for_noise_and_scaled_per_hz() {
+        let (rate, frame) = (8000.0f32, 256);
+        let bin_hz = rate / frame as f32;
+        // Uniform noise on [-1, 1) has variance 1/3.
+        let psd = welch_psd(&noise(1 << 16, 4), frame, frame / 2, "hann", rate);
+        assert_eq!(psd.len(), frame / 2 + 1);
+        let expected = 2.0 / (3.0 * rate);
+        for &p in &psd[1..psd.len() - 1] {
+            assert!((p / expected - 1.0).abs() < 0.2, "{p} vs {expected}");
+        }
+
+        let (amplitude, bin) = (0.5f32, 40);
+        let sine: Vec<f32> = (0..8192)
+            .map(|i| amplitude * (TWO_PI * bin as f32 * bin_hz * i as f32 / rate).sin())
+            .collect();
+        let psd = welch_psd(&sine, frame, frame / 2, "hann", rate);
+        assert_eq!(argmax(&psd), bin);
+        let power: f32 = psd[bin - 3..=bin + 3].iter().sum::<f32>() * bin_hz;
+        let expected = amplitude * amplitude / 2.0;
+        assert!(
+            (power - expected).abs() < 0.02 * expected,
+            "{power} vs {expected}"
+        );
+    }
+
+    /// A sine between two bins is located far closer than the raw bin.
+    #[test]
+    fn interpolate_peak_refines_between_bins() {
+        let (rate, n) = (8000.0f32, 512);
+        let true_hz = bin_to_hz(50, rate, n) + 0.37 * rate / n as f32;
+        let sine: Vec<f32> = (0..n)
+            .map(|i| (TWO_PI * true_hz * i as f32 / rate).sin())
+            .collect();
+        let mags = half_spectrum_magnitudes(&window_samples(&sine, WindowType::Hann));
+        let peak = argmax(&mags);
+        let raw_error = (bin_to_hz(peak, rate, n) - true_hz).abs();
+        let refined_error = (interpolate_peak(&mags, peak, rate, n) - true_hz).abs();
+        assert!(
+            refined_error < 0.2 * raw_error,
+            "{refined_error} vs {raw_error}"
+        );
+
+        let last = mags.len() - 1;
+        assert_eq!(interpolate_peak(&mags, last, rate, n), rate / 2.0);
+        assert_eq!(interpolate_peak(&mags, 0, rate, n), 0.0);
+    }
+
+    /// Both methods find the fundamental of a harmonic tone and reject noise.
+    #[test]
+    fn detect_pitch_finds_fundamental() {
+        let (rate, n, f0) = (16_000.0f32, 2048, 150.0f32);
+        let tone: Vec<f32> = (0..n)
+            .map(|i| {
+                let t = TWO_PI * f0 * i as f32 / rate;
+                0.5 * t.sin() + 0.8 * (2.0 * t).sin() + 0.6 * (3.0 * t).sin()
+            })
+            .collect();
+        for method in ["yin", "autocorrelation"] {
+            let pitch = detect_pitch(&tone, rate, method);
+            assert!((pitch[0] - f0).abs() < 1.0, "{method}: {pitch:?}");
+            assert!(pitch[1] > 0.9, "{method}: {pitch:?}");
+
+            let unvoiced = detect_pitch(&noise(n, 5), rate, method);
+            assert_eq!(unvoiced[0], 0.0, "{method}: {unvoiced:?}");
+            assert!(unvoiced[1] < pitch[1]);
+            assert_eq!(detect_pitch(&vec![0.0; n], rate, method), vec![0.0, 0.0]);
+        }
+    }
+
+    /// The dB PSD of unit-variance-per-Hz noise sits at the expected level
+    /// and matches `welch_psd` at the equivalent hop.
+    #[test]
+    fn psd_welch_reports_db_per_hz() {
+        let (rate, segment) = (8000.0f32, 256);
+        let samples = noise(1 << 15, 6);
+        let db = psd_welch(&samples, segment, 0.5, "hann", rate);
+        let linear = welch_psd(&samples, segment, segment / 2, "hann", rate);
+        for (d, l) in db.iter().zip(&linear) {
+            assert!((d - 10.0 * l.log10()).abs() < 1e-4);
+        }
+        let expected_db = 10.0 * (2.0 / (3.0 * rate)).log10();
+        let mean_db = db[1..db.len() - 1].iter().sum::<f32>() / (db.len() - 2) as f32;
+        assert!(
+            (mean_db - expected_db).abs() < 1.0,
+            "{mean_db} vs {expected_db}"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Self-timing helpers for tuning real-time budgets.
+//!
+//! # What
+//! Measures how long core operations take on the machine the crate is
+//! actually running on.
+//!
+//! # Why
+//! FFT cost varies widely between desktop, mobile, and WASM engines.
+//! Measuring in place lets callers pick frame sizes that fit their audio
+//! callback budget.
+//!
+//! The timers use `Instant` natively and on WASI, and `performance.now()`
+//! in the browser. A `wasm32-unknown-unknown` build without the `wasm`
+//! feature has neither clock, so it leaves this module out.
+
+use crate::simd::{self, scalar};
+use crate::{plan_forward, DB_SCALE};
+use rustfft::num_complex::Complex32;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Number of untimed FFTs run before measuring, to plan and warm caches.
+const WARMUP_RUNS: usize = 3;
+
+/// Microseconds per millisecond.
+const MICROS_PER_MILLI: f64 = 1000.0;
+
+/// Milliseconds per second.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+const MILLIS_PER_SECOND: f64 = 1000.0;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[wasm_bindgen]
+extern "C" {
+    /// `performance.now()`: high-resolution milliseconds since time origin.
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+/// Current time in milliseconds from an arbitrary but fixed origin.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    performance_now()
+}
+
+/// Current time in milliseconds from an arbitrary but fixed origin.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * MILLIS_PER_SECOND
+}
+
+/// Average wall-clock time of one complex FFT of `size` points.
+///
+/// # What
+/// Runs a few warm-up transforms, then times `runs` in-place FFTs on a
+/// fixed test signal and returns the mean in microseconds. Uses
+/// `Instant` natively and `performance.now()` under WASM, whose resolution
+/// may be coarsened by the browser; use enough `runs` to average it out.
+///
+/// # Panics
+/// Panics when `size` or `runs` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn benchmark_fft(size: usize, runs: usize) -> f32 {
+    assert!(size > 0, "size must be positive");
+    assert!(runs > 0, "runs must be positive");
+    let fft = plan_forward(size);
+    let signal: Vec<Complex32> = (0..size)
+        .map(|i| Complex32::new((i as f32 * 0.1).sin(), 0.0))
+        .collect();
+    let mut buffer = signal.clone();
+    for _ in 0..WARMUP_RUNS {
+        fft.process(&mut buffer);
+    }
+
+    let start = now_ms();
+    for _ in 0..runs {
+        buffer.copy_from_slice(&signal);
+        fft.process(&mut buffer);
+        std::hint::black_box(&buffer);
+    }
+    let elapsed_ms = now_ms() - start;
+    (elapsed_ms * MICROS_PER_MILLI / runs as f64) as f32
+}
+
+/// How many times faster the element-wise kernels run than plain loops.
+///
+/// # What
+/// Times `runs` passes of the window multiply, complex magnitude and dB
+/// conversion over `size` values, once through the kernels the spectrum
+/// paths use and once through the scalar loops, and returns the scalar
+/// time divided by the kernel time. About `1` unless the crate was built
+/// for `wasm32` with the `simd` feature and `simd128`.
+///
+/// # Why
+/// Shows on the actual browser and device whether the `simd` build pays
+/// off, which no native benchmark can.
+///
+/// # Panics
+/// Panics when `size` or `runs` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn benchmark_simd_speedup(size: usize, runs: usize) -> f32 {
+    assert!(size > 0, "size must be positive");
+    assert!(runs > 0, "runs must be positive");
+    let signal: Vec<f32> = (0..size).map(|i| (i as f32 * 0.1).sin()).collect();
+    let window: Vec<f32> = (0..size).map(|i| (i as f32 * 0.01).cos()).collect();
+    let bins: Vec<Complex32> = signal
+        .iter()
+        .zip(&window)
+        .map(|(&re, &im)| Complex32::new(re, im))
+        .collect();
+
+    let kernels = time_ms(runs, || {
+        std::hint::black_box(simd::multiply(&signal, &window));
+        let mut mags = simd::norms(&bins);
+        simd::to_db_in_place(&mut mags, DB_SCALE, 1.0);
+        std::hint::black_box(mags);
+    });
+    let loops = time_ms(runs, || {
+        let mut product = Vec::with_capacity(size);
+        scalar::multiply(&signal, &window, &mut product);
+        std::hint::black_box(product);
+        let mut mags = Vec::with_capacity(size);
+        scalar::norms(&bins, &mut mags);
+        scalar::to_db_in_place(&mut mags, DB_SCALE, 1.0);
+        std::hint::black_box(mags);
+    });
+    (loops / kernels.max(f64::MIN_POSITIVE)) as f32
+}
+
+/// Milliseconds taken by `runs` calls of `f`, after [`WARMUP_RUNS`]
+/// untimed ones.
+fn time_ms(runs: usize, mut f: impl FnMut()) -> f64 {
+    for _ in 0..WARMUP_RUNS {
+        f();
+    }
+    let start = now_ms();
+    for _ in 0..runs {
+        f();
+    }
+    now_ms() - start
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Larger transforms take measurably longer than small ones.
+    #[test]
+    fn benchmark_fft_scales_with_size() {
+        let small = benchmark_fft(64, 200);
+        let large = benchmark_fft(16_384, 200);
+        assert!(small >= 0.0);
+        assert!(large > small, "large {large} <= small {small}");
+    }
+
+    /// The speedup is a positive ratio; without `simd128` lanes both sides
+    /// run the same loops.
+    #[test]
+    fn benchmark_simd_speedup_is_a_ratio() {
+        let speedup = benchmark_simd_speedup(4096, 50);
+        assert!(speedup.is_finite() && speedup > 0.0, "{speedup}");
+    }
+}
@@ -0,0 +1,106 @@
+//! Pitch-class profiles.
+//!
+//! # What
+//! Folds spectral energy from every octave into the twelve pitch classes of
+//! equal temperament.
+//!
+//! # Why
+//! Key and chord displays care which notes sound, not in which octave;
+//! folding in WASM sends twelve values per frame instead of a spectrum.
+
+use crate::{half_spectrum_magnitudes, validate_finite, window_samples, WindowType};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Pitch classes per octave.
+const PITCH_CLASSES: usize = 12;
+
+/// Semitones from C up to A, which places the tuning reference.
+const A_PITCH_CLASS: f32 = 9.0;
+
+/// Lowest bin frequency folded into the chromagram (A0).
+const CHROMA_MIN_HZ: f32 = 27.5;
+
+/// Highest bin frequency folded in; above it harmonics dominate.
+const CHROMA_MAX_HZ: f32 = 5000.0;
+
+/// Twelve-bin pitch-class energy of one frame.
+///
+/// # What
+/// Hann-windows `frame` and adds the power of each bin between
+/// [`CHROMA_MIN_HZ`] and [`CHROMA_MAX_HZ`] to the pitch class nearest its
+/// frequency, with A4 at `tuning` Hz (usually `440`). Returns the twelve
+/// classes starting at C, scaled so the strongest is `1`. Silent frames
+/// give zeros; empty input yields an empty result.
+///
+/// # Panics
+/// Panics when `frame` contains non-finite values or `sample_rate` or
+/// `tuning` is not a positive finite number.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn chromagram(frame: &[f32], sample_rate: f32, tuning: f32) -> Vec<f32> {
+    validate_finite(frame);
+    for (name, value) in [("sample_rate", sample_rate), ("tuning", tuning)] {
+        assert!(
+            value.is_finite() && value > 0.0,
+            "{name} must be a positive finite number"
+        );
+    }
+    if frame.is_empty() {
+        return Vec::new();
+    }
+    let mags = half_spectrum_magnitudes(&window_samples(frame, WindowType::Hann));
+    let bin_hz = sample_rate / frame.len() as f32;
+    let mut chroma = vec![0.0f32; PITCH_CLASSES];
+    for (k, &m) in mags.iter().enumerate() {
+        let hz = k as f32 * bin_hz;
+        if !(CHROMA_MIN_HZ..=CHROMA_MAX_HZ).contains(&hz) {
+            continue;
+        }
+        let semitones = PITCH_CLASSES as f32 * (hz / tuning).log2() + A_PITCH_CLASS;
+        let class = (semitones.round() as i64).rem_euclid(PITCH_CLASSES as i64) as usize;
+        chroma[class] += m * m;
+    }
+    let max = chroma.iter().copied().fold(0.0, f32::max);
+    if max > 0.0 {
+        chroma.iter_mut().for_each(|c| *c /= max);
+    }
+    chroma
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TWO_PI;
+
+    /// A C major triad lights up C, E and G; retuning shifts the classes.
+    #[test]
+    fn chromagram_finds_triad_notes() {
+        let (rate, n) = (22_050.0f32, 8192);
+        let notes_hz = [261.63f32, 329.63, 392.0];
+        let triad: Vec<f32> = (0..n)
+            .map(|i| {
+                let t = i as f32 / rate;
+                notes_hz.iter().map(|f| (TWO_PI * f * t).sin()).sum()
+            })
+            .collect();
+        let chroma = chromagram(&triad, rate, 440.0);
+        assert_eq!(chroma.len(), PITCH_CLASSES);
+        for class in [0, 4, 7] {
+            assert!(chroma[class] > 0.5, "{chroma:?}");
+        }
+        for class in [1, 2, 3, 5, 6, 8, 9, 10, 11] {
+            assert!(chroma[class] < 0.1, "{chroma:?}");
+        }
+
+        // With A4 a semitone higher, the same tones read a semitone lower.
+        let retuned = chromagram(&triad, rate, 440.0 * 2f32.powf(1.0 / 12.0));
+        for class in [11, 3, 6] {
+            assert!(retuned[class] > 0.5, "{retuned:?}");
+        }
+        assert_eq!(chromagram(&vec![0.0; n], rate, 440.0), vec![0.0; 12]);
+    }
+}
@@ -0,0 +1,312 @@
+//! Serializable analysis settings and spectrograms tagged with them.
+//!
+//! # What
+//! [`AnalysisConfig`] bundles the parameters of an STFT analysis and
+//! round-trips through versioned JSON; [`analyze_signal`] runs the analysis
+//! it describes and returns a [`TaggedSpectrogram`] that carries the config
+//! alongside the frames.
+//!
+//! # Why
+//! Positional parameters drift apart between the React component, saved
+//! exports and the WASM calls. One serialized object that travels with the
+//! data keeps them consistent and lets old exports be recognized.
+//!
+//! Only [`analyze_signal`] output is tagged and versioned. The positional
+//! batch APIs, such as [`crate::compute_spectrogram`],
+//! [`crate::compute_spectrogram_parallel`] and [`crate::stft_all`], keep
+//! returning bare frames for existing callers. Exports that must be
+//! re-readable later should come from [`analyze_signal`].
+
+use crate::scaling::apply_scale;
+use crate::spectrogram::half_spectrum_frames;
+use crate::{parse_window, validate_finite, DspError, Spectrogram, SpectrumScale, WindowType};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Version written by [`AnalysisConfig::to_json`]; bump it whenever the
+/// meaning or set of fields changes.
+pub const ANALYSIS_CONFIG_VERSION: u32 = 1;
+
+/// Parameters of one STFT analysis.
+///
+/// # What
+/// Frames of `fft_size` samples every `hop` samples, windowed with
+/// `window`, reported as the `fft_size / 2 + 1` bins of `scale` relative to
+/// `reference`, for audio at `sample_rate` Hz. The JSON form is
+/// `{"version":1,"fft_size":2048,"hop":512,"window":"hann",
+/// "sample_rate":48000.0,"scale":"amplitude_db","reference":1.0}`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnalysisConfig {
+    version: u32,
+    fft_size: usize,
+    hop: usize,
+    window: WindowType,
+    sample_rate: f32,
+    scale: SpectrumScale,
+    reference: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AnalysisConfig {
+    /// Create a config at the current [`ANALYSIS_CONFIG_VERSION`].
+    ///
+    /// # Panics
+    /// Panics on an unknown window name or with the
+    /// [`DspError::InvalidConfig`] message when a value is out of range.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        fft_size: usize,
+        hop: usize,
+        window_type: &str,
+        sample_rate: f32,
+        scale: SpectrumScale,
+        reference: f32,
+    ) -> AnalysisConfig {
+        let config = AnalysisConfig {
+            version: ANALYSIS_CONFIG_VERSION,
+            fft_size,
+            hop,
+            window: parse_window(window_type),
+            sample_rate,
+            scale,
+            reference,
+        };
+        config.validate().unwrap_or_else(|err| panic!("{err}"));
+        config
+    }
+
+    /// Read a config written by [`AnalysisConfig::to_json`].
+    ///
+    /// # Errors
+    /// Returns [`DspError::InvalidConfig`] for malformed JSON, missing or
+    /// unknown fields, a version newer than [`ANALYSIS_CONFIG_VERSION`], or
+    /// out-of-range values.
+    pub fn from_json(json: &str) -> Result<AnalysisConfig, DspError> {
+        let config: AnalysisConfig =
+            serde_json::from_str(json).map_err(|err| DspError::InvalidConfig(err.to_string()))?;
+        if config.version > ANALYSIS_CONFIG_VERSION {
+            return Err(DspError::InvalidConfig(format!(
+                "unsupported version {}",
+                config.version
+            )));
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The config as compact JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("config serializes")
+    }
+
+    /// Format version of this config.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Samples per frame.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Samples between frame starts.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Analysis window.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn window(&self) -> WindowType {
+        self.window
+    }
+
+    /// Sample rate of the analyzed audio in Hz.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Quantity and unit of the frame values.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn scale(&self) -> SpectrumScale {
+        self.scale
+    }
+
+    /// Value the frames are relative to.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn reference(&self) -> f32 {
+        self.reference
+    }
+}
+
+impl AnalysisConfig {
+    /// Check that every value is usable for an analysis.
+    fn validate(&self) -> Result<(), DspError> {
+        let invalid = |reason: &str| Err(DspError::InvalidConfig(reason.to_owned()));
+        if self.fft_size < 2 {
+            return invalid("fft_size must be at least 2");
+        }
+        if self.hop == 0 {
+            return invalid("hop must be positive");
+        }
+        if !(self.sample_rate.is_finite() && self.sample_rate > 0.0) {
+            return invalid("sample_rate must be a positive finite number");
+        }
+        if !(self.reference.is_finite() && self.reference > 0.0) {
+            return invalid("reference must be a positive finite number");
+        }
+        Ok(())
+    }
+}
+
+/// A spectrogram together with the config that produced it.
+///
+/// # What
+/// [`TaggedSpectrogram::metadata_json`] describes the frames completely
+/// (config and shape), so an export can store it next to the values and
+/// be re-read without out-of-band parameters.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedSpectrogram {
+    config: AnalysisConfig,
+    spectrogram: Spectrogram,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TaggedSpectrogram {
+    /// The config the frames were computed with.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn config(&self) -> AnalysisConfig {
+        self.config
+    }
+
+    /// Number of frames (rows).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn n_frames(&self) -> usize {
+        self.spectrogram.n_frames()
+    }
+
+    /// Number of bins per frame (columns).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn n_bins(&self) -> usize {
+        self.spectrogram.n_bins()
+    }
+
+    /// Copy of the frame-major values.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn data(&self) -> Vec<f32> {
+        self.spectrogram.data()
+    }
+
+    /// `{"config":{...},"n_frames":..,"n_bins":..}` for this spectrogram.
+    pub fn metadata_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Metadata<'a> {
+            config: &'a AnalysisConfig,
+            n_frames: usize,
+            n_bins: usize,
+        }
+        let metadata = Metadata {
+            config: &self.config,
+            n_frames: self.n_frames(),
+            n_bins: self.n_bins(),
+        };
+        serde_json::to_string(&metadata).expect("metadata serializes")
+    }
+}
+
+impl TaggedSpectrogram {
+    /// The untagged spectrogram.
+    pub fn spectrogram(&self) -> &Spectrogram {
+        &self.spectrogram
+    }
+}
+
+/// Analyze `samples` as described by `config`.
+///
+/// # What
+/// Every complete frame yields the `fft_size / 2 + 1` bins of
+/// `config.scale()` relative to `config.reference()`; trailing samples that
+/// do not fill a frame are ignored. With [`SpectrumScale::AmplitudeDb`]
+/// the values equal [`crate::compute_spectrogram`] up to its dB floor.
+///
+/// # Panics
+/// Panics when `samples` contains non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn analyze_signal(samples: &[f32], config: &AnalysisConfig) -> TaggedSpectrogram {
+    validate_finite(samples);
+    let n_bins = config.fft_size / 2 + 1;
+    let mut data = Vec::new();
+    for mut mags in half_spectrum_frames(samples, config.fft_size, config.hop, config.window) {
+        apply_scale(&mut mags, config.scale, config.reference);
+        data.extend_from_slice(&mags);
+    }
+    TaggedSpectrogram {
+        config: *config,
+        spectrogram: Spectrogram::new(data.len() / n_bins, n_bins, data),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute_spectrogram;
+    use crate::test_support::noise;
+
+    /// The documented JSON form round-trips and rejects bad input.
+    #[test]
+    fn config_round_trips_through_json() {
+        let config = AnalysisConfig::new(
+            2048,
+            512,
+            "blackman-harris",
+            48_000.0,
+            SpectrumScale::PowerDb,
+            1.0,
+        );
+        let json = config.to_json();
+        assert_eq!(
+            json,
+            r#"{"version":1,"fft_size":2048,"hop":512,"window":"blackman-harris","sample_rate":48000.0,"scale":"power_db","reference":1.0}"#
+        );
+        assert_eq!(AnalysisConfig::from_json(&json), Ok(config));
+
+        let newer = json.replace(r#""version":1"#, r#""version":2"#);
+        assert!(AnalysisConfig::from_json(&newer).is_err());
+        let zero_hop = json.replace(r#""hop":512"#, r#""hop":0"#);
+        assert_eq!(
+            AnalysisConfig::from_json(&zero_hop),
+            Err(DspError::InvalidConfig("hop must be positive".into()))
+        );
+        assert!(AnalysisConfig::from_json("{}").is_err());
+    }
+
+    /// Tagged frames match `compute_spectrogram` and carry their config.
+    #[test]
+    fn analyze_signal_tags_frames() {
+        let samples = noise(4096, 9);
+        let config = AnalysisConfig::new(256, 128, "hann", 8000.0, SpectrumScale::AmplitudeDb, 1.0);
+        let tagged = analyze_signal(&samples, &config);
+        let plain = compute_spectrogram(&samples, 256, 128, "hann", 1.0);
+        assert_eq!(tagged.n_frames(), plain.n_frames());
+        assert_eq!(tagged.n_bins(), 129);
+        for (a, b) in tagged.data().iter().zip(plain.values()) {
+            assert!((a - b).abs() < 1e-3);
+        }
+        assert_eq!(tagged.config(), config);
+        assert!(tagged.metadata_json().ends_with(&format!(
+            r#""n_frames":{},"n_bins":129}}"#,
+            plain.n_frames()
+        )));
+    }
+}
@@ -0,0 +1,524 @@
+//! Helpers that prepare spectrogram data for display.
+//!
+//! # What
+//! Statistics and mappings applied to dB matrices right before they are
+//! colorized or uploaded to the GPU.
+//!
+//! # Why
+//! Choosing a color range or quantizing values touches every cell of a
+//! potentially huge matrix, which is cheaper in WASM than in JavaScript.
+
+use crate::{validate_finite, validate_not_nan};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Largest code of an 8-bit quantizer.
+pub(crate) const U8_MAX: f32 = 255.0;
+
+/// Evenly spaced stops of matplotlib's viridis colormap.
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 82, 139],
+    [44, 114, 142],
+    [33, 145, 140],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+
+/// Evenly spaced stops of matplotlib's magma colormap.
+const MAGMA: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [28, 16, 68],
+    [79, 18, 123],
+    [129, 37, 129],
+    [181, 54, 122],
+    [229, 80, 100],
+    [251, 135, 97],
+    [254, 194, 135],
+    [252, 253, 191],
+];
+
+/// Evenly spaced stops of matplotlib's inferno colormap.
+const INFERNO: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [31, 12, 72],
+    [85, 15, 109],
+    [136, 34, 106],
+    [186, 54, 85],
+    [227, 89, 51],
+    [249, 140, 10],
+    [249, 201, 50],
+    [252, 255, 164],
+];
+
+/// Black-to-white ramp.
+const GRAYSCALE: [[u8; 3]; 2] = [[0, 0, 0], [255, 255, 255]];
+
+/// Compute percentiles of `values` with linear interpolation.
+///
+/// # What
+/// Each entry of `percentiles` is clamped to `0..=100` and answered with the
+/// value at that rank, interpolating between neighboring order statistics
+/// (the same definition as NumPy's default). Infinite values, such as the
+/// `-inf` of silent bins, are skipped. Returns one value per requested
+/// percentile, or an empty vector when `values` has no finite entries.
+///
+/// # Panics
+/// Panics when `values` contains NaN or `percentiles` contains non-finite
+/// values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn db_percentiles(values: &[f32], percentiles: &[f32]) -> Vec<f32> {
+    validate_not_nan(values);
+    validate_finite(percentiles);
+    let mut sorted: Vec<f32> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return Vec::new();
+    }
+    sorted.sort_unstable_by(f32::total_cmp);
+    let last = (sorted.len() - 1) as f32;
+    percentiles
+        .iter()
+        .map(|&p| {
+            let rank = p.clamp(0.0, 100.0) / 100.0 * last;
+            let lo = rank.floor() as usize;
+            let hi = rank.ceil() as usize;
+            let frac = rank - lo as f32;
+            sorted[lo] + (sorted[hi] - sorted[lo]) * frac
+        })
+        .collect()
+}
+
+/// Choose a display range from robust percentiles of a dB matrix.
+///
+/// # What
+/// Returns `[min_db, max_db]`: the `low_pct` and `high_pct` percentiles of
+/// the finite values, suitable for passing straight to a colormap. Input
+/// without finite values (empty or entirely silent) yields an empty vector,
+/// leaving the caller's fallback range in place.
+///
+/// # Why
+/// The absolute minimum and maximum are dominated by outliers (silence at
+/// the floor, a single click); percentiles give stable contrast.
+///
+/// # Panics
+/// Panics when `db_values` contains NaN or a percentile is non-finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn auto_contrast(db_values: &[f32], low_pct: f32, high_pct: f32) -> Vec<f32> {
+    db_percentiles(db_values, &[low_pct, high_pct])
+}
+
+/// Position of `db` within `[min_db, max_db]`, clamped to `0..=1`.
+pub(crate) fn normalize_db(db: f32, min_db: f32, max_db: f32) -> f32 {
+    let span = max_db - min_db;
+    if span <= 0.0 {
+        return 0.0;
+    }
+    ((db - min_db) / span).clamp(0.0, 1.0)
+}
+
+/// Compand dB values into 8-bit codes with extra resolution near `max_db`.
+///
+/// # What
+/// Normalizes each value to `x` in `0..=1` over `[min_db, max_db]` and maps
+/// it through `y = 1 - ln(1 + mu * (1 - x)) / ln(1 + mu)`, a mu-law curve
+/// mirrored so that its steep end sits at the loud top of the range. `y` is
+/// quantized to `0..=255`. `mu <= 0` degrades to linear quantization. The
+/// `-inf` of silent bins clamps like any value below `min_db`, to code 0.
+///
+/// # Why
+/// The eye resolves detail in the bright, loud part of a spectrogram far
+/// better than in the noise floor, so codes are better spent there.
+///
+/// # Panics
+/// Panics when `db_values` contains NaN or a parameter is non-finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compand_u8(db_values: &[f32], min_db: f32, max_db: f32, mu: f32) -> Vec<u8> {
+    validate_not_nan(db_values);
+    validate_finite(&[min_db, max_db, mu]);
+    let log_mu = (1.0 + mu.max(0.0)).ln();
+    db_values
+        .iter()
+        .map(|&db| {
+            let x = normalize_db(db, min_db, max_db);
+            let y = if log_mu > 0.0 {
+                1.0 - (1.0 + mu * (1.0 - x)).ln() / log_mu
+            } else {
+                x
+            };
+            (y * U8_MAX).round() as u8
+        })
+        .collect()
+}
+
+/// Decode codes produced by [`compand_u8`] back into dB values.
+///
+/// Uses the same `min_db`, `max_db` and `mu` as the encoder.
+///
+/// # Panics
+/// Panics when any parameter is non-finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn expand_u8(codes: &[u8], min_db: f32, max_db: f32, mu: f32) -> Vec<f32> {
+    validate_finite(&[min_db, max_db, mu]);
+    let mu = mu.max(0.0);
+    let log_mu = (1.0 + mu).ln();
+    codes
+        .iter()
+        .map(|&code| {
+            let y = code as f32 / U8_MAX;
+            let x = if log_mu > 0.0 {
+                1.0 - ((1.0 + mu).powf(1.0 - y) - 1.0) / mu
+            } else {
+                y
+            };
+            min_db + x * (max_db - min_db)
+        })
+        .collect()
+}
+
+/// Map dB values to opaque RGBA pixels through a named colormap.
+///
+/// # What
+/// Each value is placed within `[min_db, max_db]` (clamped) and colored by
+/// linear interpolation between the stops of `colormap`: `"viridis"`,
+/// `"magma"`, `"inferno"` or `"grayscale"`. Returns four bytes per value,
+/// `[r, g, b, 255]`, ready for `ImageData` or a texture upload. The `-inf`
+/// of silent bins takes the lowest stop.
+///
+/// # Why
+/// The canvas fallback and PNG export otherwise color every pixel in
+/// JavaScript.
+///
+/// # Panics
+/// Panics when `db_frame` contains NaN, `min_db` or `max_db` is non-finite,
+/// or `colormap` is unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn colorize(db_frame: &[f32], min_db: f32, max_db: f32, colormap: &str) -> Vec<u8> {
+    validate_not_nan(db_frame);
+    validate_finite(&[min_db, max_db]);
+    let stops: &[[u8; 3]] = match colormap {
+        "viridis" => &VIRIDIS,
+        "magma" => &MAGMA,
+        "inferno" => &INFERNO,
+        "grayscale" => &GRAYSCALE,
+        _ => panic!("unknown colormap: {colormap}"),
+    };
+    let last = stops.len() - 1;
+    let mut rgba = Vec::with_capacity(4 * db_frame.len());
+    for &db in db_frame {
+        let pos = normalize_db(db, min_db, max_db) * last as f32;
+        let i = (pos.floor() as usize).min(last - 1);
+        let frac = pos - i as f32;
+        for (&lo, &hi) in stops[i].iter().zip(&stops[i + 1]) {
+            let (lo, hi) = (lo as f32, hi as f32);
+            rgba.push((lo + (hi - lo) * frac).round() as u8);
+        }
+        rgba.push(u8::MAX);
+    }
+    rgba
+}
+
+/// Evaluate the piecewise-linear curve through `(xs[i], ys[i])` at `x`,
+/// clamping to the end values outside `xs`.
+pub(crate) fn piecewise_linear(xs: &[f32], ys: &[f32], x: f32) -> f32 {
+    let last = xs.len() - 1;
+    if x <= xs[0] {
+        return ys[0];
+    }
+    if x >= xs[last] {
+        return ys[last];
+    }
+    let i = xs.partition_point(|&p| p <= x) - 1;
+    let frac = (x - xs[i]) / (xs[i + 1] - xs[i]);
+    ys[i] + (ys[i + 1] - ys[i]) * frac
+}
+
+/// Value of `values` at fractional index `pos`, by linear interpolation.
+fn sample_linear(values: &[f32], pos: f32) -> f32 {
+    let last = values.len() - 1;
+    let pos = pos.clamp(0.0, last as f32);
+    let i = (pos.floor() as usize).min(last);
+    let next = values[(i + 1).min(last)];
+    values[i] + (next - values[i]) * (pos - i as f32)
+}
+
+/// Resample a spectrum along a piecewise-linear frequency warp.
+///
+/// # What
+/// Positions are normalized so that `0` is the first bin and `1` the last.
+/// The warp maps input position `warp_points_in[i]` to output position
+/// `warp_points_out[i]` and is linear in between (clamped outside). Each of
+/// the `n_out` evenly spaced output positions is mapped back through the
+/// inverse warp and the input is sampled there by linear interpolation.
+/// An identity warp with `n_out == magnitudes.len()` reproduces the input.
+///
+/// # Why
+/// Emphasizing a frequency region on screen (e.g. the speech band) is a
+/// monotonic remapping of the axis; doing it here keeps every renderer
+/// consistent.
+///
+/// # Panics
+/// Panics when any input is non-finite, the control point arrays differ in
+/// length or have fewer than two points, or either array is not strictly
+/// increasing.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn warp_frequency(
+    magnitudes: &[f32],
+    warp_points_in: &[f32],
+    warp_points_out: &[f32],
+    n_out: usize,
+) -> Vec<f32> {
+    validate_finite(magnitudes);
+    validate_finite(warp_points_in);
+    validate_finite(warp_points_out);
+    assert_eq!(
+        warp_points_in.len(),
+        warp_points_out.len(),
+        "warp point arrays must have equal length"
+    );
+    assert!(
+        warp_points_in.len() >= 2,
+        "at least two warp points are required"
+    );
+    let increasing = |p: &[f32]| p.windows(2).all(|w| w[0] < w[1]);
+    assert!(
+        increasing(warp_points_in) && increasing(warp_points_out),
+        "warp points must be strictly increasing"
+    );
+    if magnitudes.is_empty() {
+        return Vec::new();
+    }
+    let last_in = (magnitudes.len() - 1) as f32;
+    let last_out = n_out.saturating_sub(1).max(1) as f32;
+    (0..n_out)
+        .map(|j| {
+            let u = j as f32 / last_out;
+            let x = piecewise_linear(warp_points_out, warp_points_in, u);
+            sample_linear(magnitudes, x * last_in)
+        })
+        .collect()
+}
+
+/// Rebin a linear half spectrum onto `out_bins` log-spaced bands.
+///
+/// # What
+/// `magnitudes` holds the `fft_size/2 + 1` linear magnitudes of one frame.
+/// Band `j` spans `fmin * (fmax / fmin)^(j / out_bins)` up to the next edge.
+/// Every linear bin is treated as a constant power density over its width,
+/// so a band receives the power of the bins it covers weighted by overlap,
+/// and the output is the square root of that power. The squares of the
+/// output therefore sum to the power of the input between `fmin` and
+/// `fmax`; band parts above Nyquist receive nothing.
+///
+/// # Why
+/// The viewer's log axis used to be resampled per pixel in the fragment
+/// shader, which the canvas fallback could not reproduce. Rebinning here
+/// gives both render paths the same data, and preserving energy keeps wide
+/// high-frequency bands from looking quieter than a single linear bin.
+///
+/// # Panics
+/// Panics when `magnitudes` contains non-finite values, `sample_rate` is
+/// not positive, or `fmin` and `fmax` do not satisfy `0 < fmin < fmax`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rebin_log(
+    magnitudes: &[f32],
+    sample_rate: f32,
+    out_bins: usize,
+    fmin: f32,
+    fmax: f32,
+) -> Vec<f32> {
+    validate_finite(magnitudes);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    assert!(
+        fmin.is_finite() && fmax.is_finite() && 0.0 < fmin && fmin < fmax,
+        "frequency range must satisfy 0 < fmin < fmax"
+    );
+    if magnitudes.is_empty() || out_bins == 0 {
+        return Vec::new();
+    }
+    let bin_hz = sample_rate / (2 * magnitudes.len().saturating_sub(1)).max(1) as f32;
+    // Bin `k` covers `[k, k + 1)` in these units.
+    let position = |j: usize| {
+        let hz = fmin * (fmax / fmin).powf(j as f32 / out_bins as f32);
+        hz / bin_hz + 0.5
+    };
+    let mut lo = position(0);
+    (1..=out_bins)
+        .map(|j| {
+            let hi = position(j);
+            let first = lo.floor() as usize;
+            let last = (hi.ceil() as usize).min(magnitudes.len());
+            let power: f32 = (first..last)
+                .map(|k| {
+                    let overlap = hi.min(k as f32 + 1.0) - lo.max(k as f32);
+                    overlap.max(0.0) * magnitudes[k] * magnitudes[k]
+                })
+                .sum();
+            lo = hi;
+            power.sqrt()
+        })
+        .collect()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tolerance for floating point comparisons in tests.
+    const TOLERANCE: f32 = 1e-4;
+
+    /// A uniform ramp from -100 to 0 dB yields percentiles linear in rank.
+    #[test]
+    fn auto_contrast_returns_percentile_range() {
+        // Shuffle the ramp deterministically so sorting is exercised.
+        let values: Vec<f32> = (0..=100).map(|i| -((i * 37 % 101) as f32)).collect();
+        let range = auto_contrast(&values, 5.0, 95.0);
+        assert_eq!(range.len(), 2);
+        assert!((range[0] + 95.0).abs() < TOLERANCE, "{}", range[0]);
+        assert!((range[1] + 5.0).abs() < TOLERANCE, "{}", range[1]);
+    }
+
+    /// Percentiles between samples interpolate linearly.
+    #[test]
+    fn db_percentiles_interpolates() {
+        let result = db_percentiles(&[0.0, 10.0], &[0.0, 25.0, 100.0]);
+        assert_eq!(result, vec![0.0, 2.5, 10.0]);
+    }
+
+    /// Silent `-inf` cells are ignored; all-silent input has no range.
+    #[test]
+    fn auto_contrast_skips_silence() {
+        let silent = f32::NEG_INFINITY;
+        let values = [silent, -80.0, silent, -20.0, silent];
+        assert_eq!(auto_contrast(&values, 0.0, 100.0), vec![-80.0, -20.0]);
+        assert!(auto_contrast(&[silent; 8], 5.0, 95.0).is_empty());
+    }
+
+    /// Companding beats linear quantization in the loud part of the range.
+    #[test]
+    fn compand_round_trip_favors_loud_values() {
+        let (min_db, max_db, mu) = (-120.0, 0.0, 255.0);
+        // Values spread over the top 12 dB.
+        let loud: Vec<f32> = (0..200).map(|i| -12.0 + i as f32 * 0.06).collect();
+        let mean_error = |decoded: Vec<f32>| -> f32 {
+            decoded
+                .iter()
+                .zip(&loud)
+                .map(|(a, b)| (a - b).abs())
+                .sum::<f32>()
+                / loud.len() as f32
+        };
+        let companded = mean_error(expand_u8(
+            &compand_u8(&loud, min_db, max_db, mu),
+            min_db,
+            max_db,
+            mu,
+        ));
+        let linear = mean_error(expand_u8(
+            &compand_u8(&loud, min_db, max_db, 0.0),
+            min_db,
+            max_db,
+            0.0,
+        ));
+        assert!(
+            companded < linear,
+            "companded {companded} vs linear {linear}"
+        );
+
+        // The range end points map to the extreme codes.
+        assert_eq!(
+            compand_u8(&[min_db, max_db], min_db, max_db, mu),
+            vec![0, 255]
+        );
+        assert_eq!(
+            compand_u8(&[f32::NEG_INFINITY], min_db, max_db, mu),
+            vec![0]
+        );
+    }
+
+    /// The identity warp is lossless; a warp can stretch the low region.
+    #[test]
+    fn warp_frequency_identity_and_stretch() {
+        let spectrum: Vec<f32> = (0..9).map(|k| (k * k) as f32).collect();
+        let identity = warp_frequency(&spectrum, &[0.0, 1.0], &[0.0, 1.0], spectrum.len());
+        for (a, b) in identity.iter().zip(&spectrum) {
+            assert!((a - b).abs() < TOLERANCE, "{a} vs {b}");
+        }
+
+        // The lowest quarter of the input fills the first half of the output.
+        let warped = warp_frequency(&spectrum, &[0.0, 0.25, 1.0], &[0.0, 0.5, 1.0], 17);
+        assert_eq!(warped.len(), 17);
+        assert!((warped[8] - spectrum[2]).abs() < TOLERANCE);
+        assert!((warped[4] - spectrum[1]).abs() < TOLERANCE);
+        assert!((warped[2] - 0.5 * (spectrum[0] + spectrum[1])).abs() < TOLERANCE);
+        assert!((warped[16] - spectrum[8]).abs() < TOLERANCE);
+    }
+
+    /// The ends of the range map to the end stops; the middle interpolates.
+    #[test]
+    fn colorize_maps_range_onto_stops() {
+        let frame = [-120.0f32, -100.0, -50.0, 0.0, 20.0];
+        let gray = colorize(&frame, -100.0, 0.0, "grayscale");
+        assert_eq!(gray.len(), 4 * frame.len());
+        assert_eq!(&gray[..4], &[0, 0, 0, 255]);
+        assert_eq!(&gray[8..12], &[128, 128, 128, 255]);
+        assert_eq!(&gray[16..], &[255, 255, 255, 255]);
+
+        let viridis = colorize(&frame, -100.0, 0.0, "viridis");
+        assert_eq!(&viridis[4..7], &VIRIDIS[0]);
+        assert_eq!(&viridis[8..11], &VIRIDIS[4]);
+        assert_eq!(&viridis[12..15], &VIRIDIS[8]);
+        for name in ["magma", "inferno"] {
+            assert_eq!(colorize(&frame, -100.0, 0.0, name).len(), 20);
+        }
+        assert_eq!(
+            colorize(&[f32::NEG_INFINITY], -100.0, 0.0, "viridis")[..3],
+            VIRIDIS[0]
+        );
+    }
+
+    /// NaN cells are still rejected.
+    #[test]
+    #[should_panic(expected = "NaN")]
+    fn colorize_rejects_nan() {
+        colorize(&[0.0, f32::NAN], -100.0, 0.0, "grayscale");
+    }
+
+    /// Rebinning preserves the energy of the covered range, puts a tone in
+    /// the band containing its frequency, and spreads one wide bin over the
+    /// narrow low bands in proportion to their width.
+    #[test]
+    fn rebin_log_preserves_energy() {
+        // 513 bins of a 1024-point FFT at 1024 Hz: 1 Hz per bin.
+        let mut magnitudes = vec![0.5; 513];
+        magnitudes[300] = 4.0;
+        let bands = rebin_log(&magnitudes, 1024.0, 40, 20.5, 500.5);
+        let energy: f32 = bands.iter().map(|b| b * b).sum();
+        let expected: f32 = magnitudes[21..=500].iter().map(|m| m * m).sum();
+        assert!(
+            (energy - expected).abs() < 1e-2 * expected,
+            "{energy} vs {expected}"
+        );
+        let edge = |j: f32| 20.5 * (500.5f32 / 20.5).powf(j / 40.0);
+        let band = (0..40).position(|j| edge(j as f32) <= 300.0 && 300.0 < edge(j as f32 + 1.0));
+        let loudest = (0..40).max_by(|&a, &b| bands[a].total_cmp(&bands[b]));
+        assert_eq!(loudest, band);
+
+        let flat = rebin_log(&[1.0, 1.0], 2.0, 4, 0.1, 0.5);
+        let widths: Vec<f32> = (0..4)
+            .map(|j| 0.1 * 5f32.powf((j + 1) as f32 / 4.0) - 0.1 * 5f32.powf(j as f32 / 4.0))
+            .collect();
+        for (b, w) in flat.iter().zip(&widths) {
+            assert!((b * b - w).abs() < TOLERANCE, "{b} vs {w}");
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Error type shared by the checked entry points of the crate.
+//!
+//! # Why
+//! The plain APIs panic on invalid input, which aborts the WASM call.
+//! Callers that prefer recoverable failures use the `*_checked` and `try_*`
+//! variants, which report problems through [`DspError`] instead. On the JS
+//! side the error surfaces as a thrown `Error` carrying the display message
+//! (with the `wasm` feature).
+
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Reasons a checked DSP entry point can reject its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DspError {
+    /// The window name does not match any supported window function.
+    UnknownWindow(String),
+    /// The input holds a NaN or infinity at `index`.
+    NonFinite { index: usize },
+    /// A frame or block had no samples.
+    EmptyInput,
+    /// A buffer's length differs from the length the call was set up for.
+    LengthMismatch { expected: usize, actual: usize },
+    /// A shape parameter is out of range; the message says which and why.
+    InvalidParameter(&'static str),
+    /// A serialized configuration could not be read; the message says why.
+    InvalidConfig(String),
+}
+
+impl fmt::Display for DspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DspError::UnknownWindow(name) => write!(f, "unknown window type: {name}"),
+            DspError::NonFinite { index } => {
+                write!(f, "input contains a non-finite value at index {index}")
+            }
+            DspError::EmptyInput => write!(f, "input must not be empty"),
+            DspError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {expected} samples, got {actual}")
+            }
+            DspError::InvalidParameter(reason) => write!(f, "{reason}"),
+            DspError::InvalidConfig(reason) => write!(f, "invalid analysis config: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DspError {}
+
+#[cfg(feature = "wasm")]
+impl From<DspError> for JsValue {
+    fn from(err: DspError) -> Self {
+        JsError::new(&err.to_string()).into()
+    }
+}
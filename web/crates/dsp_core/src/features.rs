@@ -0,0 +1,378 @@
+//! Scalar descriptors computed from magnitude spectra.
+//!
+//! # What
+//! Timbre and signal-character features (slope, shape statistics) derived
+//! from linear magnitude spectra such as those returned by
+//! [`crate::envelope_spectrum`] or the magnitude helpers, descriptors
+//! computed straight from a time-domain frame, and the per-frame
+//! normalizations applied before using spectra as features.
+//!
+//! # Why
+//! Features are tiny compared with the spectra they summarize; computing
+//! them in WASM avoids shipping whole frames to JavaScript.
+
+use crate::{
+    half_spectrum_magnitudes, validate_finite, window_samples, WindowType, DB_SCALE, EPSILON,
+};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Share of spectral energy below the rolloff frequency.
+const ROLLOFF_FRACTION: f64 = 0.85;
+
+/// Spectral slope: least-squares fit of level in dB against frequency.
+///
+/// # What
+/// `magnitudes` holds linear magnitudes of bins `0..magnitudes.len()` of an
+/// `fft_size`-point FFT, so bin `k` lies at `k * sample_rate / fft_size` Hz.
+/// Returns the fitted slope in dB per Hz. Silent spectra (all magnitudes
+/// at or below the dB floor) and spectra with fewer than two bins return `0`.
+///
+/// # Why
+/// The tilt of the spectrum separates dark, mellow timbres (steep negative
+/// slope) from bright ones and is a standard timbre descriptor.
+///
+/// # Panics
+/// Panics when `magnitudes` contains non-finite values or `sample_rate` or
+/// `fft_size` is not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spectral_slope(magnitudes: &[f32], sample_rate: f32, fft_size: usize) -> f32 {
+    validate_finite(magnitudes);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    assert!(fft_size > 0, "fft_size must be positive");
+    let n = magnitudes.len();
+    if n < 2 || magnitudes.iter().all(|&m| m <= EPSILON) {
+        return 0.0;
+    }
+
+    let bin_hz = sample_rate as f64 / fft_size as f64;
+    let mut sum_x = 0.0f64;
+    let mut sum_y = 0.0f64;
+    let mut sum_xx = 0.0f64;
+    let mut sum_xy = 0.0f64;
+    for (k, &m) in magnitudes.iter().enumerate() {
+        let x = k as f64 * bin_hz;
+        let y = (DB_SCALE * m.max(EPSILON).log10()) as f64;
+        sum_x += x;
+        sum_y += y;
+        sum_xx += x * x;
+        sum_xy += x * y;
+    }
+    let count = n as f64;
+    let denom = count * sum_xx - sum_x * sum_x;
+    if denom <= 0.0 {
+        return 0.0;
+    }
+    ((count * sum_xy - sum_x * sum_y) / denom) as f32
+}
+
+/// Scale a magnitude frame to unit L1 norm, unit L2 norm, or unit maximum.
+///
+/// # What
+/// `norm` selects the divisor: `"l1"` the sum of absolute values, `"l2"`
+/// the Euclidean norm, `"max"` the largest absolute value. An all-zero
+/// frame is returned as zeros rather than NaN.
+///
+/// # Why
+/// Chroma and other distribution-like features compare spectral shape, not
+/// level; normalizing makes frames of different loudness comparable.
+///
+/// # Panics
+/// Panics when `magnitudes` contains non-finite values or `norm` is not one
+/// of the names above.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn normalize_frame(magnitudes: &[f32], norm: &str) -> Vec<f32> {
+    validate_finite(magnitudes);
+    let divisor = match norm {
+        "l1" => magnitudes.iter().map(|m| m.abs()).sum::<f32>(),
+        "l2" => magnitudes.iter().map(|m| m * m).sum::<f32>().sqrt(),
+        "max" => magnitudes.iter().fold(0.0f32, |a, m| a.max(m.abs())),
+        _ => panic!("unknown norm: {norm}"),
+    };
+    if divisor <= 0.0 {
+        return vec![0.0; magnitudes.len()];
+    }
+    magnitudes.iter().map(|m| m / divisor).collect()
+}
+
+/// Shape descriptors of one magnitude spectrum.
+///
+/// # What
+/// Returned by [`spectral_features_from_magnitudes`]. Frequencies are in Hz;
+/// `flatness` is unitless in `0..=1`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectralFeatures {
+    centroid: f32,
+    spread: f32,
+    rolloff: f32,
+    flatness: f32,
+    bandwidth: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SpectralFeatures {
+    /// Magnitude-weighted mean frequency.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn centroid(&self) -> f32 {
+        self.centroid
+    }
+
+    /// Power-weighted standard deviation around the power-weighted mean.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn spread(&self) -> f32 {
+        self.spread
+    }
+
+    /// Lowest bin frequency below which [`ROLLOFF_FRACTION`] of the power
+    /// lies.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn rolloff(&self) -> f32 {
+        self.rolloff
+    }
+
+    /// Geometric over arithmetic mean of the power, `1` for white noise and
+    /// near `0` for a tone.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn flatness(&self) -> f32 {
+        self.flatness
+    }
+
+    /// Magnitude-weighted standard deviation around the centroid.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn bandwidth(&self) -> f32 {
+        self.bandwidth
+    }
+}
+
+impl SpectralFeatures {
+    /// Features of linear magnitudes `mags`, bin `k` lying at `k * bin_hz`.
+    /// Silent spectra give all zeros.
+    fn of(mags: &[f32], bin_hz: f64) -> SpectralFeatures {
+        let freq = |k: usize| k as f64 * bin_hz;
+        let total: f64 = mags.iter().map(|&m| m as f64).sum();
+        let energy: f64 = mags.iter().map(|&m| (m as f64) * (m as f64)).sum();
+        if total <= EPSILON as f64 {
+            return SpectralFeatures {
+                centroid: 0.0,
+                spread: 0.0,
+                rolloff: 0.0,
+                flatness: 0.0,
+                bandwidth: 0.0,
+            };
+        }
+        let weighted_spread = |weight: &dyn Fn(f32) -> f64, sum: f64| {
+            let mean = mags
+                .iter()
+                .enumerate()
+                .map(|(k, &m)| freq(k) * weight(m))
+                .sum::<f64>()
+                / sum;
+            let variance = mags
+                .iter()
+                .enumerate()
+                .map(|(k, &m)| (freq(k) - mean).powi(2) * weight(m))
+                .sum::<f64>()
+                / sum;
+            (mean, variance.sqrt())
+        };
+        let (centroid, bandwidth) = weighted_spread(&|m| m as f64, total);
+        let (_, spread) = weighted_spread(&|m| (m as f64) * (m as f64), energy);
+
+        let mut cumulative = 0.0;
+        let rolloff_bin = mags
+            .iter()
+            .position(|&m| {
+                cumulative += (m as f64) * (m as f64);
+                cumulative >= ROLLOFF_FRACTION * energy
+            })
+            .unwrap_or(mags.len() - 1);
+
+        let floor = (EPSILON as f64) * (EPSILON as f64);
+        let log_mean = mags
+            .iter()
+            .map(|&m| ((m as f64) * (m as f64)).max(floor).ln())
+            .sum::<f64>()
+            / mags.len() as f64;
+        let flatness = log_mean.exp() / (energy / mags.len() as f64);
+
+        SpectralFeatures {
+            centroid: centroid as f32,
+            spread: spread as f32,
+            rolloff: freq(rolloff_bin) as f32,
+            flatness: flatness as f32,
+            bandwidth: bandwidth as f32,
+        }
+    }
+}
+
+/// Centroid, rolloff, flatness and bandwidth of one frame.
+///
+/// # What
+/// Hann-windows `input`, takes the magnitudes of bins `0..=n/2` and
+/// returns `[centroid_hz, rolloff_hz, flatness, bandwidth_hz]` as defined
+/// on [`SpectralFeatures`]. Silent frames return four zeros; empty input
+/// yields an empty result.
+///
+/// # Why
+/// These four descriptors drive most timbre and genre demos, and computing
+/// them here avoids shipping every frame's magnitudes to JavaScript.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `sample_rate` is not
+/// positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spectral_features(input: &[f32], sample_rate: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let mags = half_spectrum_magnitudes(&window_samples(input, WindowType::Hann));
+    let f = SpectralFeatures::of(&mags, sample_rate as f64 / input.len() as f64);
+    vec![f.centroid, f.rolloff, f.flatness, f.bandwidth]
+}
+
+/// [`SpectralFeatures`] of an existing half spectrum.
+///
+/// # What
+/// `magnitudes` holds the linear magnitudes of bins `0..=fft_size/2` of an
+/// even `fft_size`-point FFT (the first half of
+/// [`crate::magnitude_linear`]), so bin `k` lies at
+/// `k * sample_rate / (2 * (len - 1))` Hz. Silent or single-bin spectra
+/// give all zeros.
+///
+/// # Why
+/// A viewer that already has magnitudes for display should not recompute
+/// the FFT, nor derive the features in JavaScript.
+///
+/// # Panics
+/// Panics when `magnitudes` contains non-finite values or `sample_rate` is
+/// not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spectral_features_from_magnitudes(magnitudes: &[f32], sample_rate: f32) -> SpectralFeatures {
+    validate_finite(magnitudes);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    let fft_size = 2 * magnitudes.len().saturating_sub(1);
+    if fft_size == 0 {
+        return SpectralFeatures::of(&[], 0.0);
+    }
+    SpectralFeatures::of(magnitudes, sample_rate as f64 / fft_size as f64)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tolerance for floating point comparisons in tests.
+    const TOLERANCE: f32 = 1e-5;
+
+    /// Falling spectra have negative slope; flat and silent ones have none.
+    #[test]
+    fn spectral_slope_sign_follows_tilt() {
+        let rate = 8000.0;
+        let fft = 64;
+        // -6 dB per octave style roll-off.
+        let falling: Vec<f32> = (0..=fft / 2).map(|k| 1.0 / (1.0 + k as f32)).collect();
+        assert!(spectral_slope(&falling, rate, fft) < 0.0);
+
+        let flat = vec![0.5f32; fft / 2 + 1];
+        assert!(spectral_slope(&flat, rate, fft).abs() < TOLERANCE);
+
+        let silent = vec![0.0f32; fft / 2 + 1];
+        assert_eq!(spectral_slope(&silent, rate, fft), 0.0);
+    }
+
+    /// A spectrum falling 1 dB per bin has a slope of -1 dB per bin width.
+    #[test]
+    fn spectral_slope_is_in_db_per_hz() {
+        let (rate, fft) = (1000.0, 100);
+        let mags: Vec<f32> = (0..20).map(|k| 10f32.powf(-(k as f32) / 20.0)).collect();
+        let bin_hz = rate / fft as f32;
+        let slope = spectral_slope(&mags, rate, fft);
+        assert!((slope + 1.0 / bin_hz).abs() < 1e-3, "{slope}");
+    }
+
+    /// Each norm reaches one on its own measure; zero frames stay zero.
+    #[test]
+    fn normalize_frame_applies_each_norm() {
+        let frame = [3.0f32, 4.0, 0.0, 1.0];
+        let l1: f32 = normalize_frame(&frame, "l1").iter().sum();
+        assert!((l1 - 1.0).abs() < TOLERANCE);
+        let l2: f32 = normalize_frame(&frame, "l2").iter().map(|v| v * v).sum();
+        assert!((l2 - 1.0).abs() < TOLERANCE);
+        let max = normalize_frame(&frame, "max");
+        assert_eq!(max[1], 1.0);
+        assert!((max[0] - 0.75).abs() < TOLERANCE);
+
+        for norm in ["l1", "l2", "max"] {
+            assert_eq!(normalize_frame(&[0.0; 4], norm), vec![0.0; 4]);
+        }
+    }
+
+    /// A tone's centroid sits at its frequency; noise is flatter than a tone.
+    #[test]
+    fn spectral_features_separate_tone_and_noise() {
+        let (rate, n, tone_hz) = (8000.0f32, 1024, 1000.0f32);
+        let tone: Vec<f32> = (0..n)
+            .map(|i| (crate::TWO_PI * tone_hz * i as f32 / rate).sin())
+            .collect();
+        let tone_features = spectral_features(&tone, rate);
+        assert_eq!(tone_features.len(), 4);
+        assert!(
+            (tone_features[0] - tone_hz).abs() < 50.0,
+            "{tone_features:?}"
+        );
+        assert!(
+            (tone_features[1] - tone_hz).abs() < 50.0,
+            "{tone_features:?}"
+        );
+
+        let noise_features = spectral_features(&crate::test_support::noise(n, 9), rate);
+        assert!(noise_features[2] > 0.3, "{noise_features:?}");
+        assert!(noise_features[2] > 10.0 * tone_features[2]);
+        assert!(noise_features[3] > tone_features[3]);
+
+        assert_eq!(spectral_features(&vec![0.0; n], rate), vec![0.0; 4]);
+    }
+
+    /// Features from magnitudes match those of the frame they came from.
+    #[test]
+    fn features_from_magnitudes_match_frame_features() {
+        let (rate, n) = (8000.0f32, 512);
+        let frame = crate::test_support::noise(n, 4);
+        let mags = half_spectrum_magnitudes(&window_samples(&frame, WindowType::Hann));
+        let features = spectral_features_from_magnitudes(&mags, rate);
+        let expected = spectral_features(&frame, rate);
+        let actual = [
+            features.centroid(),
+            features.rolloff(),
+            features.flatness(),
+            features.bandwidth(),
+        ];
+        for (a, e) in actual.iter().zip(&expected) {
+            assert!((a - e).abs() <= 1e-4 * e.abs().max(1.0), "{a} vs {e}");
+        }
+        assert!(features.spread() > 0.0);
+
+        let tone: Vec<f32> = (0..=n / 2)
+            .map(|k| if k == 64 { 1.0 } else { 0.0 })
+            .collect();
+        let features = spectral_features_from_magnitudes(&tone, rate);
+        assert_eq!(features.centroid(), 64.0 * rate / n as f32);
+        assert_eq!(features.spread(), 0.0);
+    }
+}
@@ -0,0 +1,801 @@
+//! Time-domain filtering helpers shared by the analysis pipelines.
+//!
+//! # What
+//! FIR filter design and application used ahead of the FFT, e.g. for band
+//! splitting and decimation.
+//!
+//! # Why
+//! Filtering before analysis keeps the spectra of decimated or band-split
+//! signals free of aliasing without a JS filtering dependency.
+
+use crate::{plan_forward, plan_inverse, validate_finite, window_samples, WindowType, TWO_PI};
+use rustfft::{num_complex::Complex32, Fft};
+use std::sync::Arc;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Target ratio of FFT block size to impulse-response length for
+/// overlap-save convolution. Larger blocks amortize the FFT over more
+/// output samples; beyond about 4x the gains flatten while latency and
+/// memory keep growing.
+const CONV_BLOCK_RATIO: usize = 4;
+
+/// Zero crossings of the resampling sinc kept on each side of its center.
+/// More crossings give a steeper anti-aliasing transition.
+const RESAMPLER_ZERO_CROSSINGS: f64 = 16.0;
+
+/// Fractional positions between input samples at which the resampling
+/// kernel is tabulated; positions in between are linearly interpolated.
+const RESAMPLER_PHASES: usize = 256;
+
+/// Corner frequency of the [`Preprocessor`] DC blocker in Hz: low enough to
+/// leave audible bass alone while settling within a fraction of a second.
+const DC_BLOCK_CUTOFF_HZ: f32 = 10.0;
+
+/// Quality factor of the [`Preprocessor`] high-pass: Butterworth, for the
+/// flattest passband.
+const HIGHPASS_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Design a linear-phase windowed-sinc low-pass FIR.
+///
+/// # What
+/// Returns `len` taps (forced odd so the filter has an integer group delay
+/// of `len / 2`) with cut-off `cutoff` expressed as a fraction of the sample
+/// rate (`0.0..0.5`). The taps are Blackman-windowed and normalized to unity
+/// DC gain.
+pub(crate) fn lowpass_taps(cutoff: f32, len: usize) -> Vec<f32> {
+    let len = len | 1;
+    let center = (len / 2) as f32;
+    let cutoff = cutoff.clamp(0.0, 0.5);
+    let ideal: Vec<f32> = (0..len)
+        .map(|i| {
+            let x = i as f32 - center;
+            if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (TWO_PI * cutoff * x).sin() / (std::f32::consts::PI * x)
+            }
+        })
+        .collect();
+    let mut taps = window_samples(&ideal, WindowType::Blackman);
+    let sum: f32 = taps.iter().sum();
+    if sum != 0.0 {
+        for t in &mut taps {
+            *t /= sum;
+        }
+    }
+    taps
+}
+
+/// Turn low-pass `taps` into the complementary high-pass by spectral
+/// inversion (`delta[n - center] - h[n]`).
+pub(crate) fn complementary_highpass(taps: &[f32]) -> Vec<f32> {
+    let center = taps.len() / 2;
+    taps.iter()
+        .enumerate()
+        .map(|(i, &t)| if i == center { 1.0 - t } else { -t })
+        .collect()
+}
+
+/// Convolve `input` with odd-length `taps`, compensating the group delay.
+///
+/// # What
+/// Output sample `n` is aligned with input sample `n`, so the result has
+/// the same length as `input`. Samples beyond the edges are taken as zero.
+pub(crate) fn fir_filter_centered(input: &[f32], taps: &[f32]) -> Vec<f32> {
+    let n = input.len();
+    let half = taps.len() / 2;
+    (0..n)
+        .map(|i| {
+            let mut acc = 0.0f32;
+            for (k, &t) in taps.iter().enumerate() {
+                // Tap `k` multiplies input sample `i + half - k`.
+                let j = i + half;
+                if j >= k && j - k < n {
+                    acc += t * input[j - k];
+                }
+            }
+            acc
+        })
+        .collect()
+}
+
+/// Second-order IIR section in transposed direct form II.
+///
+/// # Why
+/// Biquads are the standard building block for notch, shelving and
+/// high-pass stages; the transposed form has good numerical behavior in
+/// `f32`.
+#[derive(Clone, Debug)]
+pub(crate) struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    /// Build a section from coefficients normalized so that `a0 == 1`.
+    fn normalized(b: [f32; 3], a: [f32; 3]) -> Biquad {
+        let inv = 1.0 / a[0];
+        Biquad {
+            b0: b[0] * inv,
+            b1: b[1] * inv,
+            b2: b[2] * inv,
+            a1: a[1] * inv,
+            a2: a[2] * inv,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Notch at `freq_hz` with quality factor `q` (RBJ audio EQ cookbook).
+    pub(crate) fn notch(freq_hz: f32, sample_rate: f32, q: f32) -> Biquad {
+        let omega = TWO_PI * freq_hz / sample_rate;
+        let alpha = omega.sin() / (2.0 * q);
+        let cos = omega.cos();
+        Biquad::normalized(
+            [1.0, -2.0 * cos, 1.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// High-pass at `freq_hz` with quality factor `q` (RBJ audio EQ
+    /// cookbook).
+    pub(crate) fn highpass(freq_hz: f32, sample_rate: f32, q: f32) -> Biquad {
+        let omega = TWO_PI * freq_hz / sample_rate;
+        let alpha = omega.sin() / (2.0 * q);
+        let cos = omega.cos();
+        Biquad::normalized(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// The two-stage K-weighting filter of ITU-R BS.1770 at `sample_rate`:
+    /// a +4 dB high shelf modelling the head, then a 38 Hz high-pass.
+    ///
+    /// # How
+    /// The standard specifies coefficients at 48 kHz only; these are the
+    /// analog prototypes behind them, bilinear-transformed for any rate.
+    pub(crate) fn k_weighting(sample_rate: f32) -> [Biquad; 2] {
+        let fs = sample_rate as f64;
+        let pi = std::f64::consts::PI;
+        let (f0, gain_db, q) = (
+            1_681.974_450_955_533,
+            3.999_843_853_973_347,
+            0.707_175_236_955_419_6,
+        );
+        let k = (pi * f0 / fs).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let shelf = Biquad::normalized(
+            [
+                vh + vb * k / q + k * k,
+                2.0 * (k * k - vh),
+                vh - vb * k / q + k * k,
+            ]
+            .map(|c| c as f32),
+            [
+                1.0 + k / q + k * k,
+                2.0 * (k * k - 1.0),
+                1.0 - k / q + k * k,
+            ]
+            .map(|c| c as f32),
+        );
+        let (f0, q) = (38.135_470_876_024_44, 0.500_327_037_323_877_3);
+        let k = (pi * f0 / fs).tan();
+        // BS.1770 keeps the numerator at [1, -2, 1] after normalizing `a`.
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Biquad::normalized(
+            [a0, -2.0 * a0, a0].map(|c| c as f32),
+            [a0, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k].map(|c| c as f32),
+        );
+        [shelf, highpass]
+    }
+
+    /// Filter a single sample.
+    pub(crate) fn process_sample(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+
+    /// Clear the filter state.
+    pub(crate) fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// Comb of notch filters removing mains hum and its harmonics.
+///
+/// # What
+/// Cascades one notch biquad at each of the first `n_harmonics` multiples of
+/// `fundamental_hz` that lie below Nyquist. Filter state is kept between
+/// [`HumRemover::process`] calls, so blocks can be streamed.
+///
+/// # Why
+/// Mains interference (50/60 Hz) and its harmonics draw bright horizontal
+/// lines across spectrograms of low-level recordings.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct HumRemover {
+    notches: Vec<Biquad>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl HumRemover {
+    /// Create a hum remover. `q` sets the notch quality factor: higher values
+    /// give narrower notches that settle more slowly.
+    ///
+    /// # Panics
+    /// Panics when `fundamental_hz`, `sample_rate` or `q` is not a positive
+    /// finite number.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(fundamental_hz: f32, sample_rate: f32, n_harmonics: usize, q: f32) -> HumRemover {
+        for (name, value) in [
+            ("fundamental_hz", fundamental_hz),
+            ("sample_rate", sample_rate),
+            ("q", q),
+        ] {
+            assert!(
+                value.is_finite() && value > 0.0,
+                "{name} must be a positive finite number"
+            );
+        }
+        let nyquist = sample_rate / 2.0;
+        let notches = (1..=n_harmonics)
+            .map(|h| h as f32 * fundamental_hz)
+            .take_while(|&f| f < nyquist)
+            .map(|f| Biquad::notch(f, sample_rate, q))
+            .collect();
+        HumRemover { notches }
+    }
+
+    /// Filter `block`, continuing from the previous call's state.
+    ///
+    /// # Panics
+    /// Panics when `block` contains non-finite values.
+    pub fn process(&mut self, block: &[f32]) -> Vec<f32> {
+        validate_finite(block);
+        block
+            .iter()
+            .map(|&x| {
+                self.notches
+                    .iter_mut()
+                    .fold(x, |acc, notch| notch.process_sample(acc))
+            })
+            .collect()
+    }
+
+    /// Clear the state of every notch.
+    pub fn reset(&mut self) {
+        self.notches.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+/// Optional cleanup applied to audio before analysis.
+///
+/// # What
+/// Runs, in order and each only when enabled, a one-pole DC blocker with a
+/// corner at [`DC_BLOCK_CUTOFF_HZ`], a Butterworth high-pass at
+/// `highpass_hz`, and pre-emphasis `y[n] = x[n] - pre_emphasis * x[n - 1]`.
+/// A `highpass_hz` or `pre_emphasis` of zero disables that stage. State is
+/// kept between [`Preprocessor::process`] calls, so blocks can be streamed.
+///
+/// # Why
+/// Rumble, handling noise and DC offsets carry most of the energy of many
+/// speech and field recordings and dominate the color scale; pre-emphasis
+/// additionally lifts the weak upper formants of speech.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct Preprocessor {
+    /// Pole of the DC blocker; `None` when disabled.
+    dc_pole: Option<f32>,
+    /// Previous input and output of the DC blocker.
+    dc_state: (f32, f32),
+    highpass: Option<Biquad>,
+    pre_emphasis: f32,
+    /// Previous input of the pre-emphasis stage.
+    previous: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Preprocessor {
+    /// Create a preprocessor for audio at `sample_rate` Hz.
+    ///
+    /// # Panics
+    /// Panics when `sample_rate` is not a positive finite number,
+    /// `pre_emphasis` lies outside `[0, 1)`, or `highpass_hz` is negative
+    /// or not below Nyquist.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        sample_rate: f32,
+        dc_block: bool,
+        pre_emphasis: f32,
+        highpass_hz: f32,
+    ) -> Preprocessor {
+        assert!(
+            sample_rate.is_finite() && sample_rate > 0.0,
+            "sample_rate must be a positive finite number"
+        );
+        assert!(
+            (0.0..1.0).contains(&pre_emphasis),
+            "pre_emphasis must lie in [0, 1)"
+        );
+        assert!(
+            (0.0..sample_rate / 2.0).contains(&highpass_hz),
+            "highpass_hz must lie in [0, sample_rate / 2)"
+        );
+        Preprocessor {
+            dc_pole: dc_block.then(|| (-TWO_PI * DC_BLOCK_CUTOFF_HZ / sample_rate).exp()),
+            dc_state: (0.0, 0.0),
+            highpass: (highpass_hz > 0.0)
+                .then(|| Biquad::highpass(highpass_hz, sample_rate, HIGHPASS_Q)),
+            pre_emphasis,
+            previous: 0.0,
+        }
+    }
+
+    /// Filter `block`, continuing from the previous call's state.
+    ///
+    /// # Panics
+    /// Panics when `block` contains non-finite values.
+    pub fn process(&mut self, block: &[f32]) -> Vec<f32> {
+        validate_finite(block);
+        let mut output = block.to_vec();
+        self.process_in_place(&mut output);
+        output
+    }
+
+    /// Clear the state of every stage.
+    pub fn reset(&mut self) {
+        self.dc_state = (0.0, 0.0);
+        if let Some(highpass) = &mut self.highpass {
+            highpass.reset();
+        }
+        self.previous = 0.0;
+    }
+}
+
+impl Preprocessor {
+    /// Filter already validated `samples` in place.
+    pub(crate) fn process_in_place(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            let mut x = *sample;
+            if let Some(pole) = self.dc_pole {
+                let (x1, y1) = self.dc_state;
+                let y = x - x1 + pole * y1;
+                self.dc_state = (x, y);
+                x = y;
+            }
+            if let Some(highpass) = &mut self.highpass {
+                x = highpass.process_sample(x);
+            }
+            *sample = x - self.pre_emphasis * self.previous;
+            self.previous = x;
+        }
+    }
+}
+
+/// Pick an efficient overlap-save FFT block size for a filter of `ir_len` taps.
+///
+/// # What
+/// Returns the next power of two at or above `4 * ir_len` (and at least 1).
+/// Powers of two are the fastest sizes for `rustfft`, and the result always
+/// exceeds the filter length, so every block yields new output samples.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn optimal_conv_block_size(ir_len: usize) -> usize {
+    (CONV_BLOCK_RATIO * ir_len.max(1)).next_power_of_two()
+}
+
+/// Streaming FIR filter evaluated with FFT overlap-save convolution.
+///
+/// # What
+/// Filters successive chunks with a fixed impulse response, carrying the
+/// last `taps.len() - 1` input samples between calls. Each call returns
+/// exactly as many samples as it was given, with no added latency.
+///
+/// # Why
+/// Direct convolution costs `O(taps)` per sample, which is prohibitive for
+/// long responses such as room corrections or steep linear-phase filters.
+///
+/// # How
+/// Each FFT block holds the history followed by up to
+/// `block_size - taps.len() + 1` new samples (zero-padded when the chunk is
+/// short). After multiplying by the precomputed filter spectrum and inverse
+/// transforming, the outputs past the history are free of circular
+/// wrap-around and are emitted.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FirConvolver {
+    block_size: usize,
+    filter_spectrum: Vec<Complex32>,
+    history: Vec<f32>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    buffer: Vec<Complex32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FirConvolver {
+    /// Create a convolver for `taps`.
+    ///
+    /// `block_size == 0` selects [`optimal_conv_block_size`]; explicit sizes
+    /// are raised to at least `taps.len()` so each block makes progress.
+    ///
+    /// # Panics
+    /// Panics when `taps` is empty or contains non-finite values.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(taps: &[f32], block_size: usize) -> FirConvolver {
+        assert!(!taps.is_empty(), "taps must not be empty");
+        validate_finite(taps);
+        let block_size = if block_size == 0 {
+            optimal_conv_block_size(taps.len())
+        } else {
+            block_size.max(taps.len())
+        };
+
+        let forward = plan_forward(block_size);
+        let inverse = plan_inverse(block_size);
+        let mut filter_spectrum = vec![Complex32::new(0.0, 0.0); block_size];
+        for (dst, &t) in filter_spectrum.iter_mut().zip(taps) {
+            dst.re = t;
+        }
+        forward.process(&mut filter_spectrum);
+        // Fold the inverse FFT normalization into the filter once.
+        let scale = 1.0 / block_size as f32;
+        for c in &mut filter_spectrum {
+            *c *= scale;
+        }
+
+        FirConvolver {
+            block_size,
+            filter_spectrum,
+            history: vec![0.0; taps.len() - 1],
+            forward,
+            inverse,
+            buffer: vec![Complex32::new(0.0, 0.0); block_size],
+        }
+    }
+
+    /// FFT block size in use.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Filter `input`, continuing from the state left by previous calls.
+    ///
+    /// # Panics
+    /// Panics when `input` contains non-finite values.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        validate_finite(input);
+        let hist = self.history.len();
+        let step = self.block_size - hist;
+        let mut output = Vec::with_capacity(input.len());
+        for chunk in input.chunks(step) {
+            for (dst, &x) in self.buffer.iter_mut().zip(self.history.iter().chain(chunk)) {
+                *dst = Complex32::new(x, 0.0);
+            }
+            for dst in &mut self.buffer[hist + chunk.len()..] {
+                *dst = Complex32::new(0.0, 0.0);
+            }
+            self.forward.process(&mut self.buffer);
+            for (b, &h) in self.buffer.iter_mut().zip(&self.filter_spectrum) {
+                *b *= h;
+            }
+            self.inverse.process(&mut self.buffer);
+            output.extend(self.buffer[hist..hist + chunk.len()].iter().map(|c| c.re));
+
+            // The newest `hist` samples of history + chunk become the history.
+            if hist > 0 {
+                if chunk.len() >= hist {
+                    self.history.copy_from_slice(&chunk[chunk.len() - hist..]);
+                } else {
+                    self.history.rotate_left(chunk.len());
+                    self.history[hist - chunk.len()..].copy_from_slice(chunk);
+                }
+            }
+        }
+        output
+    }
+
+    /// Clear the carried input history.
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|v| *v = 0.0);
+    }
+}
+
+/// Streaming windowed-sinc sample-rate converter.
+///
+/// # What
+/// Converts successive chunks from `input_rate` to `output_rate` for any
+/// ratio. Output sample `j` is the band-limited input evaluated at time
+/// `j / output_rate`, low-pass filtered below the lower of the two Nyquist
+/// frequencies. Each output waits until the input extends
+/// [`RESAMPLER_ZERO_CROSSINGS`] zero crossings past it, so early calls
+/// return fewer samples than the ratio suggests and the remainder follows
+/// with later chunks.
+///
+/// # Why
+/// Microphones and decoded files rarely run at the analysis sample rate;
+/// resampling in WASM before the STFT avoids a JavaScript dependency.
+///
+/// # How
+/// A Blackman-windowed sinc is tabulated at [`RESAMPLER_PHASES`] + 1
+/// fractional offsets (a polyphase bank), each row normalized to unity DC
+/// gain. Each output interpolates between the two rows around its
+/// fractional input position and takes the dot product with the buffered
+/// input.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Resampler {
+    /// Input samples advanced per output sample.
+    step: f64,
+    /// Kernel taps per side; each row holds `2 * half` taps.
+    half: usize,
+    phases: Vec<Vec<f32>>,
+    /// Buffered input, preceded by `half - 1` zeros at stream start.
+    buffer: Vec<f32>,
+    /// Buffered samples dropped so far.
+    dropped: usize,
+    /// Outputs emitted so far; the next one lies at `produced * step`.
+    produced: u64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Resampler {
+    /// Create a converter from `input_rate` to `output_rate` Hz.
+    ///
+    /// # Panics
+    /// Panics when either rate is not a positive finite number.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(input_rate: f32, output_rate: f32) -> Resampler {
+        for (name, value) in [("input_rate", input_rate), ("output_rate", output_rate)] {
+            assert!(
+                value.is_finite() && value > 0.0,
+                "{name} must be a positive finite number"
+            );
+        }
+        let step = input_rate as f64 / output_rate as f64;
+        // Below 1 the kernel stretches so its cut-off follows the output Nyquist.
+        let scale = (1.0 / step).min(1.0);
+        let half = (RESAMPLER_ZERO_CROSSINGS / scale).ceil() as usize;
+        let phases = (0..=RESAMPLER_PHASES)
+            .map(|p| resampler_kernel(p as f64 / RESAMPLER_PHASES as f64, half, scale))
+            .collect();
+        Resampler {
+            step,
+            half,
+            phases,
+            buffer: vec![0.0; half - 1],
+            dropped: 0,
+            produced: 0,
+        }
+    }
+
+    /// Resample `chunk`, continuing from the state left by previous calls.
+    ///
+    /// # Panics
+    /// Panics when `chunk` contains non-finite values.
+    pub fn process(&mut self, chunk: &[f32]) -> Vec<f32> {
+        validate_finite(chunk);
+        self.buffer.extend_from_slice(chunk);
+        let taps = 2 * self.half;
+        let mut output = Vec::with_capacity((chunk.len() as f64 / self.step) as usize + 1);
+        loop {
+            // Position relative to `buffer[0]`; computed from the output
+            // count so chunk boundaries cannot accumulate rounding.
+            let position = self.produced as f64 * self.step + (self.half - 1) as f64;
+            let index = position as usize - self.dropped;
+            if index + self.half >= self.buffer.len() {
+                break;
+            }
+            let row = position.fract() * RESAMPLER_PHASES as f64;
+            let r = (row as usize).min(RESAMPLER_PHASES - 1);
+            let t = (row - r as f64) as f32;
+            let start = index + 1 - self.half;
+            let samples = &self.buffer[start..start + taps];
+            let value: f32 = samples
+                .iter()
+                .zip(self.phases[r].iter().zip(&self.phases[r + 1]))
+                .map(|(&x, (&a, &b))| x * (a + (b - a) * t))
+                .sum();
+            output.push(value);
+            self.produced += 1;
+        }
+        // Keep only the samples the next output still needs.
+        let next = self.produced as f64 * self.step + (self.half - 1) as f64;
+        let needed_from = (next as usize + 1).saturating_sub(self.half);
+        let consumed = needed_from
+            .saturating_sub(self.dropped)
+            .min(self.buffer.len());
+        self.buffer.drain(..consumed);
+        self.dropped += consumed;
+        output
+    }
+
+    /// Forget buffered input; the next chunk starts a new stream.
+    pub fn reset(&mut self) {
+        self.buffer = vec![0.0; self.half - 1];
+        self.dropped = 0;
+        self.produced = 0;
+    }
+}
+
+/// Resampling kernel for an output `frac` samples past an input sample.
+///
+/// Tap `j` multiplies the input `j + 1 - half` samples from that sample;
+/// `scale` is the cut-off relative to the input Nyquist.
+fn resampler_kernel(frac: f64, half: usize, scale: f64) -> Vec<f32> {
+    let pi = std::f64::consts::PI;
+    let mut taps: Vec<f64> = (0..2 * half)
+        .map(|j| {
+            let d = j as f64 + 1.0 - half as f64 - frac;
+            let x = d / half as f64;
+            if x.abs() >= 1.0 {
+                return 0.0;
+            }
+            let window = 0.42 + 0.5 * (pi * x).cos() + 0.08 * (2.0 * pi * x).cos();
+            let arg = pi * scale * d;
+            let sinc = if arg == 0.0 { 1.0 } else { arg.sin() / arg };
+            sinc * window
+        })
+        .collect();
+    let sum: f64 = taps.iter().sum();
+    taps.iter_mut().for_each(|t| *t /= sum);
+    taps.into_iter().map(|t| t as f32).collect()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::noise;
+
+    /// Tolerance for floating point comparisons in tests.
+    const TOLERANCE: f32 = 1e-4;
+
+    /// Causal direct-form convolution used as a reference.
+    fn direct_convolution(input: &[f32], taps: &[f32]) -> Vec<f32> {
+        (0..input.len())
+            .map(|n| {
+                taps.iter()
+                    .enumerate()
+                    .filter(|&(k, _)| k <= n)
+                    .map(|(k, &t)| t * input[n - k])
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// The automatic block size is a power of two no shorter than the filter.
+    #[test]
+    fn optimal_block_size_is_fast_and_long_enough() {
+        for ir_len in [1usize, 3, 100, 129, 1000] {
+            let size = optimal_conv_block_size(ir_len);
+            assert!(size.is_power_of_two(), "{size}");
+            assert!(size >= ir_len, "{size} < {ir_len}");
+            assert!(FirConvolver::new(&vec![0.1; ir_len], 0).block_size() == size);
+        }
+    }
+
+    /// Chunked overlap-save output matches direct convolution.
+    #[test]
+    fn fir_convolver_matches_direct_convolution() {
+        let taps = lowpass_taps(0.1, 31);
+        let input = noise(500, 3);
+        let expected = direct_convolution(&input, &taps);
+        for block in [0usize, 8, 64] {
+            let mut conv = FirConvolver::new(&taps, block);
+            let mut output = Vec::new();
+            for chunk in input.chunks(37) {
+                output.extend(conv.process(chunk));
+            }
+            assert_eq!(output.len(), input.len());
+            for (a, b) in output.iter().zip(&expected) {
+                assert!((a - b).abs() < TOLERANCE, "block {block}: {a} vs {b}");
+            }
+        }
+    }
+
+    /// RMS of the second half of `signal`, after the filters have settled.
+    fn settled_rms(signal: &[f32]) -> f32 {
+        let tail = &signal[signal.len() / 2..];
+        (tail.iter().map(|v| v * v).sum::<f32>() / tail.len() as f32).sqrt()
+    }
+
+    /// Hum harmonics are strongly attenuated; nearby frequencies pass.
+    #[test]
+    fn hum_remover_notches_harmonics() {
+        let rate = 8000.0;
+        let tone = |hz: f32| -> Vec<f32> {
+            (0..32_000)
+                .map(|i| (TWO_PI * hz * i as f32 / rate).sin())
+                .collect()
+        };
+        let unit_rms = settled_rms(&tone(75.0));
+        for hz in [50.0, 100.0, 150.0] {
+            let mut remover = HumRemover::new(50.0, rate, 5, 30.0);
+            let rms = settled_rms(&remover.process(&tone(hz)));
+            assert!(rms < 0.03 * unit_rms, "{hz} Hz leaked {rms}");
+        }
+        for hz in [75.0, 125.0] {
+            let mut remover = HumRemover::new(50.0, rate, 5, 30.0);
+            let rms = settled_rms(&remover.process(&tone(hz)));
+            assert!(rms > 0.9 * unit_rms, "{hz} Hz attenuated to {rms}");
+        }
+    }
+
+    /// The DC blocker and high-pass remove offset and rumble while passing
+    /// speech-band tones; pre-emphasis is the first difference it describes.
+    #[test]
+    fn preprocessor_removes_rumble() {
+        let rate = 8000.0;
+        let tone = |hz: f32, offset: f32| -> Vec<f32> {
+            (0..32_000)
+                .map(|i| offset + (TWO_PI * hz * i as f32 / rate).sin())
+                .collect()
+        };
+        let unit_rms = settled_rms(&tone(1000.0, 0.0));
+        let mut cleaner = Preprocessor::new(rate, true, 0.0, 100.0);
+        let rumble = settled_rms(&cleaner.process(&tone(20.0, 0.5)));
+        assert!(rumble < 0.05 * unit_rms, "rumble leaked {rumble}");
+        cleaner.reset();
+        let speech = settled_rms(&cleaner.process(&tone(1000.0, 0.5)));
+        assert!((speech / unit_rms - 1.0).abs() < 0.02, "{speech}");
+
+        let mut emphasis = Preprocessor::new(rate, false, 0.97, 0.0);
+        let input = noise(64, 4);
+        let mut output = emphasis.process(&input[..10]);
+        output.extend(emphasis.process(&input[10..]));
+        assert_eq!(output[0], input[0]);
+        for i in 1..input.len() {
+            assert!((output[i] - (input[i] - 0.97 * input[i - 1])).abs() < 1e-6);
+        }
+    }
+
+    /// A tone resampled in chunks matches the tone sampled at the new rate.
+    #[test]
+    fn resampler_reproduces_tone_at_new_rate() {
+        let (from, to, hz) = (44_100.0f32, 48_000.0f32, 1000.0f64);
+        let input: Vec<f32> = (0..8820)
+            .map(|i| (2.0 * std::f64::consts::PI * hz * i as f64 / from as f64).sin() as f32)
+            .collect();
+        let mut resampler = Resampler::new(from, to);
+        let mut output = Vec::new();
+        for chunk in input.chunks(128) {
+            output.extend(resampler.process(chunk));
+        }
+        let mut whole = Resampler::new(from, to);
+        assert_eq!(whole.process(&input), output);
+
+        let expected_len = (input.len() as f64 * to as f64 / from as f64) as usize;
+        assert!(output.len() <= expected_len && output.len() + 40 > expected_len);
+        for (j, &y) in output.iter().enumerate().skip(100) {
+            let expected = (2.0 * std::f64::consts::PI * hz * j as f64 / to as f64).sin();
+            assert!((y as f64 - expected).abs() < 1e-3, "sample {j}: {y}");
+        }
+    }
+
+    /// Downsampling removes content above the new Nyquist frequency.
+    #[test]
+    fn resampler_rejects_aliases_when_downsampling() {
+        let (from, to) = (48_000.0f32, 16_000.0f32);
+        let tone = |hz: f32| -> Vec<f32> {
+            (0..9600)
+                .map(|i| (crate::TWO_PI * hz * i as f32 / from).sin())
+                .collect()
+        };
+        let rms = |x: &[f32]| (x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32).sqrt();
+        let kept = Resampler::new(from, to).process(&tone(3000.0));
+        let aliased = Resampler::new(from, to).process(&tone(12_000.0));
+        assert!(rms(&kept[200..]) > 0.69);
+        assert!(rms(&aliased[200..]) < 1e-3, "{}", rms(&aliased[200..]));
+    }
+}
@@ -1,27 +1,282 @@
-use rustfft::{num_complex::Complex32, FftPlanner};
+use rustfft::{
+    num_complex::{Complex, Complex32},
+    Fft, FftNum, FftPlanner,
+};
+use serde::{Deserialize, Serialize};
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     f32::consts::PI,
-    sync::{Mutex, OnceLock},
+    str::FromStr,
+    sync::{Arc, Mutex, OnceLock},
 };
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-/// Lazily initialized global planner for FFT computations.
+mod analysis;
+// Bare `wasm32-unknown-unknown` has no clock without `performance.now()`,
+// which comes with the `wasm` feature.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown", not(feature = "wasm"))))]
+mod bench;
+mod chroma;
+mod config;
+mod cqt;
+mod display;
+mod error;
+mod features;
+mod filters;
+mod mel;
+mod png;
+mod precision;
+mod pyramid;
+mod ring;
+mod scaling;
+mod simd;
+mod spectral;
+mod spectrogram;
+mod stream;
+mod synthesis;
+#[cfg(test)]
+mod test_support;
+mod weighting;
+
+pub use analysis::*;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown", not(feature = "wasm"))))]
+pub use bench::*;
+pub use chroma::*;
+pub use config::*;
+pub use cqt::*;
+pub use display::*;
+pub use error::DspError;
+pub use features::*;
+pub use filters::{optimal_conv_block_size, FirConvolver, HumRemover, Preprocessor, Resampler};
+pub use mel::*;
+pub use png::*;
+pub use precision::*;
+pub use pyramid::*;
+pub use ring::*;
+pub use scaling::*;
+pub use spectral::*;
+pub use spectrogram::*;
+pub use stream::*;
+pub use synthesis::*;
+pub use weighting::*;
+
+/// FFT sizes whose plans [`FFT_PLANNER`] keeps by default; `0` keeps every
+/// size until [`set_fft_cache_capacity`] sets a bound.
+const DEFAULT_PLAN_CAPACITY: usize = 0;
+
+/// Lazily initialized global cache of FFT plans.
 ///
 /// # What
-/// Shares a single `FftPlanner` instance across calls.
+/// Shares planned FFT algorithms across calls. The number of sizes kept
+/// can be bounded with [`set_fft_cache_capacity`].
 ///
 /// # Why
-/// Creating a planner allocates and caches FFT algorithms. Reusing a single
-/// instance avoids repeated allocations and planning overhead.
-static FFT_PLANNER: OnceLock<Mutex<FftPlanner<f32>>> = OnceLock::new();
+/// Planning allocates twiddle tables and picks algorithms; reusing plans
+/// avoids that per call. `rustfft`'s own planner never forgets a size,
+/// which grows without bound in a long-lived WASM instance whose users
+/// keep switching FFT sizes.
+static FFT_PLANNER: OnceLock<Mutex<PlanCache<f32>>> = OnceLock::new();
+
+/// The `f64` counterpart of [`FFT_PLANNER`], used by the double-precision
+/// pipeline and bounded by the same [`set_fft_cache_capacity`].
+static FFT_PLANNER_F64: OnceLock<Mutex<PlanCache<f64>>> = OnceLock::new();
+
+/// Forward and inverse plans of one FFT size.
+struct CachedPlans<T: FftNum> {
+    n: usize,
+    forward: Option<Arc<dyn Fft<T>>>,
+    inverse: Option<Arc<dyn Fft<T>>>,
+}
+
+/// Least-recently-used cache of FFT plans keyed by size.
+///
+/// # How
+/// `entries` is ordered from least to most recently used; it holds a handful
+/// of sizes, so a linear scan beats hashing. Each miss plans with a fresh
+/// `FftPlanner`, so evicted sizes leave nothing behind in a planner cache.
+/// Plans already handed out stay valid after eviction.
+struct PlanCache<T: FftNum> {
+    capacity: usize,
+    entries: Vec<CachedPlans<T>>,
+}
+
+impl<T: FftNum> PlanCache<T> {
+    /// Empty cache holding at most `capacity` sizes (`0` means unbounded).
+    fn new(capacity: usize) -> PlanCache<T> {
+        PlanCache {
+            capacity,
+            entries: Vec::new(),
+        }
+    }
 
-/// Retrieve the global FFT planner, initializing it on first use.
+    /// Plan for size `n`, planning and caching it on a miss.
+    fn plan(&mut self, n: usize, inverse: bool) -> Arc<dyn Fft<T>> {
+        let index = match self.entries.iter().position(|e| e.n == n) {
+            Some(i) => i,
+            None => {
+                self.entries.push(CachedPlans {
+                    n,
+                    forward: None,
+                    inverse: None,
+                });
+                self.entries.len() - 1
+            }
+        };
+        let mut entry = self.entries.remove(index);
+        let slot = if inverse {
+            &mut entry.inverse
+        } else {
+            &mut entry.forward
+        };
+        let plan = Arc::clone(slot.get_or_insert_with(|| {
+            let mut planner = FftPlanner::new();
+            if inverse {
+                planner.plan_fft_inverse(n)
+            } else {
+                planner.plan_fft_forward(n)
+            }
+        }));
+        self.entries.push(entry);
+        self.evict();
+        plan
+    }
+
+    /// Drop least recently used sizes beyond the capacity.
+    fn evict(&mut self) {
+        if self.capacity > 0 && self.entries.len() > self.capacity {
+            let excess = self.entries.len() - self.capacity;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Approximate heap bytes held by the cached plans.
+    fn bytes(&self) -> usize {
+        let plans =
+            |e: &CachedPlans<T>| e.forward.is_some() as usize + e.inverse.is_some() as usize;
+        self.entries
+            .iter()
+            .map(|e| plans(e) * e.n * std::mem::size_of::<Complex<T>>())
+            .sum()
+    }
+}
+
+/// Retrieve the global plan cache, initializing it on first use.
 ///
 /// # How
-/// Wraps the planner in a `Mutex` for interior mutability because planning
+/// Wraps the cache in a `Mutex` for interior mutability because planning
 /// requires mutable access to cache FFT algorithms by size.
-fn planner() -> &'static Mutex<FftPlanner<f32>> {
-    FFT_PLANNER.get_or_init(|| Mutex::new(FftPlanner::new()))
+fn planner() -> std::sync::MutexGuard<'static, PlanCache<f32>> {
+    FFT_PLANNER
+        .get_or_init(|| Mutex::new(PlanCache::new(DEFAULT_PLAN_CAPACITY)))
+        .lock()
+        .expect("planner lock")
+}
+
+/// Retrieve the global `f64` plan cache, initializing it on first use with
+/// the capacity of the `f32` cache.
+fn planner_f64() -> std::sync::MutexGuard<'static, PlanCache<f64>> {
+    FFT_PLANNER_F64
+        .get_or_init(|| Mutex::new(PlanCache::new(planner().capacity)))
+        .lock()
+        .expect("f64 planner lock")
+}
+
+/// Plan a forward FFT of length `n` with the shared planner.
+///
+/// # How
+/// The planner lock is held only while planning to minimize contention;
+/// the returned algorithm can run without it.
+fn plan_forward(n: usize) -> Arc<dyn Fft<f32>> {
+    planner().plan(n, false)
+}
+
+/// Plan an inverse (unnormalized) FFT of length `n` with the shared planner.
+fn plan_inverse(n: usize) -> Arc<dyn Fft<f32>> {
+    planner().plan(n, true)
+}
+
+/// Plan a forward `f64` FFT of length `n` with the shared `f64` planner.
+pub(crate) fn plan_forward_f64(n: usize) -> Arc<dyn Fft<f64>> {
+    planner_f64().plan(n, false)
+}
+
+/// Drop every cached FFT plan.
+///
+/// # Why
+/// Frees the planning memory of a long-lived instance, e.g. after the user
+/// has tried many FFT sizes. Later calls plan their sizes again.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn clear_fft_cache() {
+    planner().entries.clear();
+    planner_f64().entries.clear();
+}
+
+/// Keep plans for at most `max_sizes` FFT sizes, evicting the least
+/// recently used first. `0` removes the bound, which is the default. The
+/// `f32` and `f64` plans are bounded separately, each to `max_sizes`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn set_fft_cache_capacity(max_sizes: usize) {
+    let mut cache = planner();
+    cache.capacity = max_sizes;
+    cache.evict();
+    drop(cache);
+    let mut cache = planner_f64();
+    cache.capacity = max_sizes;
+    cache.evict();
+}
+
+/// Approximate bytes held by cached FFT plans.
+///
+/// # What
+/// Counts one complex twiddle per point for every cached forward or
+/// inverse plan, which dominates a plan's footprint. Window tables are not
+/// included.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fft_cache_bytes() -> usize {
+    let bytes = planner().bytes();
+    bytes + planner_f64().bytes()
+}
+
+/// Window coefficient tables keyed by length and window type.
+///
+/// # Why
+/// STFT callers window thousands of frames of one size. Computing the
+/// table once per `(n, window)` replaces the per-sample `cos()` calls with
+/// a lookup, just as [`FFT_PLANNER`] caches FFT plans.
+static WINDOW_CACHE: OnceLock<Mutex<WindowTables>> = OnceLock::new();
+
+/// Cached coefficient tables, see [`WINDOW_CACHE`].
+type WindowTables = HashMap<(usize, WindowType), Arc<[f32]>>;
+
+/// The `n` coefficients of `window` from [`WINDOW_CACHE`], computed on
+/// first use.
+///
+/// # How
+/// The lock is released before the table is used, and the table is shared
+/// through an `Arc` so concurrent callers never copy it.
+fn cached_window(window: WindowType, n: usize) -> Arc<[f32]> {
+    let mut cache = WINDOW_CACHE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .expect("window cache lock");
+    Arc::clone(
+        cache
+            .entry((n, window))
+            .or_insert_with(|| (0..n).map(|i| window_coefficient(window, i, n)).collect()),
+    )
+}
+
+thread_local! {
+    /// Complex work buffer and FFT scratch reused by the `*_into` functions.
+    ///
+    /// # Why
+    /// Callers of the `*_into` functions reuse their output buffer to avoid
+    /// allocator churn; keeping the intermediate buffers alive per thread
+    /// makes the whole call allocation-free once the buffers have grown.
+    static INTO_BUFFERS: RefCell<(Vec<Complex32>, Vec<Complex32>)> =
+        const { RefCell::new((Vec::new(), Vec::new())) };
 }
 
 /// Full circle constant used in window and FFT calculations.
@@ -34,6 +289,9 @@ const EPSILON: f32 = 1e-12;
 /// Linear-to-decibel scaling factor (20 * log10(x)).
 const DB_SCALE: f32 = 20.0;
 
+/// Power-to-decibel scaling factor (10 * log10(x)).
+const POWER_DB_SCALE: f32 = 10.0;
+
 /// Coefficient for the Hann window: 0.5 - 0.5 * cos(theta).
 const HANN_A0: f32 = 0.5;
 
@@ -44,11 +302,39 @@ const HANN_A1: f32 = 0.5;
 const HAMMING_ALPHA: f32 = 0.54;
 const HAMMING_BETA: f32 = 0.46;
 
+/// Largest gain in dB that [`auto_gain`] applies, so near-silent blocks
+/// are not amplified into loud noise.
+const MAX_AUTO_GAIN_DB: f32 = 40.0;
+
 /// Coefficients for the Blackman window formula.
 const BLACKMAN_A0: f32 = 0.42;
 const BLACKMAN_A1: f32 = 0.5;
 const BLACKMAN_A2: f32 = 0.08;
 
+/// Coefficients for the 4-term Blackman-Harris window (-92 dB sidelobes).
+const BLACKMAN_HARRIS: [f32; 4] = [0.35875, 0.48829, 0.14128, 0.01168];
+
+/// Coefficients for the 4-term Nuttall window (-93 dB sidelobes that fall
+/// off faster than Blackman-Harris).
+const NUTTALL: [f32; 4] = [0.355_768, 0.487_396, 0.144_232, 0.012_604];
+
+/// Coefficients for the 5-term flat-top window used for amplitude
+/// measurement.
+const FLAT_TOP: [f32; 5] = [
+    0.215_578_95,
+    0.416_631_58,
+    0.277_263_16,
+    0.083_578_95,
+    0.006_947_37,
+];
+
+/// Fraction of the Tukey window covered by its cosine tapers. `0` would be
+/// rectangular and `1` Hann.
+const TUKEY_ALPHA: f32 = 0.5;
+
+/// Relative size of the last term at which the Bessel I0 series stops.
+const BESSEL_TOLERANCE: f32 = 1e-9;
+
 /// Validate that all elements in `input` are finite.
 ///
 /// # Why
@@ -61,7 +347,164 @@ fn validate_finite(input: &[f32]) {
     }
 }
 
+/// Validate that `input` contains no NaN, allowing infinities.
+///
+/// # Why
+/// dB values of silent bins are `-inf`, which display and level code must
+/// accept as "below everything"; NaN has no place on any scale.
+fn validate_not_nan(input: &[f32]) {
+    if input.iter().any(|v| v.is_nan()) {
+        panic!("input contains NaN values");
+    }
+}
+
+/// Locate the first non-finite element of `input`.
+///
+/// # Errors
+/// Returns [`DspError::NonFinite`] with the offending index.
+fn check_finite(input: &[f32]) -> Result<(), DspError> {
+    match input.iter().position(|v| !v.is_finite()) {
+        Some(index) => Err(DspError::NonFinite { index }),
+        None => Ok(()),
+    }
+}
+
+/// Check that `input` is a non-empty, finite frame.
+///
+/// # Errors
+/// Returns [`DspError::EmptyInput`] for an empty frame and
+/// [`DspError::NonFinite`] for NaN or infinite samples.
+fn check_frame(input: &[f32]) -> Result<(), DspError> {
+    if input.is_empty() {
+        return Err(DspError::EmptyInput);
+    }
+    check_finite(input)
+}
+
+/// Replace every non-finite sample with `replacement`.
+///
+/// # What
+/// Returns a copy of `input` where NaN and infinite values are substituted,
+/// so the result can be passed to [`fft_real`] and friends without
+/// triggering [`validate_finite`]. Pass `0.0` for the conventional choice; a
+/// non-finite `replacement` is itself replaced by `0.0`.
+///
+/// # Why
+/// A lightweight alternative to rejecting the whole block when a capture
+/// glitch injects a handful of bad samples.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn sanitize(input: &[f32], replacement: f32) -> Vec<f32> {
+    let replacement = if replacement.is_finite() {
+        replacement
+    } else {
+        0.0
+    };
+    input
+        .iter()
+        .map(|&v| if v.is_finite() { v } else { replacement })
+        .collect()
+}
+
+/// Scale `input` so its RMS level matches `target_rms_db` dBFS.
+///
+/// # What
+/// The gain is `target_rms / rms(input)`, limited to at most
+/// [`MAX_AUTO_GAIN_DB`] of amplification; attenuation is not limited.
+/// Silent blocks are returned unchanged.
+///
+/// # Why
+/// Normalizing the level before analysis keeps spectrogram brightness
+/// consistent across recordings made at different levels.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `target_rms_db` is
+/// not finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn auto_gain(input: &[f32], target_rms_db: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(target_rms_db.is_finite(), "target_rms_db must be finite");
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let mean_square =
+        input.iter().map(|&x| (x as f64) * (x as f64)).sum::<f64>() / input.len() as f64;
+    let rms = mean_square.sqrt() as f32;
+    if rms <= EPSILON {
+        return input.to_vec();
+    }
+    let target = 10f32.powf(target_rms_db / DB_SCALE);
+    let gain = (target / rms).min(10f32.powf(MAX_AUTO_GAIN_DB / DB_SCALE));
+    input.iter().map(|&x| x * gain).collect()
+}
+
+/// Add a sinusoid at `freq_hz` and `level_db` dBFS to a copy of `input`.
+///
+/// # What
+/// The tone `a * sin(2 pi freq_hz t)` starts at phase zero with peak
+/// amplitude `a = 10^(level_db / 20)`, so `0` dBFS is a full-scale sine.
+///
+/// # Why
+/// A tone of known level is the standard probe for checking the level
+/// calibration of the whole analysis pipeline end to end.
+///
+/// # Panics
+/// Panics when any input is non-finite or `sample_rate` is not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn inject_tone(input: &[f32], freq_hz: f32, level_db: f32, sample_rate: f32) -> Vec<f32> {
+    validate_finite(input);
+    validate_finite(&[freq_hz, level_db]);
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    let amplitude = 10f32.powf(level_db / DB_SCALE);
+    let step = std::f64::consts::TAU * freq_hz as f64 / sample_rate as f64;
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| x + amplitude * (step * i as f64).sin() as f32)
+        .collect()
+}
+
+/// Saturate samples above `threshold` smoothly towards full scale.
+///
+/// # What
+/// Samples with `|x| <= threshold` pass unchanged. Above it the excess is
+/// compressed with `threshold + (1 - threshold) * tanh((|x| - threshold) /
+/// (1 - threshold))`, which joins the linear region with matching slope and
+/// approaches `±1` asymptotically, so the output never exceeds full scale.
+///
+/// # Why
+/// Hard clipping creates a sharp corner that sprays odd harmonics across
+/// the spectrum; a smooth knee keeps the analysis far cleaner.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `threshold` is not in
+/// `(0, 1)`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn soft_limit(input: &[f32], threshold: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        threshold > 0.0 && threshold < 1.0,
+        "threshold must be in (0, 1)"
+    );
+    let headroom = 1.0 - threshold;
+    input
+        .iter()
+        .map(|&x| {
+            let magnitude = x.abs();
+            if magnitude <= threshold {
+                x
+            } else {
+                let excess = (magnitude - threshold) / headroom;
+                (threshold + headroom * excess.tanh()).copysign(x)
+            }
+        })
+        .collect()
+}
+
 // Set panic hook for better error messages in wasm
+#[cfg(feature = "wasm")]
 #[wasm_bindgen(start)]
 pub fn init_panic_hook() {
     console_error_panic_hook::set_once();
@@ -70,118 +513,1184 @@ pub fn init_panic_hook() {
 /// Compute the forward real-to-complex FFT using the `rustfft` library.
 ///
 /// # What
-/// Returns a flat array `[re0, im0, re1, im1, ...]` suitable for
-/// uploading to GPU textures without additional rearrangement.
+/// Returns the non-redundant bins `0..=n/2` as a flat array `[re0, im0,
+/// re1, im1, ...]` of `2 * (n/2 + 1)` values, suitable for uploading to GPU
+/// textures without additional rearrangement. The remaining bins are the
+/// complex conjugates of these (`X[n - k] = conj(X[k])`); [`fft_complex`]
+/// returns all `n` bins when they are needed.
 ///
 /// # Why
 /// Replaces the previous \(O(n^2)\) reference implementation with a
-/// fast \(O(n \log n)\) FFT for significant performance gains.
-#[wasm_bindgen]
+/// fast \(O(n \log n)\) FFT for significant performance gains. Half the
+/// bins of a real signal's spectrum are redundant, so dropping them halves
+/// the data copied to JS and uploaded to the GPU.
+///
+/// # How
+/// For even `n` the samples are packed pairwise into `n/2` complex values,
+/// transformed with a half-length FFT from the shared planner, and the
+/// spectra of the even and odd samples are separated and recombined with
+/// one twiddle per bin. Odd lengths use the full complex FFT.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn fft_real(input: &[f32]) -> Vec<f32> {
     validate_finite(input);
     fft_real_unchecked(input)
 }
 
+/// [`fft_real`] that reports non-finite input instead of panicking.
+///
+/// # Why
+/// A panic aborts the WASM instance, so one corrupt sample from a dropped
+/// audio buffer would otherwise break every later call. On the JS side the
+/// error is thrown as a regular `Error` the caller can catch.
+///
+/// # Errors
+/// Returns [`DspError::EmptyInput`] for an empty block and
+/// [`DspError::NonFinite`] when `input` contains NaN or infinity.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn try_fft_real(input: &[f32]) -> Result<Vec<f32>, DspError> {
+    check_frame(input)?;
+    Ok(fft_real_unchecked(input))
+}
+
 /// Internal FFT implementation that assumes `input` is finite.
 fn fft_real_unchecked(input: &[f32]) -> Vec<f32> {
+    interleave(&rfft_unchecked(input))
+}
+
+/// Forward FFT of complex samples, e.g. IQ data from an SDR.
+///
+/// # What
+/// Takes `n` complex samples interleaved as `[re0, im0, re1, im1, ...]` and
+/// returns all `n` of their FFT bins in the same interleaved layout as
+/// [`fft_real`]. Empty input yields an empty result.
+///
+/// # Why
+/// [`fft_real`] assumes a zero imaginary part, which discards half of an
+/// IQ or analytic signal.
+///
+/// # Panics
+/// Panics when `input` has odd length or contains non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fft_complex(input: &[f32]) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        input.len().is_multiple_of(2),
+        "interleaved complex input must have even length"
+    );
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let mut buffer: Vec<Complex32> = input
+        .chunks_exact(2)
+        .map(|pair| Complex32::new(pair[0], pair[1]))
+        .collect();
+    plan_forward(buffer.len()).process(&mut buffer);
+    interleave(&buffer)
+}
+
+/// [`fft_real`] writing into a caller-provided buffer.
+///
+/// # What
+/// Writes the `2 * (n/2 + 1)` interleaved values of [`fft_real`] for an
+/// `n`-sample `input` to the start of `out` and returns how many were
+/// written. Values past that in `out` are left untouched.
+///
+/// # Why
+/// Reusing one output buffer for a whole file avoids a fresh allocation
+/// per frame.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `out` holds fewer
+/// than `2 * (n/2 + 1)` values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fft_real_into(input: &[f32], out: &mut [f32]) -> usize {
+    validate_finite(input);
+    let n = input.len();
+    let len = half_spectrum_len(n);
+    assert!(out.len() >= len, "out lacks space for 2 * (n/2 + 1) values");
+    if n == 0 {
+        return 0;
+    }
+    INTO_BUFFERS.with(|cell| {
+        let (buffer, scratch) = &mut *cell.borrow_mut();
+        let fft = load_rfft(n, |i| input[i], buffer);
+        scratch.resize(fft.get_inplace_scratch_len(), Complex32::new(0.0, 0.0));
+        fft.process_with_scratch(buffer, scratch);
+        for (k, pair) in out[..len].chunks_exact_mut(2).enumerate() {
+            let c = rfft_bin(buffer, k, n);
+            pair[0] = c.re;
+            pair[1] = c.im;
+        }
+    });
+    len
+}
+
+/// Inverse of [`fft_real`]: `n` real samples from their half spectrum.
+///
+/// # What
+/// Takes the `n/2 + 1` bins `[re0, im0, re1, im1, ...]` of an `n`-sample
+/// signal (as produced by [`fft_real`]), restores the upper bins as their
+/// complex conjugates, runs the inverse FFT, divides by `n` and returns the
+/// `n` real parts. `n` is passed explicitly because even and odd lengths
+/// share a bin count. Imaginary residue, e.g. from a non-zero imaginary
+/// DC bin, is discarded. `n == 0` yields an empty result.
+///
+/// # Why
+/// Frequency-domain editing (denoising, filtering) needs a way back to
+/// audio after modifying the spectrum.
+///
+/// # Panics
+/// Panics when `spectrum` contains non-finite values or does not hold
+/// `2 * (n/2 + 1)` values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn ifft_real(spectrum: &[f32], n: usize) -> Vec<f32> {
+    validate_finite(spectrum);
+    assert_eq!(
+        spectrum.len(),
+        half_spectrum_len(n),
+        "spectrum must hold n/2 + 1 interleaved re/im bins"
+    );
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut buffer = vec![Complex32::new(0.0, 0.0); n];
+    mirror_half_spectrum(spectrum, &mut buffer);
+    plan_inverse(n).process(&mut buffer);
+    let scale = 1.0 / n as f32;
+    buffer.iter().map(|c| c.re * scale).collect()
+}
+
+/// Number of interleaved values in the half spectrum of `n` real samples.
+pub(crate) fn half_spectrum_len(n: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        2 * (n / 2 + 1)
+    }
+}
+
+/// Expand the interleaved half spectrum `half` into all `full.len()` bins,
+/// filling bin `k > n/2` with the conjugate of bin `n - k`.
+pub(crate) fn mirror_half_spectrum(half: &[f32], full: &mut [Complex32]) {
+    let n = full.len();
+    for (k, bin) in full.iter_mut().enumerate() {
+        let j = if k <= n / 2 { k } else { n - k };
+        let c = Complex32::new(half[2 * j], half[2 * j + 1]);
+        *bin = if j == k { c } else { c.conj() };
+    }
+}
+
+/// Flatten complex values into interleaved `[re, im, ...]` pairs.
+fn interleave(buffer: &[Complex32]) -> Vec<f32> {
+    let mut output = Vec::with_capacity(2 * buffer.len());
+    for c in buffer {
+        output.push(c.re);
+        output.push(c.im);
+    }
+    output
+}
+
+/// Window `input` and compute its interleaved complex spectrum in one pass.
+///
+/// # What
+/// Same result as `fft_real(&apply_window(input, window_type))`.
+///
+/// # Why
+/// The window is applied while filling the FFT's complex buffer, so no
+/// intermediate windowed `Vec` is allocated.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or the window name is
+/// unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn windowed_fft(input: &[f32], window_type: &str) -> Vec<f32> {
+    validate_finite(input);
+    let window = parse_window(window_type);
+    let n = input.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    interleave(&rfft_with(n, |i| {
+        input[i] * window_coefficient(window, i, n)
+    }))
+}
+
+/// Compute the forward FFT and map every output value to `value * scale + bias`.
+///
+/// # What
+/// Same interleaved layout as [`fft_real`], with the affine mapping applied
+/// to both real and imaginary components.
+///
+/// # Why
+/// GPU float textures often expect data pre-normalized into `[0, 1]`.
+/// Applying the mapping inside WASM saves a separate per-element JS loop.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fft_real_scaled(input: &[f32], scale: f32, bias: f32) -> Vec<f32> {
+    validate_finite(input);
+    let mut output = fft_real_unchecked(input);
+    for v in &mut output {
+        *v = *v * scale + bias;
+    }
+    output
+}
+
+/// Forward FFT of `input` zero-padded or truncated to `fft_size` samples.
+///
+/// # What
+/// Copies `input` into a zero-filled buffer of `fft_size` samples and
+/// returns its `fft_size/2 + 1` bins in the layout of [`fft_real`]. Input longer
+/// than `fft_size` is truncated to its first `fft_size` samples.
+/// `fft_size == 0` yields an empty result.
+///
+/// # Why
+/// Capture blocks rarely have power-of-two lengths, which rustfft handles
+/// fastest. Padding also samples the spectrum more finely: bin `k` of the
+/// padded transform lies at `k * sample_rate / fft_size` Hz.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fft_real_padded(input: &[f32], fft_size: usize) -> Vec<f32> {
+    validate_finite(input);
+    let mut padded = vec![0.0f32; fft_size];
+    let n = input.len().min(fft_size);
+    padded[..n].copy_from_slice(&input[..n]);
+    fft_real_unchecked(&padded)
+}
+
+/// Compute the forward FFT laid out for WebGPU storage buffers.
+///
+/// # What
+/// With `align16 = false` this is exactly [`fft_real`]: one 8-byte
+/// `vec2<f32>` per bin. With `align16 = true` every bin is padded to
+/// `[re, im, 0, 0]`, a 16-byte stride matching arrays of `vec4<f32>` or of
+/// structs that WGSL aligns to 16 bytes.
+///
+/// # Why
+/// Storage-buffer arrays of structs often require 16-byte element
+/// alignment. Padding in WASM lets the result be written to the buffer
+/// as-is instead of being re-packed in JavaScript.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fft_real_padded_layout(input: &[f32], align16: bool) -> Vec<f32> {
+    validate_finite(input);
+    let spec = fft_real_unchecked(input);
+    if !align16 {
+        return spec;
+    }
+    let mut output = Vec::with_capacity(2 * spec.len());
+    for c in spec.chunks_exact(2) {
+        output.extend_from_slice(&[c[0], c[1], 0.0, 0.0]);
+    }
+    output
+}
+
+/// Linear magnitudes of the non-redundant bins `0..=n/2` of a real block.
+///
+/// # Why
+/// The upper half of a real signal's spectrum mirrors the lower half, so
+/// analysis helpers only need the first `n/2 + 1` bins.
+fn half_spectrum_magnitudes(input: &[f32]) -> Vec<f32> {
+    simd::norms(&rfft_unchecked(input))
+}
+
+/// The non-redundant bins `0..=n/2` of the FFT of a real block.
+///
+/// # What
+/// The same half spectrum as [`fft_real`], under the name NumPy and most
+/// DSP libraries use for it.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn rfft(input: &[f32]) -> Vec<f32> {
+    fft_real(input)
+}
+
+/// Bins `0..=n/2` of the FFT of `input`, without validation.
+fn rfft_unchecked(input: &[f32]) -> Vec<Complex32> {
+    rfft_with(input.len(), |i| input[i])
+}
+
+/// Bins `0..=n/2` of the FFT of the `n` real samples `sample(i)`.
+fn rfft_with(n: usize, sample: impl Fn(usize) -> f32) -> Vec<Complex32> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut buffer = Vec::with_capacity(n);
+    load_rfft(n, sample, &mut buffer).process(&mut buffer);
+    (0..=n / 2).map(|k| rfft_bin(&buffer, k, n)).collect()
+}
+
+/// Fill `buffer` with the FFT input for the `n` real samples `sample(i)`
+/// and return the plan that transforms it.
+///
+/// Even lengths are packed pairwise into `n/2` complex values for a
+/// half-length FFT; odd lengths get the full complex FFT. [`rfft_bin`]
+/// reads bin `k` back from the transformed buffer.
+fn load_rfft(
+    n: usize,
+    sample: impl Fn(usize) -> f32,
+    buffer: &mut Vec<Complex32>,
+) -> Arc<dyn Fft<f32>> {
+    buffer.clear();
+    if n.is_multiple_of(2) {
+        buffer.extend((0..n / 2).map(|i| Complex32::new(sample(2 * i), sample(2 * i + 1))));
+        plan_forward(n / 2)
+    } else {
+        buffer.extend((0..n).map(|i| Complex32::new(sample(i), 0.0)));
+        plan_forward(n)
+    }
+}
+
+/// Bin `k <= n/2` of an `n`-point real FFT from a buffer prepared by
+/// [`load_rfft`] and transformed.
+fn rfft_bin(buffer: &[Complex32], k: usize, n: usize) -> Complex32 {
+    if n.is_multiple_of(2) {
+        unpack_rfft_bin(buffer, k, rfft_twiddle(k, n))
+    } else {
+        buffer[k]
+    }
+}
+
+/// Twiddle factor `exp(-2πik/n)` that recombines bin `k` of an `n`-point
+/// packed real FFT.
+pub(crate) fn rfft_twiddle(k: usize, n: usize) -> Complex32 {
+    let (sin, cos) = (-TWO_PI * k as f32 / n as f32).sin_cos();
+    Complex32::new(cos, sin)
+}
+
+/// Bin `k` of a real FFT from the half-length FFT of its pairwise-packed
+/// samples (see [`rfft`]).
+pub(crate) fn unpack_rfft_bin(packed: &[Complex32], k: usize, twiddle: Complex32) -> Complex32 {
+    let half = packed.len();
+    let z = packed[k % half];
+    let mirror = packed[(half - k) % half].conj();
+    let even = (z + mirror) * 0.5;
+    // (z - mirror) / 2i
+    let odd = (z - mirror) * Complex32::new(0.0, -0.5);
+    even + twiddle * odd
+}
+
+/// Window functions supported by the windowing routines.
+///
+/// # What
+/// Typed alternative to the string window names accepted by [`apply_window`].
+///
+/// # Why
+/// A misspelled string is only caught at run time. Exposing an enum to JS
+/// turns such typos into type errors at the call site.
+///
+/// Serializes as the canonical string name, e.g. `"blackman-harris"`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowType {
+    /// No tapering; samples pass through unchanged.
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    /// 4-term Blackman-Harris with very low sidelobes.
+    BlackmanHarris,
+    /// 4-term Nuttall with continuous first derivative.
+    Nuttall,
+    /// 5-term flat-top with almost no scalloping loss.
+    #[serde(rename = "flattop")]
+    FlatTop,
+    /// Tukey (tapered cosine) covering [`TUKEY_ALPHA`] of the frame.
+    Tukey,
+}
+
+impl FromStr for WindowType {
+    type Err = DspError;
+
+    /// Parse the window names accepted by the string API.
+    ///
+    /// `"rect"`, `"rectangular"` and `"none"` all select
+    /// [`WindowType::Rectangular`].
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "hann" => Ok(WindowType::Hann),
+            "hamming" => Ok(WindowType::Hamming),
+            "blackman" => Ok(WindowType::Blackman),
+            "blackman-harris" => Ok(WindowType::BlackmanHarris),
+            "nuttall" => Ok(WindowType::Nuttall),
+            "flattop" => Ok(WindowType::FlatTop),
+            "tukey" => Ok(WindowType::Tukey),
+            "rect" | "rectangular" | "none" => Ok(WindowType::Rectangular),
+            _ => Err(DspError::UnknownWindow(name.to_owned())),
+        }
+    }
+}
+
+/// Parse a window name for the string APIs.
+///
+/// # Panics
+/// Panics with the [`DspError::UnknownWindow`] message on an unknown name.
+pub(crate) fn parse_window(name: &str) -> WindowType {
+    WindowType::from_str(name).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Apply window function to input buffer. What: Multiplies input by window coefficients.
+/// Why: Reduces spectral leakage in FFT analysis.
+///
+/// # Panics
+/// Panics on an unknown window name; use [`apply_window_checked`] to get
+/// a [`DspError`] instead.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_window(input: &[f32], window_type: &str) -> Vec<f32> {
+    validate_finite(input);
+    apply_window_unchecked(input, window_type)
+}
+
+/// Apply a window selected by name, rejecting unknown names and
+/// non-finite input.
+///
+/// # Errors
+/// Returns [`DspError::UnknownWindow`] when `window_type` is not recognized
+/// and [`DspError::NonFinite`] when `input` contains NaN or infinity.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_window_checked(input: &[f32], window_type: &str) -> Result<Vec<f32>, DspError> {
+    let window = WindowType::from_str(window_type)?;
+    check_finite(input)?;
+    Ok(window_samples(input, window))
+}
+
+/// Apply a window selected by [`WindowType`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_window_typed(input: &[f32], window_type: WindowType) -> Vec<f32> {
+    validate_finite(input);
+    window_samples(input, window_type)
+}
+
+/// Apply window coefficients without validating `input`.
+fn apply_window_unchecked(input: &[f32], window_type: &str) -> Vec<f32> {
+    let window = parse_window(window_type);
+    window_samples(input, window)
+}
+
+/// Fill the window cache for `window_type` at `size` samples.
+///
+/// # Why
+/// The first frame of a given size otherwise pays for computing the table.
+/// Calling this during setup keeps that cost out of a real-time callback.
+///
+/// # Panics
+/// Panics on an unknown window name.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn precompute_window(window_type: &str, size: usize) {
+    cached_window(parse_window(window_type), size);
+}
+
+/// Coefficient `i` of an `n`-point `window`.
+fn window_coefficient(window: WindowType, i: usize, n: usize) -> f32 {
+    let denom = (n as f32 - 1.0).max(1.0);
+    let phase = TWO_PI * i as f32 / denom;
+    match window {
+        WindowType::Hann => HANN_A0 - HANN_A1 * phase.cos(),
+        WindowType::Hamming => HAMMING_ALPHA - HAMMING_BETA * phase.cos(),
+        WindowType::Blackman => {
+            BLACKMAN_A0 - BLACKMAN_A1 * phase.cos() + BLACKMAN_A2 * (2.0 * phase).cos()
+        }
+        WindowType::BlackmanHarris => cosine_sum(&BLACKMAN_HARRIS, phase),
+        WindowType::Nuttall => cosine_sum(&NUTTALL, phase),
+        WindowType::FlatTop => cosine_sum(&FLAT_TOP, phase),
+        WindowType::Tukey => {
+            let taper = TUKEY_ALPHA * denom / 2.0;
+            let distance = (i as f32).min(denom - i as f32);
+            if distance >= taper {
+                1.0
+            } else {
+                HANN_A0 - HANN_A1 * (PI * distance / taper).cos()
+            }
+        }
+        WindowType::Rectangular => 1.0, // No window
+    }
+}
+
+/// Generalized cosine window `sum_k (-1)^k a_k cos(k * phase)`.
+fn cosine_sum(terms: &[f32], phase: f32) -> f32 {
+    terms
+        .iter()
+        .enumerate()
+        .map(|(k, &a)| {
+            let sign = if k.is_multiple_of(2) { 1.0 } else { -1.0 };
+            sign * a * (k as f32 * phase).cos()
+        })
+        .sum()
+}
+
+/// Modified Bessel function of the first kind, order zero.
+fn bessel_i0(x: f32) -> f32 {
+    let half_sq = (x / 2.0) * (x / 2.0);
+    let mut term = 1.0f32;
+    let mut sum = 1.0f32;
+    let mut k = 1.0f32;
+    while term > BESSEL_TOLERANCE * sum {
+        term *= half_sq / (k * k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Apply a Kaiser window with shape parameter `beta`.
+///
+/// # What
+/// Multiplies `input` by `I0(beta * sqrt(1 - r^2)) / I0(beta)`, where `r`
+/// runs from `-1` to `1` across the frame. `beta = 0` is rectangular; larger
+/// values trade a wider main lobe for lower sidelobes (about `8.6` matches
+/// Blackman).
+///
+/// # Why
+/// Unlike the fixed windows, Kaiser lets callers tune the leakage trade-off
+/// continuously, so it takes a parameter rather than a [`WindowType`].
+///
+/// # Panics
+/// Panics if `beta` is negative or not finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_window_kaiser(input: &[f32], beta: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        beta.is_finite() && beta >= 0.0,
+        "beta must be a non-negative finite number"
+    );
+    simd::multiply(input, &kaiser_coefficients(beta, input.len()))
+}
+
+/// Apply a Gaussian window with width `sigma`.
+///
+/// # What
+/// Multiplies `input` by `exp(-0.5 * (r / sigma)^2)`, where `r` runs from
+/// `-1` to `1` across the frame, so `sigma` is the standard deviation as a
+/// fraction of half the frame. Values around `0.4` reach about -14 dB at
+/// the edges; smaller values taper harder.
+///
+/// # Why
+/// A Gaussian has no sidelobes of its own and the best time-frequency
+/// localization, which makes it the usual choice for reassignment and
+/// Gabor-style displays.
+///
+/// # Panics
+/// Panics if `sigma` is not a positive finite number.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_window_gaussian(input: &[f32], sigma: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        sigma.is_finite() && sigma > 0.0,
+        "sigma must be a positive finite number"
+    );
+    simd::multiply(input, &gaussian_coefficients(sigma, input.len()))
+}
+
+/// Apply any window by name, with a shape parameter for those that take one.
+///
+/// # What
+/// `"kaiser"` uses `param` as `beta` ([`apply_window_kaiser`]) and
+/// `"gaussian"` as `sigma` ([`apply_window_gaussian`]); every name accepted
+/// by [`apply_window`] ignores it.
+///
+/// # Errors
+/// Returns [`DspError::UnknownWindow`] for an unrecognized name,
+/// [`DspError::InvalidParameter`] when `param` is out of range for a
+/// parametric window and [`DspError::NonFinite`] when `input` contains NaN
+/// or infinity.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_window_with_param(
+    input: &[f32],
+    window_type: &str,
+    param: f32,
+) -> Result<Vec<f32>, DspError> {
+    let coefficients = match window_type {
+        "kaiser" if param.is_finite() && param >= 0.0 => kaiser_coefficients(param, input.len()),
+        "kaiser" => {
+            return Err(DspError::InvalidParameter(
+                "beta must be a non-negative finite number",
+            ))
+        }
+        "gaussian" if param.is_finite() && param > 0.0 => gaussian_coefficients(param, input.len()),
+        "gaussian" => {
+            return Err(DspError::InvalidParameter(
+                "sigma must be a positive finite number",
+            ))
+        }
+        _ => {
+            let window = WindowType::from_str(window_type)?;
+            check_finite(input)?;
+            return Ok(window_samples(input, window));
+        }
+    };
+    check_finite(input)?;
+    Ok(simd::multiply(input, &coefficients))
+}
+
+/// Position of sample `i` of `n` on `-1..=1`.
+fn centered_position(i: usize, n: usize) -> f32 {
+    2.0 * i as f32 / (n as f32 - 1.0).max(1.0) - 1.0
+}
+
+/// The `n` coefficients of a Kaiser window with shape `beta`.
+fn kaiser_coefficients(beta: f32, n: usize) -> Vec<f32> {
+    let norm = bessel_i0(beta);
+    (0..n)
+        .map(|i| {
+            let r = centered_position(i, n);
+            bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / norm
+        })
+        .collect()
+}
+
+/// The `n` coefficients of a Gaussian window with width `sigma`.
+fn gaussian_coefficients(sigma: f32, n: usize) -> Vec<f32> {
+    (0..n)
+        .map(|i| {
+            let r = centered_position(i, n) / sigma;
+            (-0.5 * r * r).exp()
+        })
+        .collect()
+}
+
+/// Multiply `input` by the coefficients of `window`.
+fn window_samples(input: &[f32], window: WindowType) -> Vec<f32> {
+    let n = input.len();
+    if window == WindowType::Rectangular {
+        return input.to_vec();
+    }
+    simd::multiply(input, &cached_window(window, n))
+}
+
+/// The `n` coefficients of `window` on their own.
+fn window_coefficients(window: WindowType, n: usize) -> Vec<f32> {
+    window_samples(&vec![1.0; n], window)
+}
+
+/// Mean of `coefficients`, or `0` when empty.
+fn mean_coefficient(coefficients: &[f32]) -> f32 {
+    if coefficients.is_empty() {
+        return 0.0;
+    }
+    coefficients.iter().sum::<f32>() / coefficients.len() as f32
+}
+
+/// Mean of the squared `coefficients`, or `0` when empty.
+fn mean_square_coefficient(coefficients: &[f32]) -> f32 {
+    if coefficients.is_empty() {
+        return 0.0;
+    }
+    coefficients.iter().map(|w| w * w).sum::<f32>() / coefficients.len() as f32
+}
+
+/// Coherent gain of an `n`-point window: the mean of its coefficients.
+///
+/// # Why
+/// A windowed sinusoid's peak bin is scaled by this factor (about `0.5` for
+/// Hann); dividing by it restores the true amplitude. Unknown window names
+/// panic, matching [`apply_window`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn window_coherent_gain(window_type: &str, n: usize) -> f32 {
+    let window = parse_window(window_type);
+    mean_coefficient(&window_coefficients(window, n))
+}
+
+/// Apply a window scaled to unit coherent gain.
+///
+/// # What
+/// Multiplies `input` by the window coefficients divided by their mean
+/// ([`window_coherent_gain`]), so the peak bin of a windowed sinusoid keeps
+/// the amplitude it has without a window. Empty input yields an empty
+/// result. Windows whose coherent gain is zero (such as Hann of one or two
+/// samples, which is all zeros) cannot be normalized and are applied
+/// unscaled.
+///
+/// # Why
+/// Calibrated level meters read the tone level straight from the peak bin;
+/// with a plain Hann window it would read about 6 dB low.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or the window name is
+/// unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_window_normalized(input: &[f32], window_type: &str) -> Vec<f32> {
+    validate_finite(input);
+    let window = parse_window(window_type);
+    let coefficients = cached_window(window, input.len());
+    let gain = mean_coefficient(&coefficients);
+    let scale = if gain.abs() > EPSILON {
+        1.0 / gain
+    } else {
+        1.0
+    };
+    input
+        .iter()
+        .zip(coefficients.iter())
+        .map(|(&x, &w)| x * w * scale)
+        .collect()
+}
+
+/// Energy gain of an `n`-point window: the mean of its squared coefficients.
+///
+/// # Why
+/// Broadband power is scaled by this factor (about `0.375` for Hann);
+/// noise-floor and PSD estimates divide by it.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn window_energy_gain(window_type: &str, n: usize) -> f32 {
+    let window = parse_window(window_type);
+    mean_square_coefficient(&window_coefficients(window, n))
+}
+
+/// A window's coefficients together with its normalization gains.
+///
+/// # What
+/// Returned by [`window_info`]; all values are computed once on creation.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    coefficients: Vec<f32>,
+    coherent_gain: f32,
+    energy_gain: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl WindowInfo {
+    /// Copy of the window coefficients.
+    pub fn coefficients(&self) -> Vec<f32> {
+        self.coefficients.clone()
+    }
+
+    /// Mean coefficient, see [`window_coherent_gain`].
+    pub fn coherent_gain(&self) -> f32 {
+        self.coherent_gain
+    }
+
+    /// Mean squared coefficient, see [`window_energy_gain`].
+    pub fn energy_gain(&self) -> f32 {
+        self.energy_gain
+    }
+}
+
+/// Window coefficients and gains in a single call.
+///
+/// Unknown window names panic, matching
+/// [`apply_window`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn window_info(window_type: &str, length: usize) -> WindowInfo {
+    let window = parse_window(window_type);
+    let coefficients = window_coefficients(window, length);
+    WindowInfo {
+        coherent_gain: mean_coefficient(&coefficients),
+        energy_gain: mean_square_coefficient(&coefficients),
+        coefficients,
+    }
+}
+
+/// Coefficients of a Princen-Bradley (power-complementary) window.
+///
+/// # What
+/// `"vorbis"` selects the Vorbis power-complementary window; any other name
+/// selects the sine window `sin(pi * (i + 0.5) / n)`. Both satisfy
+/// `w[i]^2 + w[i + n/2]^2 == 1` for even `n`.
+fn pr_window_coefficients(window_type: &str, n: usize) -> Vec<f32> {
+    let denom = n as f32;
+    (0..n)
+        .map(|i| {
+            let sine = (PI * (i as f32 + 0.5) / denom).sin();
+            match window_type {
+                "vorbis" => (0.5 * PI * sine * sine).sin(),
+                _ => sine,
+            }
+        })
+        .collect()
+}
+
+/// Apply a perfect-reconstruction window for MDCT-style filterbanks.
+///
+/// # What
+/// Multiplies `input` by the sine window (default) or the Vorbis window
+/// (`"vorbis"`). At 50% overlap the squared left and right halves sum to one
+/// (the Princen-Bradley condition), so analysis followed by synthesis with
+/// the same window reconstructs the signal exactly.
+///
+/// # Why
+/// The tapers used by [`apply_window`] are designed for leakage control and
+/// do not satisfy the Princen-Bradley condition.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_pr_window(input: &[f32], window_type: &str) -> Vec<f32> {
+    validate_finite(input);
+    let coefficients = pr_window_coefficients(window_type, input.len());
+    input
+        .iter()
+        .zip(coefficients)
+        .map(|(&x, w)| x * w)
+        .collect()
+}
+
+/// Compute STFT frame: window + FFT + magnitude. What: Complete STFT pipeline in WASM.
+/// Why: Single call reduces JS↔WASM boundary crossings for performance.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frame(input: &[f32], window_type: &str, reference: f32) -> Vec<f32> {
+    validate_finite(input);
+    let windowed = apply_window_unchecked(input, window_type);
+    magnitude_dbfs_unchecked(&windowed, reference)
+}
+
+/// [`stft_frame`] of `input` zero-padded to a longer FFT.
+///
+/// # What
+/// Windows the `M = input.len()` samples with a window of length `M`, then
+/// places them in a zero-filled frame of `fft_size` samples: at the start
+/// for `pad_mode = "right"`, or in the middle (starting at
+/// `(fft_size - M) / 2`) for `"center"`. Returns the `fft_size` dBFS bins
+/// of that frame in the layout of [`stft_frame`]; bin `k` lies at
+/// `k * sample_rate / fft_size` Hz. `fft_size == 0` picks the next power of
+/// two of at least `M`. The two modes differ by a circular shift, so their
+/// magnitudes agree; the mode only changes the phase reference.
+///
+/// # Why
+/// Padding in JavaScript before [`stft_frame`] windows the zeros too,
+/// which shortens the effective window and widens every peak.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, the window name or
+/// `pad_mode` is unknown, or a non-zero `fft_size` is shorter than `input`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frame_padded(
+    input: &[f32],
+    window_type: &str,
+    reference: f32,
+    fft_size: usize,
+    pad_mode: &str,
+) -> Vec<f32> {
+    validate_finite(input);
+    let fft_size = match fft_size {
+        0 => input.len().next_power_of_two(),
+        n => n,
+    };
+    assert!(
+        fft_size >= input.len(),
+        "fft_size must be at least the input length"
+    );
+    let offset = match pad_mode {
+        "right" => 0,
+        "center" => (fft_size - input.len()) / 2,
+        _ => panic!("unknown pad_mode: {pad_mode}"),
+    };
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let mut frame = vec![0.0f32; fft_size];
+    frame[offset..offset + input.len()]
+        .copy_from_slice(&apply_window_unchecked(input, window_type));
+    magnitude_dbfs_unchecked(&frame, reference)
+}
+
+/// [`stft_frame`] that reports invalid input instead of panicking.
+///
+/// # Errors
+/// Returns [`DspError::UnknownWindow`] for an unrecognized window name,
+/// [`DspError::EmptyInput`] for an empty frame and [`DspError::NonFinite`]
+/// when `input` contains NaN or infinity.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn try_stft_frame(
+    input: &[f32],
+    window_type: &str,
+    reference: f32,
+) -> Result<Vec<f32>, DspError> {
+    let window = WindowType::from_str(window_type)?;
+    check_frame(input)?;
+    Ok(magnitude_dbfs_unchecked(
+        &window_samples(input, window),
+        reference,
+    ))
+}
+
+/// Compute an STFT frame keeping the phase of every bin.
+///
+/// # What
+/// Returns `n` bins interleaved as `[mag_db0, phase0, mag_db1, phase1,
+/// ...]`. Magnitudes are exactly those of [`stft_frame`]; phases are
+/// `im.atan2(re)` in radians, wrapped to `(-pi, pi]`.
+///
+/// # Why
+/// Phase-vocoder style resynthesis needs the phase that [`stft_frame`]
+/// discards, and computing both in one pass keeps the single-call
+/// performance benefit.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frame_complex(input: &[f32], window_type: &str, reference: f32) -> Vec<f32> {
+    validate_finite(input);
+    let n = input.len();
+    let windowed = apply_window_unchecked(input, window_type);
+    let half = rfft_unchecked(&windowed);
+    let mut mags: Vec<f32> = half.iter().map(|c| c.norm()).collect();
+    let mut phases: Vec<f32> = half.iter().map(|c| spectral::wrap_phase(c.arg())).collect();
+    // Upper bins are conjugates of the lower ones.
+    for k in half.len()..n {
+        mags.push(mags[n - k]);
+        phases.push(spectral::wrap_phase(-phases[n - k]));
+    }
+    linear_to_dbfs_in_place(&mut mags, reference);
+    mags.iter()
+        .zip(&phases)
+        .flat_map(|(&m, &p)| [m, p])
+        .collect()
+}
+
+/// Half-spectrum [`stft_frame_complex`]: magnitude and phase of bins
+/// `0..=n/2` only.
+///
+/// # What
+/// Returns `n/2 + 1` pairs `[mag_db0, phase0, mag_db1, phase1, ...]`, equal
+/// to the first `n/2 + 1` pairs of [`stft_frame_complex`]. Feed the phases
+/// of consecutive frames to [`instantaneous_frequency`].
+///
+/// # Why
+/// The upper bins of a real frame only mirror the lower ones, so phase
+/// vocoders and hover readouts need no more than this half.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frame_polar(input: &[f32], window_type: &str, reference: f32) -> Vec<f32> {
+    validate_finite(input);
+    let half = rfft_unchecked(&apply_window_unchecked(input, window_type));
+    let mut mags = simd::norms(&half);
+    linear_to_dbfs_in_place(&mut mags, reference);
+    mags.iter()
+        .zip(&half)
+        .flat_map(|(&m, c)| [m, spectral::wrap_phase(c.arg())])
+        .collect()
+}
+
+/// Compute a half-spectrum STFT frame with frequency-reassigned energy.
+///
+/// # What
+/// Every bin's energy is moved to the frequency of the component that
+/// produced it and accumulated there, so a sinusoid between two bins shows
+/// up as one sharp line instead of a window-wide smear. Returns the
+/// `n/2 + 1` bins `0..=n/2` in dB relative to `reference`; bins that
+/// received no energy sit at the dB floor.
+///
+/// # How
+/// Besides the windowed spectrum `X_h` a second FFT with the window's
+/// derivative `dh` gives `X_dh`; the reassigned bin is
+/// `k - n / (2π) · Im(X_dh · conj(X_h)) / |X_h|^2`, rounded to the nearest
+/// bin. The derivative is taken by central differences, so any window that
+/// tapers to zero works. A single frame has no neighbours to rebin time
+/// into, so only frequency is reassigned.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or the window name is
+/// unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frame_reassigned(input: &[f32], window_type: &str, reference: f32) -> Vec<f32> {
+    validate_finite(input);
     let n = input.len();
     if n == 0 {
         return Vec::new();
     }
+    let window = cached_window(parse_window(window_type), n);
+    let at = |i: isize| {
+        if (0..n as isize).contains(&i) {
+            window[i as usize]
+        } else {
+            0.0
+        }
+    };
+    let derivative: Vec<f32> = (0..n as isize)
+        .map(|i| 0.5 * (at(i + 1) - at(i - 1)))
+        .collect();
+    let windowed: Vec<f32> = input
+        .iter()
+        .zip(window.iter())
+        .map(|(&x, &w)| x * w)
+        .collect();
+    let differentiated: Vec<f32> = input
+        .iter()
+        .zip(&derivative)
+        .map(|(&x, &d)| x * d)
+        .collect();
+    let spectrum = rfft_unchecked(&windowed);
+    let spectrum_d = rfft_unchecked(&differentiated);
 
-    // Convert real input into complex numbers required by `rustfft`.
-    let mut buffer: Vec<Complex32> = input.iter().map(|&x| Complex32::new(x, 0.0)).collect();
+    let mut power = vec![0.0f32; spectrum.len()];
+    let bins_per_radian = n as f32 / TWO_PI;
+    for (k, (x, xd)) in spectrum.iter().zip(&spectrum_d).enumerate() {
+        let energy = x.norm_sqr();
+        if energy <= EPSILON {
+            continue;
+        }
+        let shift = (xd * x.conj()).im / energy;
+        let target = (k as f32 - bins_per_radian * shift).round();
+        if (0.0..power.len() as f32).contains(&target) {
+            power[target as usize] += energy;
+        }
+    }
+    let mut mags: Vec<f32> = power.iter().map(|p| p.sqrt()).collect();
+    linear_to_dbfs_in_place(&mut mags, reference);
+    mags
+}
 
-    // Plan and execute the FFT using the shared planner.
-    // Acquire planner lock only for planning to minimize contention.
-    let fft = {
-        let mut planner = planner().lock().expect("planner lock");
-        planner.plan_fft_forward(n)
-    };
-    fft.process(&mut buffer);
+/// Compute an STFT frame with a window selected by [`WindowType`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frame_typed(input: &[f32], window_type: WindowType, reference: f32) -> Vec<f32> {
+    validate_finite(input);
+    let windowed = window_samples(input, window_type);
+    magnitude_dbfs_unchecked(&windowed, reference)
+}
 
-    // Flatten complex results into interleaved real/imaginary pairs.
-    let mut output = Vec::with_capacity(2 * n);
-    for c in buffer {
-        output.push(c.re);
-        output.push(c.im);
+/// Compute magnitude spectrum in dBFS from a real block. Windowing is expected to be done by caller.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn magnitude_dbfs(input: &[f32], reference: f32) -> Vec<f32> {
+    validate_finite(input);
+    magnitude_dbfs_unchecked(input, reference)
+}
+
+/// [`magnitude_dbfs`] with every value raised to at least `floor_db`.
+///
+/// # Why
+/// Silent bins land near the `EPSILON` floor at about -240 dB, which
+/// stretches a color map over range nobody wants to see. Pinning them to a
+/// display floor keeps the map focused on the audible range.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `floor_db` is not
+/// finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn magnitude_dbfs_clamped(input: &[f32], reference: f32, floor_db: f32) -> Vec<f32> {
+    validate_finite(input);
+    assert!(floor_db.is_finite(), "floor_db must be finite");
+    let mut mags = magnitude_dbfs_unchecked(input, reference);
+    for m in &mut mags {
+        *m = m.max(floor_db);
     }
-    output
+    mags
 }
 
-/// Apply window function to input buffer. What: Multiplies input by window coefficients.
-/// Why: Reduces spectral leakage in FFT analysis.
-#[wasm_bindgen]
-pub fn apply_window(input: &[f32], window_type: &str) -> Vec<f32> {
+/// [`magnitude_dbfs`] quantized to 8-bit codes for UNORM8 textures.
+///
+/// # What
+/// Returns one byte per bin in the [`magnitude_dbfs`] layout: `0` at or
+/// below `min_db`, `255` at or above `max_db` and linear steps in
+/// between, rounded to the nearest code. A range with `max_db <= min_db`
+/// maps everything to `0`.
+///
+/// # Why
+/// Quantizing in the dB conversion loop replaces a per-pixel JavaScript
+/// pass and sends a quarter of the bytes of an `f32` frame across the
+/// WASM boundary.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `min_db` or `max_db`
+/// is not finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn magnitude_quantized(input: &[f32], reference: f32, min_db: f32, max_db: f32) -> Vec<u8> {
     validate_finite(input);
-    apply_window_unchecked(input, window_type)
+    validate_finite(&[min_db, max_db]);
+    let safe_ref = reference.max(EPSILON);
+    magnitudes_unchecked(input)
+        .iter()
+        .map(|&m| {
+            let db = DB_SCALE * (m / safe_ref).log10();
+            (display::normalize_db(db, min_db, max_db) * display::U8_MAX).round() as u8
+        })
+        .collect()
 }
 
-/// Apply window coefficients without validating `input`.
-fn apply_window_unchecked(input: &[f32], window_type: &str) -> Vec<f32> {
+/// Half-spectrum [`magnitude_dbfs`] writing into a caller-provided buffer.
+///
+/// # What
+/// Writes the dBFS magnitudes of bins `0..=n/2`, identical to the first
+/// `n/2 + 1` values of [`magnitude_dbfs`], to the start of `out` and
+/// returns how many were written.
+///
+/// # Why
+/// The upper bins mirror the lower ones, and writing into a reused buffer
+/// avoids a fresh allocation per frame.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `out` holds fewer
+/// than `n/2 + 1` values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn magnitude_dbfs_into(input: &[f32], reference: f32, out: &mut [f32]) -> usize {
+    validate_finite(input);
     let n = input.len();
-    let mut output = vec![0.0f32; n];
-    let denom = (n as f32 - 1.0).max(1.0);
-    match window_type {
-        "hann" => {
-            for (i, &x) in input.iter().enumerate() {
-                let phase = TWO_PI * i as f32 / denom;
-                let w = HANN_A0 - HANN_A1 * phase.cos();
-                output[i] = x * w;
-            }
-        }
-        "hamming" => {
-            for (i, &x) in input.iter().enumerate() {
-                let phase = TWO_PI * i as f32 / denom;
-                let w = HAMMING_ALPHA - HAMMING_BETA * phase.cos();
-                output[i] = x * w;
-            }
+    if n == 0 {
+        return 0;
+    }
+    let bins = n / 2 + 1;
+    assert!(out.len() >= bins, "out lacks space for n/2 + 1 bins");
+    INTO_BUFFERS.with(|cell| {
+        let (buffer, scratch) = &mut *cell.borrow_mut();
+        buffer.clear();
+        let even = n.is_multiple_of(2);
+        if even {
+            let pairs = input.chunks_exact(2);
+            buffer.extend(pairs.map(|pair| Complex32::new(pair[0], pair[1])));
+        } else {
+            buffer.extend(input.iter().map(|&x| Complex32::new(x, 0.0)));
         }
-        "blackman" => {
-            for (i, &x) in input.iter().enumerate() {
-                let phase = TWO_PI * i as f32 / denom;
-                let w = BLACKMAN_A0 - BLACKMAN_A1 * phase.cos() + BLACKMAN_A2 * (2.0 * phase).cos();
-                output[i] = x * w;
-            }
+        let fft = plan_forward(buffer.len());
+        scratch.resize(fft.get_inplace_scratch_len(), Complex32::new(0.0, 0.0));
+        fft.process_with_scratch(buffer, scratch);
+        for (k, m) in out[..bins].iter_mut().enumerate() {
+            *m = if even {
+                unpack_rfft_bin(buffer, k, rfft_twiddle(k, n)).norm()
+            } else {
+                buffer[k].norm()
+            };
         }
-        _ => output.copy_from_slice(input), // No window
-    }
-    output
+    });
+    linear_to_dbfs_in_place(&mut out[..bins], reference);
+    bins
 }
 
-/// Compute STFT frame: window + FFT + magnitude. What: Complete STFT pipeline in WASM.
-/// Why: Single call reduces JS↔WASM boundary crossings for performance.
-#[wasm_bindgen]
-pub fn stft_frame(input: &[f32], window_type: &str, reference: f32) -> Vec<f32> {
+/// [`magnitude_dbfs`] that reports non-finite input instead of panicking.
+///
+/// # Errors
+/// Returns [`DspError::EmptyInput`] for an empty block and
+/// [`DspError::NonFinite`] when `input` contains NaN or infinity.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn try_magnitude_dbfs(input: &[f32], reference: f32) -> Result<Vec<f32>, DspError> {
+    check_frame(input)?;
+    Ok(magnitude_dbfs_unchecked(input, reference))
+}
+
+/// Compute the linear magnitude of all `n` FFT bins of a real block.
+///
+/// Same layout as [`magnitude_dbfs`], without the dB conversion.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn magnitude_linear(input: &[f32]) -> Vec<f32> {
     validate_finite(input);
-    let windowed = apply_window_unchecked(input, window_type);
-    magnitude_dbfs_unchecked(&windowed, reference)
+    magnitudes_unchecked(input)
 }
 
-/// Compute magnitude spectrum in dBFS from a real block. Windowing is expected to be done by caller.
-#[wasm_bindgen]
-pub fn magnitude_dbfs(input: &[f32], reference: f32) -> Vec<f32> {
+/// Compute the power `re² + im²` of the non-redundant bins `0..=n/2`.
+///
+/// # Why
+/// Power displays that apply their own log mapping need neither the square
+/// root nor the dB conversion, and only half the bins of a real signal.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn power_spectrum_half(input: &[f32]) -> Vec<f32> {
     validate_finite(input);
-    magnitude_dbfs_unchecked(input, reference)
+    rfft_unchecked(input).iter().map(|c| c.norm_sqr()).collect()
 }
 
 /// Compute magnitude spectrum without validating `input`.
 fn magnitude_dbfs_unchecked(input: &[f32], reference: f32) -> Vec<f32> {
-    let spec = fft_real_unchecked(input);
-    let mut mags = Vec::with_capacity(spec.len() / 2);
-    let mut i = 0usize;
-    let safe_ref = reference.max(EPSILON);
-    while i + 1 < spec.len() {
-        let re = spec[i];
-        let im = spec[i + 1];
-        let mag = (re * re + im * im).sqrt();
-        let db = DB_SCALE * (mag / safe_ref).log10();
-        mags.push(db);
-        i += 2;
+    let mut mags = magnitudes_unchecked(input);
+    linear_to_dbfs_in_place(&mut mags, reference);
+    mags
+}
+
+/// Linear magnitudes of all `n` FFT bins of `input`.
+///
+/// # How
+/// Computes the half spectrum with [`rfft`] and mirrors it, since
+/// `|X[n - k]| == |X[k]|` for real input.
+fn magnitudes_unchecked(input: &[f32]) -> Vec<f32> {
+    let n = input.len();
+    let mut mags = half_spectrum_magnitudes(input);
+    for k in mags.len()..n {
+        mags.push(mags[n - k]);
     }
     mags
 }
 
+/// Convert linear magnitudes to dB relative to `reference`.
+fn linear_to_dbfs_in_place(mags: &mut [f32], reference: f32) {
+    simd::to_db_in_place(mags, DB_SCALE, reference.max(EPSILON));
+}
+
 // -----------------------------------------------------------------------------
 // Tests
 // -----------------------------------------------------------------------------
@@ -189,6 +1698,7 @@ fn magnitude_dbfs_unchecked(input: &[f32], reference: f32) -> Vec<f32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{allocations_during, noise};
     use std::time::Instant;
 
     /// Tolerance for floating point comparisons in tests.
@@ -245,6 +1755,478 @@ mod tests {
         }
     }
 
+    /// Scaling and bias are applied to every interleaved component.
+    #[test]
+    fn fft_real_scaled_applies_scale_and_bias() {
+        let data: Vec<f32> = (0..16).map(|i| (i as f32 * 0.5).sin()).collect();
+        let plain = fft_real(&data);
+        assert_eq!(fft_real_scaled(&data, 1.0, 0.0), plain);
+
+        let (scale, bias) = (0.25, 0.5);
+        let scaled = fft_real_scaled(&data, scale, bias);
+        for (s, p) in scaled.iter().zip(plain.iter()) {
+            assert!((s - (p * scale + bias)).abs() < TOLERANCE, "{s} vs {p}");
+        }
+    }
+
+    /// The padded layout inserts two zeros after every bin.
+    #[test]
+    fn fft_real_padded_layout_pads_to_16_bytes() {
+        let data: Vec<f32> = (0..8).map(|i| (i as f32 * 0.9).cos()).collect();
+        let plain = fft_real(&data);
+        assert_eq!(fft_real_padded_layout(&data, false), plain);
+
+        let padded = fft_real_padded_layout(&data, true);
+        assert_eq!(padded.len(), 2 * plain.len());
+        for (p, c) in padded.chunks_exact(4).zip(plain.chunks_exact(2)) {
+            assert_eq!(p, &[c[0], c[1], 0.0, 0.0]);
+        }
+    }
+
+    /// Half-spectrum power equals the squared linear magnitudes.
+    #[test]
+    fn power_spectrum_half_squares_linear_magnitudes() {
+        let data: Vec<f32> = (0..32).map(|i| (i as f32 * 0.37).sin() + 0.2).collect();
+        let mags = magnitude_linear(&data);
+        assert_eq!(mags.len(), data.len());
+        let power = power_spectrum_half(&data);
+        assert_eq!(power.len(), data.len() / 2 + 1);
+        for (p, m) in power.iter().zip(&mags) {
+            assert!(
+                (p - m * m).abs() < TOLERANCE * (m * m).max(1.0),
+                "{p} vs {m}"
+            );
+        }
+    }
+
+    /// The fused path matches windowing then transforming, with fewer
+    /// allocations.
+    #[test]
+    fn windowed_fft_matches_and_allocates_less() {
+        let data: Vec<f32> = (0..PERF_SIZE).map(|i| (i as f32 * 0.01).sin()).collect();
+        let separate = fft_real(&apply_window(&data, "blackman"));
+        let fused = windowed_fft(&data, "blackman");
+        for (a, b) in fused.iter().zip(&separate) {
+            assert!((a - b).abs() < TOLERANCE, "{a} vs {b}");
+        }
+
+        let separate_allocs = allocations_during(|| {
+            std::hint::black_box(fft_real(&apply_window(&data, "hann")));
+        });
+        let fused_allocs = allocations_during(|| {
+            std::hint::black_box(windowed_fft(&data, "hann"));
+        });
+        assert!(
+            fused_allocs < separate_allocs,
+            "fused {fused_allocs} >= separate {separate_allocs}"
+        );
+    }
+
+    /// Quiet and loud copies of a tone end up at the same RMS level.
+    #[test]
+    fn auto_gain_equalizes_levels() {
+        let tone: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.05).sin()).collect();
+        let rms_db = |x: &[f32]| {
+            let ms = x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32;
+            10.0 * ms.log10()
+        };
+        let quiet: Vec<f32> = tone.iter().map(|v| v * 0.01).collect();
+        let loud: Vec<f32> = tone.iter().map(|v| v * 0.9).collect();
+        for block in [&quiet, &loud] {
+            let level = rms_db(&auto_gain(block, -20.0));
+            assert!((level + 20.0).abs() < TOLERANCE, "{level}");
+        }
+
+        // Near silence is limited to the maximum gain.
+        let faint: Vec<f32> = tone.iter().map(|v| v * 1e-6).collect();
+        let boosted = auto_gain(&faint, -20.0);
+        let gain_db = rms_db(&boosted) - rms_db(&faint);
+        assert!((gain_db - MAX_AUTO_GAIN_DB).abs() < TOLERANCE, "{gain_db}");
+    }
+
+    /// Every public entry point maps empty input to empty output.
+    #[test]
+    fn empty_input_yields_empty_output() {
+        let e: &[f32] = &[];
+        assert!(sanitize(e, 0.0).is_empty());
+        assert!(auto_gain(e, -20.0).is_empty());
+        assert!(fft_real(e).is_empty());
+        assert!(rfft(e).is_empty());
+        assert!(ifft_real(e, 0).is_empty());
+        assert!(windowed_fft(e, "hann").is_empty());
+        assert!(fft_real_scaled(e, 2.0, 1.0).is_empty());
+        assert!(fft_real_padded_layout(e, true).is_empty());
+        for window in ["hann", "hamming", "blackman", "rect"] {
+            assert!(apply_window(e, window).is_empty());
+            assert!(stft_frame(e, window, 1.0).is_empty());
+        }
+        assert!(apply_window_checked(e, "hann").unwrap().is_empty());
+        assert!(apply_window_typed(e, WindowType::Blackman).is_empty());
+        assert!(stft_frame_typed(e, WindowType::Hann, 1.0).is_empty());
+        assert!(apply_pr_window(e, "sine").is_empty());
+        assert!(magnitude_dbfs(e, 1.0).is_empty());
+        assert!(magnitude_linear(e).is_empty());
+        assert!(power_spectrum_half(e).is_empty());
+
+        assert!(envelope_spectrum(e, 8000.0).is_empty());
+        assert!(capon_spectrum(e, 4, 16, 8000.0).is_empty());
+        assert!(lpc_envelope(e, 4, 16).is_empty());
+        assert!(two_band_spectrum(e, 500.0, 8000.0, 64, 64, "hann").is_empty());
+        assert!(dft_at_frequencies(e, &[100.0, 200.0], 8000.0).is_empty());
+        assert!(modulation_spectrum(e, 64, 16, "hann", 8000.0, 4).is_empty());
+
+        assert!(db_percentiles(e, &[5.0, 95.0]).is_empty());
+        assert!(auto_contrast(e, 5.0, 95.0).is_empty());
+        assert!(compand_u8(e, -100.0, 0.0, 8.0).is_empty());
+        assert!(expand_u8(&[], -100.0, 0.0, 8.0).is_empty());
+        assert!(colorize(e, -100.0, 0.0, "viridis").is_empty());
+        assert!(export_png(e, 0, 0, "viridis", -100.0, 0.0).is_empty());
+        assert!(stft_frame_padded(e, "hann", 1.0, 0, "right").is_empty());
+        assert!(stft_frame_f64(&[], "hann", 1.0).is_empty());
+        assert!(fft_real_f64(&[]).is_empty());
+        assert!(stft_frame_polar(e, "hann", 1.0).is_empty());
+        assert!(instantaneous_frequency(e, e, 1, 48_000.0).is_empty());
+        assert!(Preprocessor::new(8000.0, true, 0.5, 50.0)
+            .process(e)
+            .is_empty());
+        assert!(rebin_log(e, 48_000.0, 64, 20.0, 20_000.0).is_empty());
+
+        let mel = mel_spectrogram(e, 64, 16, "hann", 8000.0, 8, 0.0, 4000.0);
+        assert_eq!((mel.n_frames(), mel.values().len()), (0, 0));
+        assert!(mel_spectrogram_frame(e, "hann", 8000.0, 8).is_empty());
+        assert!(mel_bands(e, 8000.0, 8, 0.0, 0.0).is_empty());
+        assert!(mfcc(e, 8000.0, 8, 4).is_empty());
+        assert!(log_compress(e, 1.0).is_empty());
+
+        assert!(complex_ratio(e, e, 1e-6).is_empty());
+        assert!(average_complex(e, 1).is_empty());
+        assert!(average_magnitude(e, 1).is_empty());
+        assert!(interchannel_phase(e, e).is_empty());
+        assert_eq!(total_energy(e), 0.0);
+        assert_eq!(spectral_energy(e), 0.0);
+        assert_eq!(spectral_slope(e, 8000.0, 64), 0.0);
+
+        assert!(stft_frames(e, 64, 16, "hann", 1.0, true).is_empty());
+        assert!(fft_complex(e).is_empty());
+        assert!(stft_frame_reassigned(e, "hann", 1.0).is_empty());
+        assert!(magnitude_quantized(e, 1.0, -60.0, 0.0).is_empty());
+        assert!(chromagram(e, 8000.0, 440.0).is_empty());
+        assert!(stft_frame_with_options(e, "hann", &mut SpectrumOptions::default()).is_empty());
+        assert!(magnitude_dba(e, 1.0, 8000.0).is_empty());
+        assert!(welch_psd(e, 64, 16, "hann", 8000.0).is_empty());
+        assert!(spectral_features(e, 8000.0).is_empty());
+        assert!(istft_signal(e, 64, 16, "hann").is_empty());
+        assert!(istft(e, 64, 16, "hann").is_empty());
+        assert!(fft_real_padded(&[1.0], 0).is_empty());
+        assert!(magnitude_dbfs_clamped(e, 1.0, -90.0).is_empty());
+        assert!(apply_window_normalized(e, "hann").is_empty());
+        assert!(compute_spectrogram_sparse(e, 64, 16, "hann", 1.0, -120.0).is_empty());
+        assert_eq!(stft_all(e, 64, 16, "hann", 1.0).n_frames(), 0);
+        assert!(stft_multichannel(e, 2, 64, 16, "hann", 1.0, false).is_empty());
+        assert!(remove_stationary(e, 0, 0).is_empty());
+        assert!(time_stretch(e, 1.5, 64, 16, "hann").is_empty());
+        assert!(pitch_shift(e, 3.0, 64, 16, "hann").is_empty());
+
+        assert!(StftProcessor::new(64, 16, "hann", 1.0).push(e).is_empty());
+        assert!(SpectrumSmoother::new(0.5).process(e).is_empty());
+        assert!(FirConvolver::new(&[1.0, 0.5], 0).process(e).is_empty());
+        assert!(HumRemover::new(50.0, 8000.0, 3, 30.0).process(e).is_empty());
+    }
+
+    /// Padding adds bins but keeps the peak at the same frequency.
+    #[test]
+    fn padded_fft_keeps_peak_frequency() {
+        let (n, padded, cycles) = (300, 1024, 25.0);
+        let tone: Vec<f32> = (0..n)
+            .map(|i| (TWO_PI * cycles * i as f32 / n as f32).cos())
+            .collect();
+        let peak_hz = |spec: &[f32], size: usize| {
+            let mags: Vec<f32> = spec.chunks_exact(2).map(|c| c[0].hypot(c[1])).collect();
+            let peak = (0..mags.len())
+                .max_by(|&a, &b| mags[a].total_cmp(&mags[b]))
+                .unwrap();
+            peak as f32 / size as f32
+        };
+        let spec = fft_real_padded(&tone, padded);
+        assert_eq!(spec.len(), 2 * (padded / 2 + 1));
+        let bin_width = 1.0 / padded as f32;
+        assert!((peak_hz(&spec, padded) - peak_hz(&fft_real(&tone), n)).abs() <= bin_width);
+        assert_eq!(fft_real_padded(&tone, 64), fft_real(&tone[..64]));
+    }
+
+    /// The `_into` variants match the allocating versions without
+    /// allocating once warmed up.
+    #[test]
+    fn into_variants_match_allocating_versions() {
+        for n in [64, 75] {
+            let data = noise(n, 17);
+            let mut out = vec![0.0f32; 2 * n + 3];
+
+            let len = 2 * (n / 2 + 1);
+            assert_eq!(fft_real_into(&data, &mut out), len);
+            assert_eq!(out[..len], fft_real(&data)[..]);
+            assert_eq!(
+                allocations_during(|| {
+                    fft_real_into(&data, &mut out);
+                }),
+                0
+            );
+
+            let bins = magnitude_dbfs_into(&data, 1.0, &mut out);
+            assert_eq!(bins, n / 2 + 1);
+            assert_eq!(out[..bins], magnitude_dbfs(&data, 1.0)[..bins]);
+            assert_eq!(
+                allocations_during(|| {
+                    magnitude_dbfs_into(&data, 1.0, &mut out);
+                }),
+                0
+            );
+        }
+    }
+
+    /// An output buffer that is too small is rejected.
+    #[test]
+    #[should_panic(expected = "out lacks space for 2 * (n/2 + 1) values")]
+    fn fft_real_into_rejects_small_buffer() {
+        fft_real_into(&[1.0, 2.0, 3.0], &mut [0.0; 3]);
+    }
+
+    /// Reassignment gathers a between-bins tone into its nearest bin.
+    #[test]
+    fn reassigned_frame_sharpens_tone() {
+        let n = 512;
+        let tone: Vec<f32> = (0..n)
+            .map(|i| (TWO_PI * 40.3 * i as f32 / n as f32).sin())
+            .collect();
+        let share_in_peak = |db: &[f32]| {
+            let power: Vec<f32> = db.iter().map(|&d| 10f32.powf(d / 10.0)).collect();
+            let peak = power.iter().copied().fold(0.0, f32::max);
+            peak / power.iter().sum::<f32>()
+        };
+        let plain = stft_frame(&tone, "hann", 1.0);
+        let reassigned = stft_frame_reassigned(&tone, "hann", 1.0);
+        assert_eq!(reassigned.len(), n / 2 + 1);
+        let peak = (0..reassigned.len())
+            .max_by(|&a, &b| reassigned[a].total_cmp(&reassigned[b]))
+            .unwrap();
+        assert_eq!(peak, 40);
+        assert!(share_in_peak(&reassigned) > 0.9);
+        assert!(share_in_peak(&plain[..n / 2 + 1]) < 0.6);
+    }
+
+    /// Padding windows only the real samples, both modes give the same
+    /// magnitudes, and the finer grid puts the peak at the tone frequency.
+    #[test]
+    fn padded_frame_windows_only_input() {
+        let tone: Vec<f32> = (0..100)
+            .map(|i| (TWO_PI * 0.123 * i as f32).sin())
+            .collect();
+        let right = stft_frame_padded(&tone, "hann", 1.0, 0, "right");
+        let center = stft_frame_padded(&tone, "hann", 1.0, 1024, "center");
+        assert_eq!(right.len(), 128);
+        assert_eq!(center.len(), 1024);
+
+        let mut manual = apply_window(&tone, "hann");
+        manual.resize(128, 0.0);
+        let expected = magnitude_dbfs(&manual, 1.0);
+        for (a, b) in right.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-4);
+        }
+        let mut shifted = vec![0.0; 1024];
+        shifted[462..562].copy_from_slice(&apply_window(&tone, "hann"));
+        let shifted = magnitude_dbfs(&shifted, 1.0);
+        let right = stft_frame_padded(&tone, "hann", 1.0, 1024, "right");
+        let peak = (0..512)
+            .max_by(|&a, &b| right[a].total_cmp(&right[b]))
+            .unwrap();
+        assert_eq!(peak, 126);
+        // Compare linear amplitudes; dB in the sidelobe nulls is noisy.
+        let linear = |db: f32| 10f32.powf(db / DB_SCALE);
+        for ((&r, &c), &e) in right.iter().zip(&center).zip(&shifted) {
+            assert!((linear(r) - linear(c)).abs() < 1e-3);
+            assert!((linear(c) - linear(e)).abs() < 1e-4);
+        }
+    }
+
+    /// Quantized codes match the dB values mapped onto the range.
+    #[test]
+    fn magnitude_quantized_matches_dbfs() {
+        let input = apply_window(&noise(256, 21), "hann");
+        let db = magnitude_dbfs(&input, 1.0);
+        let codes = magnitude_quantized(&input, 1.0, -60.0, 20.0);
+        assert_eq!(codes.len(), db.len());
+        for (&c, &d) in codes.iter().zip(&db) {
+            let expected = ((d + 60.0) / 80.0).clamp(0.0, 1.0) * 255.0;
+            assert!(
+                (c as f32 - expected).abs() <= 0.5 + 1e-3,
+                "{c} vs {expected}"
+            );
+        }
+        assert_eq!(magnitude_quantized(&[0.0; 8], 1.0, -60.0, 0.0), vec![0; 8]);
+    }
+
+    /// Silent bins are pinned to the floor; loud bins are left alone.
+    #[test]
+    fn clamped_dbfs_pins_silence_to_floor() {
+        let floor = -90.0;
+        assert!(magnitude_dbfs_clamped(&[0.0; 64], 1.0, floor)
+            .iter()
+            .all(|&db| db == floor));
+
+        let loud: Vec<f32> = (0..64).map(|i| (0.3 * i as f32).sin() + 0.5).collect();
+        let plain = magnitude_dbfs(&loud, 1.0);
+        assert!(plain.iter().all(|&db| db > floor));
+        assert_eq!(magnitude_dbfs_clamped(&loud, 1.0, floor), plain);
+    }
+
+    /// A normalized window preserves the peak-bin amplitude of a sine.
+    #[test]
+    fn normalized_window_preserves_sine_peak() {
+        let (n, bin) = (1024, 37);
+        let sine: Vec<f32> = (0..n)
+            .map(|i| (TWO_PI * bin as f32 * i as f32 / n as f32).sin())
+            .collect();
+        let plain = magnitude_linear(&sine)[bin];
+        for name in ["hann", "hamming", "blackman", "blackman-harris", "flattop"] {
+            let windowed = magnitude_linear(&apply_window_normalized(&sine, name))[bin];
+            assert!(
+                (windowed - plain).abs() < 1e-2 * plain,
+                "{name}: {windowed} vs {plain}"
+            );
+        }
+    }
+
+    /// Windows with zero coherent gain are applied unscaled instead of
+    /// dividing by zero.
+    #[test]
+    fn normalized_window_handles_zero_gain() {
+        assert_eq!(
+            apply_window_normalized(&[1.0], "hann"),
+            apply_window(&[1.0], "hann")
+        );
+        let short = apply_window_normalized(&[1.0, -1.0], "hann");
+        assert!(short.iter().all(|v| v.is_finite()), "{short:?}");
+    }
+
+    /// Window info agrees with the dedicated window and gain functions.
+    #[test]
+    fn window_info_matches_gain_functions() {
+        let n = 256;
+        for name in ["hann", "hamming", "blackman", "rect"] {
+            let info = window_info(name, n);
+            assert_eq!(info.coefficients(), apply_window(&vec![1.0; n], name));
+            assert_eq!(info.coherent_gain(), window_coherent_gain(name, n));
+            assert_eq!(info.energy_gain(), window_energy_gain(name, n));
+        }
+        let hann = window_info("hann", n);
+        assert!((hann.coherent_gain() - 0.5).abs() < 1e-2);
+        assert!((hann.energy_gain() - 0.375).abs() < 1e-2);
+    }
+
+    /// An injected tone reads back at its level after window-gain correction.
+    #[test]
+    fn inject_tone_reads_back_at_level() {
+        let (n, rate, freq, level) = (1024, 8192.0f32, 1024.0f32, -20.0f32);
+        let background: Vec<f32> = (0..n).map(|i| 1e-4 * (i as f32 * 0.3).cos()).collect();
+        let signal = inject_tone(&background, freq, level, rate);
+
+        let mags = half_spectrum_magnitudes(&apply_window(&signal, "hann"));
+        let peak = (1..mags.len())
+            .max_by(|&a, &b| mags[a].total_cmp(&mags[b]))
+            .unwrap();
+        assert_eq!(peak, (freq * n as f32 / rate) as usize);
+        let amplitude = 2.0 * mags[peak] / (n as f32 * window_coherent_gain("hann", n));
+        let measured = DB_SCALE * amplitude.log10();
+        assert!((measured - level).abs() < 0.1, "{measured}");
+    }
+
+    /// Soft limiting an over-level sine distorts less than hard clipping.
+    #[test]
+    fn soft_limit_distorts_less_than_clipping() {
+        let (n, bin) = (1024, 8);
+        let sine: Vec<f32> = (0..n)
+            .map(|i| 1.5 * (TWO_PI * (bin * i) as f32 / n as f32).sin())
+            .collect();
+        let distortion = |x: &[f32]| {
+            let mags = half_spectrum_magnitudes(x);
+            let harmonics: f32 = (2..mags.len() / bin).map(|h| mags[h * bin].powi(2)).sum();
+            harmonics / mags[bin].powi(2)
+        };
+        let soft = soft_limit(&sine, 0.5);
+        assert!(soft.iter().all(|v| v.abs() < 1.0));
+        let hard: Vec<f32> = sine.iter().map(|v| v.clamp(-1.0, 1.0)).collect();
+        let (soft_thd, hard_thd) = (distortion(&soft), distortion(&hard));
+        assert!(soft_thd < hard_thd, "soft {soft_thd} vs hard {hard_thd}");
+        assert_eq!(soft_limit(&[0.25, -0.5], 0.5), vec![0.25, -0.5]);
+    }
+
+    /// The inverse FFT undoes the forward FFT.
+    #[test]
+    fn ifft_real_round_trips() {
+        for n in [1usize, 7, 16, 100] {
+            let data: Vec<f32> = (0..n).map(|i| (i as f32 * 0.3).sin() - 0.1).collect();
+            let restored = ifft_real(&fft_real(&data), n);
+            assert_eq!(restored.len(), n);
+            for (a, b) in restored.iter().zip(&data) {
+                assert!((a - b).abs() < TOLERANCE, "{a} vs {b}");
+            }
+        }
+        assert!(ifft_real(&[], 0).is_empty());
+    }
+
+    /// A spectrum that does not match the signal length is rejected.
+    #[test]
+    #[should_panic(expected = "spectrum must hold n/2 + 1 interleaved re/im bins")]
+    fn ifft_real_rejects_mismatched_length() {
+        ifft_real(&fft_real(&[1.0, 2.0, 3.0, 4.0]), 6);
+    }
+
+    /// The real FFT returns exactly the non-redundant reference bins.
+    #[test]
+    fn rfft_matches_reference_half() {
+        for n in [1usize, 2, 9, 16, 64] {
+            let data: Vec<f32> = (0..n).map(|i| (i as f32 * 0.7).sin() + 0.25).collect();
+            let expected = reference_fft(&data);
+            let half = rfft(&data);
+            assert_eq!(half.len(), 2 * (n / 2 + 1));
+            for (a, b) in half.iter().zip(&expected) {
+                assert!((a - b).abs() < TOLERANCE, "n={n}: {a} vs {b}");
+            }
+        }
+
+        // The full-length magnitudes mirror the half spectrum correctly.
+        let data: Vec<f32> = (0..32).map(|i| (i as f32 * 0.2).cos()).collect();
+        let expected = reference_fft(&data);
+        for (m, c) in magnitude_linear(&data).iter().zip(expected.chunks_exact(2)) {
+            assert!((m - (c[0] * c[0] + c[1] * c[1]).sqrt()).abs() < TOLERANCE);
+        }
+    }
+
+    /// The complex frame keeps `stft_frame` magnitudes and adds phase.
+    #[test]
+    fn stft_frame_complex_adds_phase() {
+        let (n, bin) = (64, 5);
+        let data: Vec<f32> = (0..n).map(|i| (i as f32 * 0.43).sin() + 0.1).collect();
+        let complex = stft_frame_complex(&data, "hann", 1.0);
+        let mags: Vec<f32> = complex.iter().step_by(2).copied().collect();
+        assert_eq!(mags, stft_frame(&data, "hann", 1.0));
+        assert!(complex.iter().skip(1).step_by(2).all(|p| p.abs() <= PI));
+
+        let cosine: Vec<f32> = (0..n)
+            .map(|i| (TWO_PI * (bin * i) as f32 / n as f32).cos())
+            .collect();
+        let complex = stft_frame_complex(&cosine, "rect", 1.0);
+        assert!(complex[2 * bin + 1].abs() < TOLERANCE);
+        assert!(complex[2 * (n - bin) + 1].abs() < TOLERANCE);
+
+        let polar = stft_frame_polar(&data, "hann", 1.0);
+        let complex = stft_frame_complex(&data, "hann", 1.0);
+        assert_eq!(polar.len(), n + 2);
+        for (p, c) in polar.iter().zip(&complex) {
+            assert!((p - c).abs() < 1e-5);
+        }
+    }
+
     /// Verify that the optimized FFT is faster than the naive reference.
     #[test]
     fn fft_is_faster_than_reference() {
@@ -293,6 +2275,93 @@ mod tests {
         );
     }
 
+    /// The plan cache evicts the least recently used size and accounts for
+    /// what it holds.
+    #[test]
+    fn plan_cache_is_bounded_lru() {
+        let mut cache = PlanCache::<f32>::new(2);
+        let first = cache.plan(64, false);
+        cache.plan(128, false);
+        cache.plan(64, true);
+        cache.plan(256, false);
+        let sizes: Vec<usize> = cache.entries.iter().map(|e| e.n).collect();
+        assert_eq!(sizes, vec![64, 256]);
+        assert!(Arc::ptr_eq(&first, &cache.plan(64, false)));
+        let bytes = (2 * 64 + 256) * std::mem::size_of::<Complex32>();
+        assert_eq!(cache.bytes(), bytes);
+
+        cache.capacity = 1;
+        cache.evict();
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.entries[0].n, 64);
+
+        let mut wide = PlanCache::<f64>::new(1);
+        wide.plan(64, false);
+        wide.plan(128, false);
+        assert_eq!(wide.bytes(), 128 * std::mem::size_of::<Complex<f64>>());
+    }
+
+    /// After warm-up, windowing allocates only its output.
+    #[test]
+    fn precomputed_window_is_reused() {
+        let data = vec![1.0f32; 777];
+        precompute_window("hamming", data.len());
+        let allocations = allocations_during(|| drop(apply_window(&data, "hamming")));
+        assert_eq!(allocations, 1);
+        assert_eq!(
+            bits(&apply_window(&data, "hamming")),
+            bits(&formula_window(&data, "hamming"))
+        );
+    }
+
+    /// Window `input` with the per-sample formula `apply_window` used before
+    /// coefficient tables were cached, written out independently of
+    /// `window_coefficient`.
+    fn formula_window(input: &[f32], window_type: &str) -> Vec<f32> {
+        let denom = (input.len() as f32 - 1.0).max(1.0);
+        input
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let phase = 2.0 * std::f32::consts::PI * i as f32 / denom;
+                let w = match window_type {
+                    "hann" => 0.5 - 0.5 * phase.cos(),
+                    "hamming" => 0.54 - 0.46 * phase.cos(),
+                    "blackman" => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+                    _ => 1.0,
+                };
+                x * w
+            })
+            .collect()
+    }
+
+    /// Bit patterns of `values`, so `-0.0` and `0.0` compare unequal.
+    fn bits(values: &[f32]) -> Vec<u32> {
+        values.iter().map(|v| v.to_bits()).collect()
+    }
+
+    /// The cached window tables are bit-identical to the uncached formula,
+    /// first use and repeat use alike.
+    #[test]
+    fn cached_window_matches_formula_bits() {
+        for n in [1usize, 2, 7, 64, PERF_SIZE, 1000] {
+            let data = noise(n, n as u64);
+            for window in ["hann", "hamming", "blackman", "rect"] {
+                let expected = bits(&formula_window(&data, window));
+                assert_eq!(
+                    bits(&apply_window(&data, window)),
+                    expected,
+                    "{window} n={n}"
+                );
+                assert_eq!(
+                    bits(&apply_window(&data, window)),
+                    expected,
+                    "{window} n={n}"
+                );
+            }
+        }
+    }
+
     /// Ensure `validate_finite` rejects non-finite input.
     #[test]
     #[should_panic(expected = "input contains non-finite values")]
@@ -300,6 +2369,104 @@ mod tests {
         validate_finite(&[0.0, f32::NAN]);
     }
 
+    /// Empty frames, unknown windows and mismatched frame sizes are
+    /// reported as errors.
+    #[test]
+    fn try_variants_validate_shape_and_window() {
+        let frame = [0.5f32, -0.25, 1.0, 0.0];
+        assert_eq!(try_fft_real(&[]), Err(DspError::EmptyInput));
+        assert_eq!(try_magnitude_dbfs(&[], 1.0), Err(DspError::EmptyInput));
+        assert_eq!(try_stft_frame(&[], "hann", 1.0), Err(DspError::EmptyInput));
+        assert_eq!(
+            try_stft_frame(&frame, "hanning", 1.0),
+            Err(DspError::UnknownWindow("hanning".to_owned()))
+        );
+        assert_eq!(
+            try_stft_frame(&frame, "hann", 1.0),
+            Ok(stft_frame(&frame, "hann", 1.0))
+        );
+
+        let mut analyzer = FrameAnalyzer::new(8, "hann");
+        let error = DspError::LengthMismatch {
+            expected: 8,
+            actual: 4,
+        };
+        assert_eq!(error.to_string(), "expected 8 samples, got 4");
+        assert_eq!(analyzer.try_analyze(&frame, 1.0), Err(error));
+    }
+
+    /// The `try_*` and checked entry points report NaN input without panicking.
+    #[test]
+    fn try_variants_report_non_finite_input() {
+        let data = [1.0f32, 2.0, f32::NAN, 4.0];
+        let error = DspError::NonFinite { index: 2 };
+        let expected = Err(error.clone());
+        assert_eq!(try_fft_real(&data), expected);
+        assert_eq!(try_magnitude_dbfs(&data, 1.0), expected);
+        assert_eq!(apply_window_checked(&data, "hann"), expected);
+        assert_eq!(
+            error.to_string(),
+            "input contains a non-finite value at index 2"
+        );
+
+        let clean = [1.0f32, 2.0, 3.0, 4.0];
+        assert_eq!(try_fft_real(&clean), Ok(fft_real(&clean)));
+        assert_eq!(
+            try_magnitude_dbfs(&clean, 1.0),
+            Ok(magnitude_dbfs(&clean, 1.0))
+        );
+        let plain = allocations_during(|| drop(fft_real(&clean)));
+        assert_eq!(allocations_during(|| drop(try_fft_real(&clean))), plain);
+    }
+
+    /// Complex input with zero imaginary parts matches `fft_real` on the
+    /// lower half and mirrors it above.
+    #[test]
+    fn fft_complex_matches_fft_real_for_real_signal() {
+        let real = noise(96, 11);
+        let iq: Vec<f32> = real.iter().flat_map(|&x| [x, 0.0]).collect();
+        let half = fft_real(&real);
+        let mut expected = vec![Complex32::new(0.0, 0.0); real.len()];
+        mirror_half_spectrum(&half, &mut expected);
+        let actual = fft_complex(&iq);
+        assert_eq!(actual.len(), 2 * real.len());
+        for (a, e) in actual.iter().zip(&interleave(&expected)) {
+            assert!((a - e).abs() < TOLERANCE, "{a} vs {e}");
+        }
+    }
+
+    /// Odd-length interleaved input is rejected.
+    #[test]
+    #[should_panic(expected = "interleaved complex input must have even length")]
+    fn fft_complex_rejects_odd_length() {
+        fft_complex(&[1.0, 0.0, 2.0]);
+    }
+
+    /// `sanitize` removes NaN and infinities so the FFT accepts the block.
+    #[test]
+    fn sanitize_replaces_non_finite_values() {
+        let data = [1.0, f32::NAN, f32::INFINITY, -2.0, f32::NEG_INFINITY];
+        let clean = sanitize(&data, 0.0);
+        assert_eq!(clean, vec![1.0, 0.0, 0.0, -2.0, 0.0]);
+        assert!(clean.iter().all(|v| v.is_finite()));
+        assert_eq!(fft_real(&clean).len(), 2 * (data.len() / 2 + 1));
+        assert!(sanitize(&data, f32::NAN).iter().all(|v| v.is_finite()));
+    }
+
+    /// Both perfect-reconstruction windows satisfy Princen-Bradley.
+    #[test]
+    fn pr_windows_satisfy_princen_bradley() {
+        let n = 64;
+        let ones = vec![1.0f32; n];
+        for name in ["sine", "vorbis"] {
+            let w = apply_pr_window(&ones, name);
+            for i in 0..n / 2 {
+                let sum = w[i] * w[i] + w[i + n / 2] * w[i + n / 2];
+                assert!((sum - 1.0).abs() < TOLERANCE, "{name}[{i}]: {sum}");
+            }
+        }
+    }
+
     /// Verify the STFT pipeline matches manual window + magnitude calculation.
     #[test]
     fn stft_frame_matches_manual_pipeline() {
@@ -310,4 +2477,85 @@ mod tests {
             assert!((a - b).abs() < TOLERANCE, "{a} vs {b}");
         }
     }
+
+    /// The typed window API matches the string API for every known name.
+    #[test]
+    fn typed_window_matches_string_window() {
+        let data: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        for (name, window) in [
+            ("hann", WindowType::Hann),
+            ("hamming", WindowType::Hamming),
+            ("blackman", WindowType::Blackman),
+            ("rect", WindowType::Rectangular),
+        ] {
+            assert_eq!(apply_window(&data, name), apply_window_typed(&data, window));
+        }
+    }
+
+    /// Unknown window names are an error in the checked path.
+    #[test]
+    fn checked_window_rejects_unknown_name() {
+        let data = [1.0f32, 2.0, 3.0];
+        assert_eq!(
+            apply_window_checked(&data, "han"),
+            Err(DspError::UnknownWindow("han".to_owned()))
+        );
+    }
+
+    /// The string API panics on unknown names instead of passing through.
+    #[test]
+    #[should_panic(expected = "unknown window type: han")]
+    fn unknown_window_name_panics() {
+        apply_window(&[1.0, 2.0, 3.0], "han");
+    }
+
+    /// Each window's DC gain (mean coefficient) matches its published value.
+    #[test]
+    fn window_dc_gain_matches_published_values() {
+        let n = 4097;
+        for (name, expected) in [
+            ("rect", 1.0),
+            ("hann", 0.5),
+            ("hamming", 0.54),
+            ("blackman", 0.42),
+            ("blackman-harris", 0.35875),
+            ("nuttall", 0.35577),
+            ("flattop", 0.21558),
+            ("tukey", 0.75),
+        ] {
+            let gain = window_coherent_gain(name, n);
+            assert!((gain - expected).abs() < 1e-3, "{name}: {gain}");
+        }
+        let kaiser = apply_window_kaiser(&vec![1.0; n], 0.0);
+        assert!(kaiser.iter().all(|&w| (w - 1.0).abs() < TOLERANCE));
+        // The continuous Kaiser window has DC gain sinh(beta) / (beta * I0(beta)).
+        let kaiser = apply_window_kaiser(&vec![1.0; n], 8.6);
+        let gain = kaiser.iter().sum::<f32>() / n as f32;
+        assert!((gain - 0.4208).abs() < 1e-3, "kaiser: {gain}");
+        // The continuous Gaussian has DC gain sigma * sqrt(pi / 2) * erf(1 / (sigma * sqrt(2))).
+        let gaussian = apply_window_gaussian(&vec![1.0; n], 0.4);
+        let gain = gaussian.iter().sum::<f32>() / n as f32;
+        assert!((gain - 0.4951).abs() < 1e-3, "gaussian: {gain}");
+    }
+
+    /// The parameterized entry point matches the dedicated functions and
+    /// reports bad names and parameters.
+    #[test]
+    fn window_with_param_dispatches_and_validates() {
+        let input = noise(64, 3);
+        let kaiser = apply_window_with_param(&input, "kaiser", 6.0).unwrap();
+        assert_eq!(kaiser, apply_window_kaiser(&input, 6.0));
+        let gaussian = apply_window_with_param(&input, "gaussian", 0.3).unwrap();
+        assert_eq!(gaussian, apply_window_gaussian(&input, 0.3));
+        let nuttall = apply_window_with_param(&input, "nuttall", 0.0).unwrap();
+        assert_eq!(nuttall, apply_window(&input, "nuttall"));
+        assert_eq!(
+            apply_window_with_param(&input, "gauss", 0.3),
+            Err(DspError::UnknownWindow("gauss".to_owned()))
+        );
+        assert!(matches!(
+            apply_window_with_param(&input, "gaussian", 0.0),
+            Err(DspError::InvalidParameter(_))
+        ));
+    }
 }
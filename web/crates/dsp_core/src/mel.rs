@@ -0,0 +1,609 @@
+//! Mel-scale filterbanks, mel spectrograms and MFCCs.
+//!
+//! # What
+//! Triangular filters spaced evenly on the mel scale that pool linear
+//! frequency bins into perceptual bands.
+//!
+//! # Why
+//! Speech and music models expect mel features. The filter matrix is
+//! computed once and reused for every frame, which is far cheaper than
+//! rebuilding it in JavaScript.
+
+use crate::spectrogram::{frame_count, half_spectrum_frames};
+use crate::{parse_window, power_spectrum_half, validate_finite, window_samples, Spectrogram};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Floor added to the smoothed energy before the PCEN gain division.
+const PCEN_EPSILON: f32 = 1e-6;
+
+/// Floor added to mel power before the logarithm in [`mfcc`].
+const MFCC_LOG_FLOOR: f32 = 1e-10;
+
+/// Scale factor of the HTK mel formula `mel = 2595 * log10(1 + hz / 700)`.
+const MEL_SCALE: f32 = 2595.0;
+
+/// Corner frequency in Hz of the HTK mel formula.
+const MEL_CORNER_HZ: f32 = 700.0;
+
+/// Convert a frequency in Hz to mels.
+fn hz_to_mel(hz: f32) -> f32 {
+    MEL_SCALE * (1.0 + hz / MEL_CORNER_HZ).log10()
+}
+
+/// Convert mels back to a frequency in Hz.
+fn mel_to_hz(mel: f32) -> f32 {
+    MEL_CORNER_HZ * (10f32.powf(mel / MEL_SCALE) - 1.0)
+}
+
+/// Triangular mel filterbank for a fixed FFT size and sample rate.
+///
+/// # What
+/// `n_mels` triangles whose edges and peaks are spaced evenly in mels
+/// between `fmin` and `fmax`. Each triangle peaks at `1` on its center
+/// frequency and reaches `0` at its neighbours' centers. Bands narrower than
+/// the bin spacing may contain no bins and then always output `0`.
+///
+/// # How
+/// The weights are stored as a dense `n_mels × (fft_size/2 + 1)` matrix so
+/// [`MelFilterbank::apply`] is a plain matrix-vector product.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone)]
+pub struct MelFilterbank {
+    n_mels: usize,
+    n_bins: usize,
+    weights: Vec<f32>,
+}
+
+/// [`MelFilterbank`] under the name the per-frame mel API asks for.
+pub type MelFilterBank = MelFilterbank;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MelFilterbank {
+    /// Build the filters for `fft_size`-point spectra at `sample_rate`.
+    ///
+    /// # Panics
+    /// Panics when `n_mels` is zero, `fft_size < 2`, `sample_rate` is not
+    /// positive, or `0 <= fmin < fmax <= sample_rate / 2` does not hold.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        sample_rate: f32,
+        fft_size: usize,
+        n_mels: usize,
+        fmin: f32,
+        fmax: f32,
+    ) -> MelFilterbank {
+        assert!(n_mels > 0, "n_mels must be positive");
+        assert!(fft_size >= 2, "fft_size must be at least 2");
+        assert!(
+            sample_rate.is_finite() && sample_rate > 0.0,
+            "sample_rate must be a positive finite number"
+        );
+        assert!(
+            fmin >= 0.0 && fmin < fmax && fmax <= sample_rate / 2.0,
+            "frequencies must satisfy 0 <= fmin < fmax <= sample_rate / 2"
+        );
+        let n_bins = fft_size / 2 + 1;
+        let bin_hz = sample_rate / fft_size as f32;
+        let (mel_lo, mel_hi) = (hz_to_mel(fmin), hz_to_mel(fmax));
+        let edges: Vec<f32> = (0..n_mels + 2)
+            .map(|i| mel_to_hz(mel_lo + (mel_hi - mel_lo) * i as f32 / (n_mels + 1) as f32))
+            .collect();
+
+        let mut weights = vec![0.0f32; n_mels * n_bins];
+        for (m, row) in weights.chunks_exact_mut(n_bins).enumerate() {
+            let (left, center, right) = (edges[m], edges[m + 1], edges[m + 2]);
+            for (k, w) in row.iter_mut().enumerate() {
+                let hz = k as f32 * bin_hz;
+                let rising = (hz - left) / (center - left);
+                let falling = (right - hz) / (right - center);
+                *w = rising.min(falling).max(0.0);
+            }
+        }
+        MelFilterbank {
+            n_mels,
+            n_bins,
+            weights,
+        }
+    }
+
+    /// Number of mel bands.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn n_mels(&self) -> usize {
+        self.n_mels
+    }
+
+    /// Number of spectrum bins each band expects (`fft_size/2 + 1`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn n_bins(&self) -> usize {
+        self.n_bins
+    }
+
+    /// Pool a half spectrum into mel bands.
+    ///
+    /// # Panics
+    /// Panics when `spectrum.len()` differs from [`MelFilterbank::n_bins`].
+    pub fn apply(&self, spectrum: &[f32]) -> Vec<f32> {
+        assert_eq!(
+            spectrum.len(),
+            self.n_bins,
+            "spectrum length must equal the filterbank bin count"
+        );
+        self.weights
+            .chunks_exact(self.n_bins)
+            .map(|row| row.iter().zip(spectrum).map(|(w, s)| w * s).sum())
+            .collect()
+    }
+}
+
+/// Flattened triangular mel filter matrix.
+///
+/// # What
+/// Returns the [`MelFilterbank`] weights as `n_mels` rows of
+/// `fft_size/2 + 1` values, row-major. `fmax = 0` selects the Nyquist
+/// frequency. When `n_mels` is too large for `fft_size`, the narrowest
+/// bands fall between bins and their rows are all zero.
+///
+/// # Panics
+/// Panics under the same conditions as [`MelFilterbank::new`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn mel_filterbank(
+    sample_rate: f32,
+    fft_size: usize,
+    n_mels: usize,
+    fmin: f32,
+    fmax: f32,
+) -> Vec<f32> {
+    let fmax = if fmax == 0.0 { sample_rate / 2.0 } else { fmax };
+    MelFilterbank::new(sample_rate, fft_size, n_mels, fmin, fmax).weights
+}
+
+/// Pool a half-spectrum magnitude frame into mel bands.
+///
+/// # What
+/// `magnitudes` holds bins `0..=fft_size/2` of an even `fft_size`, so
+/// `fft_size = 2 * (magnitudes.len() - 1)`. Returns the `n_mels` weighted
+/// sums of [`MelFilterbank::apply`]; `fmax = 0` selects Nyquist, as in
+/// [`mel_filterbank`]. Build a [`MelFilterBank`] once instead when many
+/// frames share the same parameters. Empty input yields an empty result.
+///
+/// This is the per-frame `mel_spectrogram(frame, sample_rate, n_mels,
+/// fmin, fmax)` call; it is named `mel_bands` because [`mel_spectrogram`]
+/// already names the whole-clip spectrogram.
+///
+/// # Panics
+/// Panics when `magnitudes` contains non-finite values, has a single bin,
+/// or the filterbank parameters are invalid (see [`MelFilterbank::new`]).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn mel_bands(
+    magnitudes: &[f32],
+    sample_rate: f32,
+    n_mels: usize,
+    fmin: f32,
+    fmax: f32,
+) -> Vec<f32> {
+    validate_finite(magnitudes);
+    if magnitudes.is_empty() {
+        return Vec::new();
+    }
+    let fft_size = 2 * (magnitudes.len() - 1);
+    let fmax = if fmax == 0.0 { sample_rate / 2.0 } else { fmax };
+    MelFilterbank::new(sample_rate, fft_size, n_mels, fmin, fmax).apply(magnitudes)
+}
+
+/// Mel power spectrum of a single frame.
+///
+/// # What
+/// Windows `input`, takes the power of bins `0..=n/2` and pools them into
+/// `n_mels` bands spanning `0` Hz to Nyquist, where `n = input.len()` is
+/// the FFT size. Empty input yields an empty result.
+///
+/// # Why
+/// Streaming frontends that frame audio themselves need mel features one
+/// frame at a time; [`mel_spectrogram`] covers whole clips.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, has a single sample, or
+/// the filterbank parameters are invalid (see [`MelFilterbank::new`]).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn mel_spectrogram_frame(
+    input: &[f32],
+    window_type: &str,
+    sample_rate: f32,
+    n_mels: usize,
+) -> Vec<f32> {
+    validate_finite(input);
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let bank = MelFilterbank::new(sample_rate, input.len(), n_mels, 0.0, sample_rate / 2.0);
+    let power = power_spectrum_half(&window_samples(input, parse_window(window_type)));
+    bank.apply(&power)
+}
+
+/// Mel-frequency cepstral coefficients of a single frame.
+///
+/// # What
+/// Computes the [`mel_spectrogram_frame`] power of `frame` with a Hann
+/// window, takes `ln(power + MFCC_LOG_FLOOR)` and returns the first
+/// `n_coeffs` coefficients of its orthonormal DCT-II. Coefficient `0`
+/// tracks overall level; the others describe the spectral envelope and
+/// ignore gain. Empty input yields an empty result.
+///
+/// # Why
+/// Speech and ML front-ends feed on MFCCs; one call avoids a second WASM
+/// module or a JavaScript DCT.
+///
+/// # Panics
+/// Panics when `frame` contains non-finite values, `n_coeffs` exceeds
+/// `n_mels`, or the filterbank parameters are invalid (see
+/// [`MelFilterbank::new`]).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn mfcc(frame: &[f32], sample_rate: f32, n_mels: usize, n_coeffs: usize) -> Vec<f32> {
+    assert!(n_coeffs <= n_mels, "n_coeffs must not exceed n_mels");
+    let log_mel: Vec<f64> = mel_spectrogram_frame(frame, "hann", sample_rate, n_mels)
+        .iter()
+        .map(|&p| ((p + MFCC_LOG_FLOOR) as f64).ln())
+        .collect();
+    if log_mel.is_empty() {
+        return Vec::new();
+    }
+    let n = n_mels as f64;
+    (0..n_coeffs)
+        .map(|k| {
+            let scale = if k == 0 {
+                (1.0 / n).sqrt()
+            } else {
+                (2.0 / n).sqrt()
+            };
+            let sum: f64 = log_mel
+                .iter()
+                .enumerate()
+                .map(|(m, &v)| v * (std::f64::consts::PI * k as f64 * (m as f64 + 0.5) / n).cos())
+                .sum();
+            (scale * sum) as f32
+        })
+        .collect()
+}
+
+/// Mel power spectrogram of a whole clip.
+///
+/// # What
+/// Windows every complete `fft_size` frame spaced `hop` samples apart,
+/// takes the power spectrum of bins `0..=fft_size/2`, and pools it with a
+/// [`MelFilterbank`] built once for all frames. Returns an
+/// `n_frames × n_mels` [`Spectrogram`] of linear power. Unknown window
+/// names panic, matching [`crate::stft_frame`].
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, `hop` is zero, or the
+/// filterbank parameters are invalid (see [`MelFilterbank::new`]).
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn mel_spectrogram(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    sample_rate: f32,
+    n_mels: usize,
+    fmin: f32,
+    fmax: f32,
+) -> Spectrogram {
+    validate_finite(input);
+    assert!(hop > 0, "hop must be positive");
+    let bank = MelFilterbank::new(sample_rate, fft_size, n_mels, fmin, fmax);
+    let window = parse_window(window_type);
+    let n_frames = frame_count(input.len(), fft_size, hop);
+
+    let mut data = Vec::with_capacity(n_frames * n_mels);
+    for mags in half_spectrum_frames(input, fft_size, hop, window) {
+        let power: Vec<f32> = mags.into_iter().map(|m| m * m).collect();
+        data.extend(bank.apply(&power));
+    }
+    Spectrogram::new(n_frames, n_mels, data)
+}
+
+/// Log-amplitude compression `ln(1 + c * max(value, 0))`, elementwise.
+///
+/// # What
+/// Negative inputs are clamped to zero before compression, so the output is
+/// always non-negative. `c = 0` maps everything to zero; larger `c`
+/// compresses more strongly towards a pure logarithm.
+///
+/// # Why
+/// Unlike dB conversion the curve is finite at zero, which keeps silent
+/// cells well-behaved as neural-network inputs.
+///
+/// # Panics
+/// Panics when `values` contains non-finite values or `c` is negative or
+/// non-finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn log_compress(values: &[f32], c: f32) -> Vec<f32> {
+    validate_finite(values);
+    assert!(
+        c.is_finite() && c >= 0.0,
+        "c must be a non-negative finite number"
+    );
+    values.iter().map(|&v| (c * v.max(0.0)).ln_1p()).collect()
+}
+
+/// Streaming per-channel energy normalization (PCEN).
+///
+/// # What
+/// For every bin keeps a first-order smoothed energy
+/// `m = (1 - s) * m + s * e` and outputs
+/// `(e / (eps + m)^alpha + delta)^r - delta^r`. The smoother starts from
+/// the first frame it sees, so there is no start-up transient.
+///
+/// # Why
+/// Dividing by the slowly tracked energy acts as adaptive gain control:
+/// steady loud backgrounds are flattened while onsets, which rise faster
+/// than the smoother, stand out. The state persists between
+/// [`Pcen::process`] calls so frames can arrive one at a time.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Pcen {
+    alpha: f32,
+    delta: f32,
+    r: f32,
+    s: f32,
+    smoothed: Vec<f32>,
+    primed: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Pcen {
+    /// Create a normalizer for frames of `n_bins` values.
+    ///
+    /// `alpha` is the gain exponent, `delta` and `r` the offset and exponent
+    /// of the root compression, and `s` the smoother coefficient.
+    ///
+    /// # Panics
+    /// Panics when `n_bins` is zero, `s` is outside `(0, 1]`, `r` is not
+    /// positive, or `alpha` or `delta` is negative.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(n_bins: usize, alpha: f32, delta: f32, r: f32, s: f32) -> Pcen {
+        assert!(n_bins > 0, "n_bins must be positive");
+        assert!(s > 0.0 && s <= 1.0, "s must be in (0, 1]");
+        assert!(r > 0.0, "r must be positive");
+        assert!(alpha >= 0.0, "alpha must be non-negative");
+        assert!(delta >= 0.0, "delta must be non-negative");
+        Pcen {
+            alpha,
+            delta,
+            r,
+            s,
+            smoothed: vec![0.0; n_bins],
+            primed: false,
+        }
+    }
+
+    /// Normalize one frame of non-negative energies, updating the smoother.
+    ///
+    /// # Panics
+    /// Panics when `mel_frame` contains non-finite values or its length
+    /// differs from `n_bins`.
+    pub fn process(&mut self, mel_frame: &[f32]) -> Vec<f32> {
+        validate_finite(mel_frame);
+        assert_eq!(
+            mel_frame.len(),
+            self.smoothed.len(),
+            "frame length must equal n_bins"
+        );
+        if !self.primed {
+            self.smoothed.copy_from_slice(mel_frame);
+            self.primed = true;
+        }
+        let offset = self.delta.powf(self.r);
+        mel_frame
+            .iter()
+            .zip(self.smoothed.iter_mut())
+            .map(|(&e, m)| {
+                *m = (1.0 - self.s) * *m + self.s * e;
+                let gain = (PCEN_EPSILON + *m).powf(self.alpha);
+                (e / gain + self.delta).powf(self.r) - offset
+            })
+            .collect()
+    }
+
+    /// Forget the smoothed energy so the next frame restarts the tracker.
+    pub fn reset(&mut self) {
+        self.smoothed.fill(0.0);
+        self.primed = false;
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TWO_PI;
+
+    /// Tolerance for floating point comparisons in tests.
+    const TOLERANCE: f32 = 1e-3;
+
+    /// The mel conversions invert each other.
+    #[test]
+    fn mel_conversion_round_trips() {
+        for hz in [0.0f32, 100.0, 1000.0, 8000.0] {
+            assert!((mel_to_hz(hz_to_mel(hz)) - hz).abs() < TOLERANCE * hz.max(1.0));
+        }
+        assert!((hz_to_mel(1000.0) - 1000.0).abs() < 1.0);
+    }
+
+    /// A tone lands in the band centered nearest to it in every frame.
+    #[test]
+    fn mel_spectrogram_concentrates_tone() {
+        let (rate, fft_size, hop, n_mels) = (16_000.0f32, 512, 256, 20);
+        let (fmin, fmax) = (0.0, rate / 2.0);
+        let tone_hz = 1000.0;
+        let input: Vec<f32> = (0..4096)
+            .map(|i| (TWO_PI * tone_hz * i as f32 / rate).sin())
+            .collect();
+
+        let mel = mel_spectrogram(&input, fft_size, hop, "hann", rate, n_mels, fmin, fmax);
+        let n_frames = frame_count(input.len(), fft_size, hop);
+        assert_eq!(mel.n_frames(), n_frames);
+        assert_eq!(mel.n_bins(), n_mels);
+        assert_eq!(mel.values().len(), n_frames * n_mels);
+
+        let (mel_lo, mel_hi) = (hz_to_mel(fmin), hz_to_mel(fmax));
+        let expected = (0..n_mels)
+            .min_by(|&a, &b| {
+                let center = |m: usize| {
+                    mel_to_hz(mel_lo + (mel_hi - mel_lo) * (m + 1) as f32 / (n_mels + 1) as f32)
+                };
+                (center(a) - tone_hz)
+                    .abs()
+                    .total_cmp(&(center(b) - tone_hz).abs())
+            })
+            .unwrap();
+        for row in mel.values().chunks_exact(n_mels) {
+            let peak = (0..n_mels)
+                .max_by(|&a, &b| row[a].total_cmp(&row[b]))
+                .unwrap();
+            assert_eq!(peak, expected);
+        }
+    }
+
+    /// Compression is zero for `c = 0`, grows with `c`, and clamps negatives.
+    #[test]
+    fn log_compress_behaves_monotonically() {
+        let values = [0.0f32, 0.5, 2.0, -3.0];
+        assert!(log_compress(&values, 0.0).iter().all(|&v| v == 0.0));
+
+        let weak = log_compress(&values, 1.0);
+        let strong = log_compress(&values, 10.0);
+        assert!((weak[2] - 3f32.ln()).abs() < TOLERANCE);
+        for i in 1..3 {
+            assert!(strong[i] > weak[i]);
+        }
+        assert_eq!(weak[3], 0.0);
+        assert_eq!(strong[3], 0.0);
+    }
+
+    /// A loud steady background is flattened while an onset stands out.
+    #[test]
+    fn pcen_suppresses_background_and_keeps_onsets() {
+        let (loud, quiet) = (1000.0f32, 1.0f32);
+        let mut pcen = Pcen::new(2, 0.98, 2.0, 0.5, 0.025);
+        let mut steady = Vec::new();
+        for _ in 0..300 {
+            steady = pcen.process(&[loud, quiet]);
+        }
+        // A 60 dB level difference shrinks to a ratio close to one.
+        assert!(steady[0] / steady[1] < 1.5, "{steady:?}");
+
+        let onset = pcen.process(&[loud, loud]);
+        assert!(onset[1] > 5.0 * steady[0], "{onset:?} vs {steady:?}");
+        assert!((onset[0] - steady[0]).abs() < TOLERANCE);
+    }
+
+    /// Every band has weight and band centers rise with the band index.
+    #[test]
+    fn mel_filterbank_rows_are_positive_and_ordered() {
+        let (rate, fft_size, n_mels) = (16_000.0f32, 512, 40);
+        let n_bins = fft_size / 2 + 1;
+        let weights = mel_filterbank(rate, fft_size, n_mels, 0.0, 0.0);
+        assert_eq!(weights.len(), n_mels * n_bins);
+        assert_eq!(
+            weights,
+            mel_filterbank(rate, fft_size, n_mels, 0.0, rate / 2.0)
+        );
+
+        let mut last_center = f32::NEG_INFINITY;
+        for row in weights.chunks_exact(n_bins) {
+            let sum: f32 = row.iter().sum();
+            assert!(sum > 0.0);
+            let center = row
+                .iter()
+                .enumerate()
+                .map(|(k, w)| k as f32 * w)
+                .sum::<f32>()
+                / sum;
+            assert!(center > last_center, "{center} <= {last_center}");
+            last_center = center;
+        }
+
+        // Too many bands for the resolution leaves some of them empty.
+        let crowded = mel_filterbank(rate, 64, 64, 0.0, 0.0);
+        assert!(crowded
+            .chunks_exact(33)
+            .any(|row| row.iter().all(|&w| w == 0.0)));
+    }
+
+    /// A single frame matches the corresponding row of the clip version.
+    #[test]
+    fn mel_spectrogram_frame_matches_clip_row() {
+        let (rate, fft_size, n_mels) = (8000.0f32, 256, 16);
+        let frame: Vec<f32> = (0..fft_size)
+            .map(|i| (TWO_PI * 700.0 * i as f32 / rate).sin())
+            .collect();
+        let single = mel_spectrogram_frame(&frame, "hann", rate, n_mels);
+        let clip = mel_spectrogram(
+            &frame,
+            fft_size,
+            fft_size,
+            "hann",
+            rate,
+            n_mels,
+            0.0,
+            rate / 2.0,
+        );
+        assert_eq!(single.len(), n_mels);
+        for (a, b) in single.iter().zip(clip.values()) {
+            assert!((a - b).abs() <= TOLERANCE * b.abs().max(1.0), "{a} vs {b}");
+        }
+    }
+
+    /// A magnitude peak at 1 kHz lands in the band centered nearest to it,
+    /// matching a reusable `MelFilterBank`.
+    #[test]
+    fn mel_bands_pool_magnitude_frame() {
+        let (rate, fft_size, n_mels) = (16_000.0f32, 512, 24);
+        let mut mags = vec![0.0f32; fft_size / 2 + 1];
+        mags[32] = 1.0; // 1 kHz
+        let bands = mel_bands(&mags, rate, n_mels, 0.0, 0.0);
+        let bank = MelFilterBank::new(rate, fft_size, n_mels, 0.0, rate / 2.0);
+        assert_eq!(bands, bank.apply(&mags));
+
+        let centers: Vec<f32> = (1..=n_mels)
+            .map(|m| {
+                let mel = hz_to_mel(rate / 2.0) * m as f32 / (n_mels + 1) as f32;
+                mel_to_hz(mel)
+            })
+            .collect();
+        let nearest = (0..n_mels)
+            .min_by(|&a, &b| {
+                (centers[a] - 1000.0)
+                    .abs()
+                    .total_cmp(&(centers[b] - 1000.0).abs())
+            })
+            .unwrap();
+        let loudest = (0..n_mels)
+            .max_by(|&a, &b| bands[a].total_cmp(&bands[b]))
+            .unwrap();
+        assert_eq!(loudest, nearest);
+    }
+
+    /// A gain change moves only coefficient zero.
+    #[test]
+    fn mfcc_separates_level_from_envelope() {
+        let (rate, n_mels, n_coeffs) = (16_000.0f32, 26, 13);
+        let frame = crate::test_support::noise(512, 6);
+        let louder: Vec<f32> = frame.iter().map(|&x| 2.0 * x).collect();
+        let quiet = mfcc(&frame, rate, n_mels, n_coeffs);
+        let loud = mfcc(&louder, rate, n_mels, n_coeffs);
+        assert_eq!(quiet.len(), n_coeffs);
+
+        // Doubling the amplitude adds ln(4) to every log-mel band.
+        let shift = 4f32.ln() * (n_mels as f32).sqrt();
+        assert!((loud[0] - quiet[0] - shift).abs() < TOLERANCE * shift);
+        for k in 1..n_coeffs {
+            assert!((loud[k] - quiet[k]).abs() < TOLERANCE, "c{k}");
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! PNG encoding of colorized spectrograms.
+//!
+//! # What
+//! [`export_png`] turns a matrix of dB frames into the bytes of an RGBA PNG
+//! file, colored like [`crate::colorize`].
+//!
+//! # Why
+//! Reading the image back from the GPU canvas quantizes through the
+//! display pipeline and fails on some mobile browsers. Encoding from the
+//! frames themselves gives the exact colors everywhere.
+
+use crate::colorize;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The eight bytes every PNG file starts with.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// IHDR bit depth: eight bits per channel.
+const BIT_DEPTH: u8 = 8;
+
+/// IHDR color type: truecolor with alpha.
+const COLOR_TYPE_RGBA: u8 = 6;
+
+/// Bytes per RGBA pixel.
+const BYTES_PER_PIXEL: usize = 4;
+
+/// zlib header for deflate with a 32 KiB window and no preset dictionary.
+const ZLIB_HEADER: [u8; 2] = [0x78, 0x01];
+
+/// Largest payload of one stored deflate block.
+const MAX_STORED_BLOCK: usize = u16::MAX as usize;
+
+/// Modulus of the Adler-32 checksum.
+const ADLER_MODULUS: u32 = 65_521;
+
+/// Reversed polynomial of the CRC-32 used by PNG chunks.
+const CRC_POLYNOMIAL: u32 = 0xedb8_8320;
+
+/// Encode `width` dB frames of `height` bins as an RGBA PNG.
+///
+/// # What
+/// `frames` holds the frames back to back, as returned by
+/// [`crate::stft_signal`] when each frame has `height` bins. Frame `x`
+/// becomes column `x` of the image, with its first bin in the bottom row so
+/// frequency increases upwards. Values are colored by [`crate::colorize`]
+/// with `colormap`, `min_db` and `max_db`, so the `-inf` of silent bins
+/// takes the lowest color. Returns the complete file; a
+/// zero `width` or `height` yields an empty vector, since PNG has no empty
+/// images.
+///
+/// # How
+/// Every scanline uses filter type 0 and the image data is wrapped in
+/// stored (uncompressed) deflate blocks, which keeps the encoder tiny; the
+/// bytes can be recompressed by any PNG optimizer.
+///
+/// # Panics
+/// Panics when `frames` does not hold `width * height` values or contains
+/// NaN, `min_db` or `max_db` is non-finite, or `colormap` is unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn export_png(
+    frames: &[f32],
+    width: usize,
+    height: usize,
+    colormap: &str,
+    min_db: f32,
+    max_db: f32,
+) -> Vec<u8> {
+    assert_eq!(
+        frames.len(),
+        width * height,
+        "frames must hold width * height values"
+    );
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let image: Vec<f32> = (0..height)
+        .rev()
+        .flat_map(|bin| (0..width).map(move |x| frames[x * height + bin]))
+        .collect();
+    let rgba = colorize(&image, min_db, max_db, colormap);
+    let row_bytes = width * BYTES_PER_PIXEL;
+    let mut scanlines = Vec::with_capacity(height * (row_bytes + 1));
+    for row in rgba.chunks_exact(row_bytes) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[BIT_DEPTH, COLOR_TYPE_RGBA, 0, 0, 0]);
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Append a PNG chunk: length, type, data and the CRC of type and data.
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wrap non-empty `data` in a zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED_BLOCK);
+    let mut out = Vec::with_capacity(data.len() + 5 * blocks + 6);
+    out.extend_from_slice(&ZLIB_HEADER);
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    while let Some(chunk) = chunks.next() {
+        let last = u8::from(chunks.peek().is_none());
+        let len = chunk.len() as u16;
+        out.push(last);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Adler-32 checksum of `data`, as ending a zlib stream.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % ADLER_MODULUS;
+        b = (b + a) % ADLER_MODULUS;
+    }
+    (b << 16) | a
+}
+
+/// CRC-32 of `data`, as ending a PNG chunk.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (CRC_POLYNOMIAL & mask);
+        }
+    }
+    !crc
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The checksums reproduce their published check values.
+    #[test]
+    fn checksums_match_reference_values() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    /// The file carries the dimensions in IHDR, valid chunk CRCs, and pixel
+    /// rows that are the colorized frames with low bins at the bottom.
+    #[test]
+    fn export_png_encodes_colorized_frames() {
+        // Three frames of two bins: bin 0 is quiet, bin 1 loud.
+        let frames = [-90.0, 0.0, -90.0, -10.0, -60.0, 0.0];
+        let png = export_png(&frames, 3, 2, "grayscale", -100.0, 0.0);
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..20], 3u32.to_be_bytes());
+        assert_eq!(png[20..24], 2u32.to_be_bytes());
+
+        let mut pos = 8;
+        let mut idat = Vec::new();
+        while pos < png.len() {
+            let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+            let body = &png[pos + 4..pos + 8 + len];
+            let crc = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(body));
+            if &body[..4] == b"IDAT" {
+                idat.extend_from_slice(&body[4..]);
+            }
+            pos += 12 + len;
+        }
+        // One stored block: zlib header, block header, data, Adler-32.
+        let data = &idat[7..idat.len() - 4];
+        let top = colorize(&[0.0, -10.0, 0.0], -100.0, 0.0, "grayscale");
+        let bottom = colorize(&[-90.0, -90.0, -60.0], -100.0, 0.0, "grayscale");
+        assert_eq!(data[0], 0);
+        assert_eq!(data[1..13], top[..]);
+        assert_eq!(data[13], 0);
+        assert_eq!(data[14..], bottom[..]);
+        assert_eq!(idat[idat.len() - 4..], adler32(data).to_be_bytes());
+    }
+
+    /// A recording that starts with digital silence exports, with the
+    /// silent `-inf` frames in the lowest color.
+    #[test]
+    fn export_png_accepts_silent_frames() {
+        let mut signal = vec![0.0f32; 256];
+        signal.extend(crate::test_support::noise(256, 3));
+        let frames = crate::stft_signal(&signal, 64, 64, "hann", 1.0);
+        assert_eq!(frames[0], f32::NEG_INFINITY);
+        let png = export_png(&frames, frames.len() / 64, 64, "grayscale", -100.0, 0.0);
+        assert_eq!(&png[12..16], b"IHDR");
+        // First scanline: filter byte, then the first frame's top bin.
+        let pixel = &png[8 + 25 + 8 + 7 + 1..][..4];
+        assert_eq!(pixel, &[0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Time-decimated mipmap levels of a growing spectrogram.
+//!
+//! # What
+//! [`FramePyramid`] ingests frames one at a time and keeps levels in which
+//! every frame pools two frames of the level below, by maximum and by mean.
+//!
+//! # Why
+//! Zoomed out over hours of audio, the viewer cannot upload every frame,
+//! and dropping frames in JavaScript hides short events. A max-pooled level
+//! keeps every peak visible at a fraction of the size.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// One decimated level: max-pooled and mean-pooled frames, frame-major.
+struct Level {
+    max: Vec<f32>,
+    mean: Vec<f32>,
+}
+
+/// Max- and mean-pooled mipmap levels over time.
+///
+/// # What
+/// Level 0 holds the pushed frames; frame `i` of level `L` pools frames
+/// `2i` and `2i + 1` of level `L - 1`, i.e. raw frames `i * 2^L` up to
+/// `(i + 1) * 2^L`. Level `L` therefore has `floor(pushed / 2^L)` frames;
+/// a trailing partial group appears once it completes. Mean pooling takes
+/// the arithmetic mean of the values as given (average dB for dB frames).
+/// All levels above 0 together take about twice the memory of level 0.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FramePyramid {
+    n_bins: usize,
+    levels: Vec<Level>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FramePyramid {
+    /// Create an empty pyramid of `n_levels` levels, level 0 included, for
+    /// frames of `n_bins` values.
+    ///
+    /// # Panics
+    /// Panics when `n_bins` or `n_levels` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(n_bins: usize, n_levels: usize) -> FramePyramid {
+        assert!(n_bins > 0, "n_bins must be positive");
+        assert!(n_levels > 0, "n_levels must be positive");
+        FramePyramid {
+            n_bins,
+            levels: (0..n_levels)
+                .map(|_| Level {
+                    max: Vec::new(),
+                    mean: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Values per frame.
+    pub fn n_bins(&self) -> usize {
+        self.n_bins
+    }
+
+    /// Number of levels, level 0 included.
+    pub fn n_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Number of complete frames in `level`.
+    ///
+    /// # Panics
+    /// Panics when `level >= n_levels()`.
+    pub fn n_frames(&self, level: usize) -> usize {
+        self.level_ref(level).max.len() / self.n_bins
+    }
+
+    /// Append `frame` and update every level it completes a group in.
+    ///
+    /// # Panics
+    /// Panics when `frame` does not hold `n_bins` values.
+    pub fn push_frame(&mut self, frame: &[f32]) {
+        assert_eq!(frame.len(), self.n_bins, "frame must hold n_bins values");
+        let n = self.n_bins;
+        let base = &mut self.levels[0];
+        base.max.extend_from_slice(frame);
+        base.mean.extend_from_slice(frame);
+        for l in 1..self.levels.len() {
+            let (below, above) = self.levels.split_at_mut(l);
+            let (below, above) = (&below[l - 1], &mut above[0]);
+            let frames_below = below.max.len() / n;
+            if frames_below < 2 * (above.max.len() / n + 1) {
+                break;
+            }
+            let pair = (frames_below - 2) * n..frames_below * n;
+            let (max, mean) = (&below.max[pair.clone()], &below.mean[pair]);
+            above.max.extend((0..n).map(|b| max[b].max(max[n + b])));
+            above
+                .mean
+                .extend((0..n).map(|b| 0.5 * (mean[b] + mean[n + b])));
+        }
+    }
+
+    /// Max-pooled frames of `level`, frame-major.
+    ///
+    /// # Panics
+    /// Panics when `level >= n_levels()`.
+    pub fn level_max(&self, level: usize) -> Vec<f32> {
+        self.level_ref(level).max.clone()
+    }
+
+    /// Mean-pooled frames of `level`, frame-major.
+    ///
+    /// # Panics
+    /// Panics when `level >= n_levels()`.
+    pub fn level_mean(&self, level: usize) -> Vec<f32> {
+        self.level_ref(level).mean.clone()
+    }
+
+    /// Forget all frames, keeping the shape.
+    pub fn reset(&mut self) {
+        for level in &mut self.levels {
+            level.max.clear();
+            level.mean.clear();
+        }
+    }
+}
+
+impl FramePyramid {
+    /// The level at index `level`.
+    fn level_ref(&self, level: usize) -> &Level {
+        assert!(level < self.levels.len(), "level out of range");
+        &self.levels[level]
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Levels hold floor(pushed / 2^L) frames, pool by max and mean, and
+    /// keep a one-frame spike visible at the top.
+    #[test]
+    fn pyramid_pools_pairs_of_frames() {
+        let mut pyramid = FramePyramid::new(2, 3);
+        let frames: Vec<[f32; 2]> = (0..7).map(|i| [i as f32, -(i as f32)]).collect();
+        for frame in &frames {
+            pyramid.push_frame(frame);
+        }
+        assert_eq!(
+            (0..3).map(|l| pyramid.n_frames(l)).collect::<Vec<_>>(),
+            vec![7, 3, 1]
+        );
+        assert_eq!(pyramid.level_max(0), pyramid.level_mean(0));
+        assert_eq!(pyramid.level_max(1), vec![1.0, 0.0, 3.0, -2.0, 5.0, -4.0]);
+        assert_eq!(pyramid.level_mean(1), vec![0.5, -0.5, 2.5, -2.5, 4.5, -4.5]);
+        assert_eq!(pyramid.level_max(2), vec![3.0, 0.0]);
+        assert_eq!(pyramid.level_mean(2), vec![1.5, -1.5]);
+
+        pyramid.reset();
+        for i in 0..8 {
+            let level = if i == 5 { 0.0 } else { -90.0 };
+            pyramid.push_frame(&[level, level]);
+        }
+        assert_eq!(pyramid.level_max(2), vec![-90.0, -90.0, 0.0, 0.0]);
+        assert_eq!(pyramid.level_mean(2)[2], -67.5);
+    }
+}
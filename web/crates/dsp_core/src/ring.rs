@@ -0,0 +1,227 @@
+//! Texture-ready ring buffer of spectrogram rows.
+//!
+//! # What
+//! [`FrameRing`] keeps the latest `rows` frames of `cols` bins in one
+//! contiguous row-major buffer laid out like the viewer's scrolling
+//! texture, so the whole ring can be uploaded without rearranging.
+//!
+//! # Why
+//! Keeping the ring in JavaScript copied every frame across the WASM
+//! boundary twice, and its wrap-around bookkeeping kept regressing. Frames
+//! computed here go straight into the buffer the GPU reads.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Circular store of the most recent spectrogram rows.
+///
+/// # What
+/// Row `r` of the texture occupies `data[r * cols..(r + 1) * cols]`.
+/// [`FrameRing::push_row`] writes into [`FrameRing::write_row`] and
+/// advances it, wrapping to row 0 after the last one, so once the ring is
+/// full the oldest row is the one about to be overwritten. Rows not written
+/// yet hold zeros. Shaders scroll by offsetting the texture's `v`
+/// coordinate by `write_row / rows`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FrameRing {
+    cols: usize,
+    rows: usize,
+    /// Row the next push writes to.
+    write_row: usize,
+    /// Rows holding pushed frames, at most `rows`.
+    row_count: usize,
+    data: Vec<f32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FrameRing {
+    /// Create an empty ring of `rows` rows with `cols` bins each.
+    ///
+    /// # Panics
+    /// Panics when `cols` or `rows` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(cols: usize, rows: usize) -> FrameRing {
+        assert!(cols > 0, "cols must be positive");
+        assert!(rows > 0, "rows must be positive");
+        FrameRing {
+            cols,
+            rows,
+            write_row: 0,
+            row_count: 0,
+            data: vec![0.0; cols * rows],
+        }
+    }
+
+    /// Bins per row.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Rows in the ring.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Row the next [`FrameRing::push_row`] writes to.
+    pub fn write_row(&self) -> usize {
+        self.write_row
+    }
+
+    /// Number of rows holding pushed frames.
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// Store `frame` as the newest row, overwriting the oldest when full.
+    ///
+    /// # Panics
+    /// Panics when `frame` does not hold `cols` values.
+    pub fn push_row(&mut self, frame: &[f32]) {
+        assert_eq!(frame.len(), self.cols, "frame must hold cols values");
+        let start = self.write_row * self.cols;
+        self.data[start..start + self.cols].copy_from_slice(frame);
+        self.write_row = (self.write_row + 1) % self.rows;
+        self.row_count = (self.row_count + 1).min(self.rows);
+    }
+
+    /// Value of bin `bin` in the `age`-th oldest stored row.
+    ///
+    /// # Panics
+    /// Panics when `age >= row_count()` or `bin >= cols()`.
+    pub fn read(&self, age: usize, bin: usize) -> f32 {
+        assert!(age < self.row_count, "row out of range");
+        assert!(bin < self.cols, "bin out of range");
+        self.data[self.slot(age) * self.cols + bin]
+    }
+
+    /// The stored rows from oldest to newest, concatenated.
+    ///
+    /// # Why
+    /// The canvas fallback draws rows in time order and cannot offset a
+    /// texture coordinate to undo the wrap-around.
+    pub fn ordered(&self) -> Vec<f32> {
+        (0..self.row_count)
+            .flat_map(|age| {
+                let start = self.slot(age) * self.cols;
+                self.data[start..start + self.cols].iter().copied()
+            })
+            .collect()
+    }
+
+    /// Change the ring to `rows` rows of `cols` bins.
+    ///
+    /// # What
+    /// Keeps the newest `min(row_count, rows)` rows, truncated or
+    /// zero-padded to `cols` bins, and stores them from row 0 in time order
+    /// so [`FrameRing::write_row`] follows the newest. A resize to the
+    /// current shape changes nothing.
+    ///
+    /// # Panics
+    /// Panics when `cols` or `rows` is zero.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        if cols == self.cols && rows == self.rows {
+            return;
+        }
+        let mut resized = FrameRing::new(cols, rows);
+        let kept = self.row_count.min(rows);
+        let shared = cols.min(self.cols);
+        for age in self.row_count - kept..self.row_count {
+            let start = self.slot(age) * self.cols;
+            let row = resized.write_row * cols;
+            resized.data[row..row + shared].copy_from_slice(&self.data[start..start + shared]);
+            resized.write_row = (resized.write_row + 1) % rows;
+            resized.row_count += 1;
+        }
+        *self = resized;
+    }
+
+    /// Zero every row and start again from row 0.
+    pub fn clear(&mut self) {
+        self.data.fill(0.0);
+        self.write_row = 0;
+        self.row_count = 0;
+    }
+
+    /// Zero-copy `Float32Array` over the ring's `rows * cols` values.
+    ///
+    /// # What
+    /// The view aliases WASM memory: any later call into the module may
+    /// grow memory and detach it, so request a fresh view for every upload
+    /// and never store it.
+    ///
+    /// # Why
+    /// Handing the view to `texImage2D` uploads the ring with no copy on
+    /// the JavaScript side.
+    #[cfg(feature = "wasm")]
+    pub fn texture_view(&self) -> js_sys::Float32Array {
+        // SAFETY: the view is created over data that stays alive and
+        // unmodified until control returns to JavaScript; the docs forbid
+        // keeping it across further calls into the module.
+        unsafe { js_sys::Float32Array::view(&self.data) }
+    }
+}
+
+impl FrameRing {
+    /// The ring's `rows * cols` values in texture order.
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Row index holding the `age`-th oldest stored frame.
+    fn slot(&self, age: usize) -> usize {
+        (self.write_row + self.rows - self.row_count + age) % self.rows
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A row of `cols` copies of `value`.
+    fn row(value: f32, cols: usize) -> Vec<f32> {
+        vec![value; cols]
+    }
+
+    /// Pushing past capacity wraps to row 0 and overwrites the oldest row,
+    /// while reads and the ordered copy stay in time order.
+    #[test]
+    fn push_row_wraps_around() {
+        let mut ring = FrameRing::new(2, 3);
+        for value in 1..=4 {
+            ring.push_row(&row(value as f32, 2));
+        }
+        assert_eq!(ring.row_count(), 3);
+        assert_eq!(ring.write_row(), 1);
+        assert_eq!(ring.data(), &[4.0, 4.0, 2.0, 2.0, 3.0, 3.0]);
+        assert_eq!(ring.ordered(), vec![2.0, 2.0, 3.0, 3.0, 4.0, 4.0]);
+        assert_eq!(ring.read(0, 1), 2.0);
+        assert_eq!(ring.read(2, 0), 4.0);
+
+        ring.clear();
+        assert_eq!(ring.row_count(), 0);
+        assert!(ring.data().iter().all(|&v| v == 0.0));
+    }
+
+    /// Resizing keeps the newest rows in order, adapting their width, and
+    /// later pushes continue after them.
+    #[test]
+    fn resize_keeps_newest_rows() {
+        let mut ring = FrameRing::new(2, 3);
+        for value in 1..=4 {
+            ring.push_row(&row(value as f32, 2));
+        }
+        ring.resize(3, 2);
+        assert_eq!(ring.row_count(), 2);
+        assert_eq!(ring.ordered(), vec![3.0, 3.0, 0.0, 4.0, 4.0, 0.0]);
+        ring.push_row(&row(5.0, 3));
+        assert_eq!(ring.ordered(), vec![4.0, 4.0, 0.0, 5.0, 5.0, 5.0]);
+
+        ring.resize(1, 4);
+        assert_eq!(ring.ordered(), vec![4.0, 5.0]);
+        assert_eq!(ring.write_row(), 2);
+    }
+}
@@ -0,0 +1,249 @@
+//! Configurable output scaling for STFT frames.
+//!
+//! # What
+//! [`SpectrumOptions`] selects amplitude or power, dB or linear output, a dB
+//! floor and peak normalization; [`stft_frame_with_options`] applies them in
+//! the same pass as windowing and the FFT.
+//!
+//! # Why
+//! [`crate::stft_frame`] always returns 20·log10 amplitudes, so a viewer
+//! that wants power, a clamped floor or normalized frames has to rewrite
+//! every frame in JavaScript.
+
+use crate::{
+    magnitudes_unchecked, parse_window, validate_finite, window_samples, DB_SCALE, POWER_DB_SCALE,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Smallest linear value before taking a logarithm, about -240 dB.
+const LINEAR_FLOOR: f32 = 1e-24;
+
+/// Quantity and unit of the values in a scaled frame.
+///
+/// Serializes in snake case, e.g. `"amplitude_db"`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpectrumScale {
+    /// `20 * log10(|X| / reference)`, as returned by [`crate::stft_frame`].
+    AmplitudeDb,
+    /// `10 * log10(|X|^2 / reference)`, `reference` being a power.
+    PowerDb,
+    /// `|X| / reference`.
+    Amplitude,
+    /// `|X|^2 / reference`.
+    Power,
+}
+
+/// Which peak a scaled frame is normalized to.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// Values are relative to `reference` only.
+    None,
+    /// The loudest bin of each frame becomes 0 dB (or 1 when linear).
+    Frame,
+    /// The loudest bin seen since creation or [`SpectrumOptions::reset_peak`]
+    /// becomes 0 dB (or 1 when linear).
+    Global,
+}
+
+/// Output scaling applied by [`stft_frame_with_options`].
+///
+/// # What
+/// Holds the [`SpectrumScale`], the `reference` that values are relative
+/// to, a `floor_db` below which dB values are clamped (pass `-Infinity` to
+/// disable it; linear scales ignore it) and the [`Normalization`]. For
+/// [`Normalization::Global`] the options also track the running peak, so
+/// reuse one instance for all frames of a view.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpectrumOptions {
+    scale: SpectrumScale,
+    reference: f32,
+    floor_db: f32,
+    normalization: Normalization,
+    peak: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SpectrumOptions {
+    /// Create options; see the type docs for the meaning of each argument.
+    ///
+    /// # Panics
+    /// Panics when `reference` is not a positive finite number or
+    /// `floor_db` is NaN.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        scale: SpectrumScale,
+        reference: f32,
+        floor_db: f32,
+        normalization: Normalization,
+    ) -> SpectrumOptions {
+        assert!(
+            reference.is_finite() && reference > 0.0,
+            "reference must be a positive finite number"
+        );
+        assert!(!floor_db.is_nan(), "floor_db must not be NaN");
+        SpectrumOptions {
+            scale,
+            reference,
+            floor_db,
+            normalization,
+            peak: 0.0,
+        }
+    }
+
+    /// Forget the running peak used by [`Normalization::Global`].
+    pub fn reset_peak(&mut self) {
+        self.peak = 0.0;
+    }
+}
+
+impl Default for SpectrumOptions {
+    /// The scaling of [`crate::stft_frame`] with a reference of 1.
+    fn default() -> Self {
+        SpectrumOptions::new(
+            SpectrumScale::AmplitudeDb,
+            1.0,
+            f32::NEG_INFINITY,
+            Normalization::None,
+        )
+    }
+}
+
+/// Compute an STFT frame scaled according to `options`.
+///
+/// # What
+/// Returns the `n` bins of [`crate::stft_frame`] in the layout it uses. The
+/// linear values `|X| / reference` or `|X|^2 / reference` are divided by
+/// the selected peak, converted to dB for the dB scales and clamped to
+/// `floor_db`. With [`Normalization::Global`] the running peak in `options`
+/// is raised to this frame's peak first.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or the window name is
+/// unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frame_with_options(
+    input: &[f32],
+    window_type: &str,
+    options: &mut SpectrumOptions,
+) -> Vec<f32> {
+    validate_finite(input);
+    let mut values = magnitudes_unchecked(&window_samples(input, parse_window(window_type)));
+    to_linear_scale(&mut values, options.scale, options.reference);
+    let frame_peak = values.iter().copied().fold(0.0, f32::max);
+    options.peak = options.peak.max(frame_peak);
+    let peak = match options.normalization {
+        Normalization::None => 1.0,
+        Normalization::Frame => frame_peak,
+        Normalization::Global => options.peak,
+    };
+    if peak > 0.0 {
+        values.iter_mut().for_each(|v| *v /= peak);
+    }
+    to_db_scale(&mut values, options.scale, options.floor_db);
+    values
+}
+
+/// Convert linear magnitudes to the values of `scale`, unnormalized and
+/// without a floor.
+pub(crate) fn apply_scale(magnitudes: &mut [f32], scale: SpectrumScale, reference: f32) {
+    to_linear_scale(magnitudes, scale, reference);
+    to_db_scale(magnitudes, scale, f32::NEG_INFINITY);
+}
+
+/// Square magnitudes for the power scales and divide by `reference`.
+fn to_linear_scale(values: &mut [f32], scale: SpectrumScale, reference: f32) {
+    let power = matches!(scale, SpectrumScale::PowerDb | SpectrumScale::Power);
+    for v in values {
+        if power {
+            *v *= *v;
+        }
+        *v /= reference;
+    }
+}
+
+/// Take the logarithm for the dB scales, clamping at `floor_db`; linear
+/// scales are left unchanged.
+fn to_db_scale(values: &mut [f32], scale: SpectrumScale, floor_db: f32) {
+    let db_scale = match scale {
+        SpectrumScale::AmplitudeDb => DB_SCALE,
+        SpectrumScale::PowerDb => POWER_DB_SCALE,
+        SpectrumScale::Amplitude | SpectrumScale::Power => return,
+    };
+    for v in values {
+        *v = (db_scale * v.max(LINEAR_FLOOR).log10()).max(floor_db);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stft_frame;
+
+    /// Tolerance for dB comparisons in tests.
+    const DB_TOLERANCE: f32 = 1e-3;
+
+    /// A 1 kHz tone at 48 kHz with amplitude `gain`.
+    fn tone(gain: f32) -> Vec<f32> {
+        (0..256)
+            .map(|i| gain * (std::f32::consts::TAU * 1000.0 * i as f32 / 48_000.0).sin())
+            .collect()
+    }
+
+    /// Default options reproduce `stft_frame`, and power dB equals
+    /// amplitude dB for a unit reference.
+    #[test]
+    fn default_options_match_stft_frame() {
+        let input = tone(0.5);
+        let expected = stft_frame(&input, "hann", 1.0);
+        let mut options = SpectrumOptions::default();
+        let amplitude = stft_frame_with_options(&input, "hann", &mut options);
+        let mut options = SpectrumOptions::new(
+            SpectrumScale::PowerDb,
+            1.0,
+            f32::NEG_INFINITY,
+            Normalization::None,
+        );
+        let power = stft_frame_with_options(&input, "hann", &mut options);
+        for ((e, a), p) in expected.iter().zip(&amplitude).zip(&power) {
+            assert!((e - a).abs() < DB_TOLERANCE);
+            assert!((e - p).abs() < DB_TOLERANCE);
+        }
+    }
+
+    /// Frame normalization puts the peak at 0 dB, the floor clamps the rest
+    /// and global normalization remembers louder earlier frames.
+    #[test]
+    fn normalization_and_floor() {
+        let mut options =
+            SpectrumOptions::new(SpectrumScale::AmplitudeDb, 1.0, -60.0, Normalization::Frame);
+        let frame = stft_frame_with_options(&tone(0.1), "hann", &mut options);
+        let max = frame.iter().copied().fold(f32::MIN, f32::max);
+        assert!(max.abs() < DB_TOLERANCE);
+        assert!(frame.iter().all(|&v| v >= -60.0));
+
+        let mut options = SpectrumOptions::new(
+            SpectrumScale::Amplitude,
+            1.0,
+            f32::NEG_INFINITY,
+            Normalization::Global,
+        );
+        stft_frame_with_options(&tone(1.0), "hann", &mut options);
+        let quiet = stft_frame_with_options(&tone(0.25), "hann", &mut options);
+        let max = quiet.iter().copied().fold(0.0, f32::max);
+        assert!((max - 0.25).abs() < 1e-4);
+        options.reset_peak();
+        let quiet = stft_frame_with_options(&tone(0.25), "hann", &mut options);
+        let max = quiet.iter().copied().fold(0.0, f32::max);
+        assert!((max - 1.0).abs() < 1e-4);
+    }
+}
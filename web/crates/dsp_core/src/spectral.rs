@@ -0,0 +1,420 @@
+//! Bin-wise operations on complex spectra.
+//!
+//! # What
+//! Helpers that combine one or more interleaved `[re0, im0, re1, im1, ...]`
+//! spectra, as produced by [`crate::fft_real`], into derived spectra.
+//!
+//! # Why
+//! Measurement workflows (transfer functions, impedance, averaging) need
+//! per-bin complex arithmetic that is too slow to run in JavaScript for large
+//! FFT sizes.
+
+use crate::{rfft_unchecked, validate_finite, TWO_PI};
+use std::f32::consts::PI;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Compute the regularized complex quotient `num / den` bin by bin.
+///
+/// # What
+/// Both inputs are interleaved complex spectra of equal length. The output
+/// uses the same interleaved layout and contains `num * conj(den) /
+/// (|den|^2 + epsilon)` for every bin.
+///
+/// # Why
+/// Two-port and impedance measurements divide a response spectrum by an
+/// excitation spectrum. Bins where the excitation carries almost no energy
+/// would otherwise explode towards infinity; `epsilon` bounds the result.
+///
+/// # Panics
+/// Panics when the inputs differ in length, have an odd length, or contain
+/// non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn complex_ratio(num: &[f32], den: &[f32], epsilon: f32) -> Vec<f32> {
+    assert_eq!(
+        num.len(),
+        den.len(),
+        "numerator and denominator spectra must have equal length"
+    );
+    assert!(
+        num.len().is_multiple_of(2),
+        "interleaved complex spectra must have even length"
+    );
+    validate_finite(num);
+    validate_finite(den);
+
+    let eps = epsilon.max(0.0);
+    let mut output = Vec::with_capacity(num.len());
+    for (n, d) in num.chunks_exact(2).zip(den.chunks_exact(2)) {
+        let (a, b) = (n[0], n[1]);
+        let (c, e) = (d[0], d[1]);
+        let denom = c * c + e * e + eps;
+        if denom == 0.0 {
+            // Only reachable with `epsilon == 0` and an empty bin.
+            output.push(0.0);
+            output.push(0.0);
+            continue;
+        }
+        // (a + ib)(c - ie) = (ac + be) + i(bc - ae)
+        output.push((a * c + b * e) / denom);
+        output.push((b * c - a * e) / denom);
+    }
+    output
+}
+
+/// Length of each of the `count` equal blocks stored back to back in `data`.
+fn block_len(data: &[f32], count: usize) -> usize {
+    assert!(count > 0, "count must be positive");
+    assert!(
+        data.len().is_multiple_of(count),
+        "input length must be a multiple of count"
+    );
+    data.len() / count
+}
+
+/// Element-wise mean of the equally sized blocks of `data`.
+fn mean_blocks(data: &[f32], len: usize) -> Vec<f32> {
+    let mut acc = vec![0.0f32; len];
+    for block in data.chunks_exact(len) {
+        for (a, &b) in acc.iter_mut().zip(block) {
+            *a += b;
+        }
+    }
+    let scale = len as f32 / data.len() as f32;
+    for a in &mut acc {
+        *a *= scale;
+    }
+    acc
+}
+
+/// Coherently average `count` interleaved complex spectra stored back to back.
+///
+/// # What
+/// Returns the element-wise mean of the complex values, in the same
+/// interleaved layout as a single input spectrum.
+///
+/// # Why
+/// For stimuli synchronized with the capture, averaging complex values lets
+/// random noise cancel out: its power drops by `1 / count` while the
+/// phase-locked signal is preserved.
+///
+/// # Panics
+/// Panics when `count` is zero, the input length is not a multiple of
+/// `count`, a spectrum has odd length, or the input contains non-finite
+/// values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn average_complex(spectra: &[f32], count: usize) -> Vec<f32> {
+    validate_finite(spectra);
+    let len = block_len(spectra, count);
+    assert!(
+        len.is_multiple_of(2),
+        "interleaved complex spectra must have even length"
+    );
+    if len == 0 {
+        return Vec::new();
+    }
+    mean_blocks(spectra, len)
+}
+
+/// Incoherently average `count` magnitude spectra stored back to back.
+///
+/// # What
+/// Returns the element-wise mean of the magnitudes.
+///
+/// # Why
+/// Magnitude averaging discards phase, so it works for unsynchronized
+/// signals. It smooths the noise floor but does not lower it, unlike
+/// [`average_complex`].
+///
+/// # Panics
+/// Panics when `count` is zero, the input length is not a multiple of
+/// `count`, or the input contains non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn average_magnitude(mags: &[f32], count: usize) -> Vec<f32> {
+    validate_finite(mags);
+    let len = block_len(mags, count);
+    if len == 0 {
+        return Vec::new();
+    }
+    mean_blocks(mags, len)
+}
+
+/// Time-domain energy of a block: the sum of squared samples.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn total_energy(input: &[f32]) -> f32 {
+    validate_finite(input);
+    input.iter().map(|&x| x * x).sum()
+}
+
+/// Energy of a block computed from its single-sided magnitude spectrum.
+///
+/// # What
+/// `magnitudes` holds the linear magnitudes of bins `0..=n/2` of an
+/// unnormalized FFT of an even-length block of `n = 2 * (len - 1)` samples.
+/// Interior bins are counted twice to account for their mirrored negative
+/// frequency; DC and Nyquist appear once. The sum is divided by `n`, so the
+/// result equals [`total_energy`] of the block (Parseval's theorem).
+///
+/// # Why
+/// A level meter driven by the spectrum must weight the edge bins
+/// correctly or it over-reads DC-heavy and Nyquist-heavy content.
+///
+/// # Panics
+/// Panics when `magnitudes` contains non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spectral_energy(magnitudes: &[f32]) -> f32 {
+    validate_finite(magnitudes);
+    let bins = magnitudes.len();
+    if bins < 2 {
+        // A single bin describes a one-sample block.
+        return magnitudes.first().map_or(0.0, |m| m * m);
+    }
+    let n = 2 * (bins - 1);
+    let edges = magnitudes[0] * magnitudes[0] + magnitudes[bins - 1] * magnitudes[bins - 1];
+    let interior: f32 = magnitudes[1..bins - 1].iter().map(|&m| m * m).sum();
+    (edges + 2.0 * interior) / n as f32
+}
+
+/// Wrap an angle in radians into `(-pi, pi]`.
+pub(crate) fn wrap_phase(angle: f32) -> f32 {
+    let wrapped = (angle + PI).rem_euclid(TWO_PI) - PI;
+    if wrapped <= -PI {
+        wrapped + TWO_PI
+    } else {
+        wrapped
+    }
+}
+
+/// Instantaneous frequency of every bin from the phases of two frames.
+///
+/// # What
+/// `prev_phase` and `cur_phase` hold the phases of bins `0..=fft_size/2`
+/// (as in [`crate::stft_frame_polar`]) of two frames `hop` samples apart,
+/// so `fft_size = 2 * (len - 1)`. Bin `k` is expected to advance by
+/// `2 * pi * k * hop / fft_size`; the deviation from that, wrapped to
+/// `(-pi, pi]`, refines its nominal frequency `k * sample_rate / fft_size`
+/// to `k * sample_rate / fft_size + deviation * sample_rate / (2 * pi *
+/// hop)`. Returns one frequency in Hz per bin.
+///
+/// # Why
+/// A bin spans `sample_rate / fft_size` Hz; the phase advance pins a
+/// sinusoid's frequency far more precisely, which phase vocoders need and
+/// which makes hover readouts exact.
+///
+/// # Panics
+/// Panics when the phase arrays differ in length or contain non-finite
+/// values, `hop` is zero, or `sample_rate` is not positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn instantaneous_frequency(
+    prev_phase: &[f32],
+    cur_phase: &[f32],
+    hop: usize,
+    sample_rate: f32,
+) -> Vec<f32> {
+    assert_eq!(
+        prev_phase.len(),
+        cur_phase.len(),
+        "phase frames must have equal length"
+    );
+    validate_finite(prev_phase);
+    validate_finite(cur_phase);
+    assert!(hop > 0, "hop must be positive");
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    let fft_size = (2 * prev_phase.len().saturating_sub(1)).max(1) as f64;
+    let hop = hop as f64;
+    prev_phase
+        .iter()
+        .zip(cur_phase)
+        .enumerate()
+        .map(|(k, (&prev, &cur))| {
+            // The expected advance grows with `k * hop`; wrap it in f64
+            // before it loses the fractional part in f32.
+            let expected =
+                (std::f64::consts::TAU * k as f64 * hop / fft_size) % std::f64::consts::TAU;
+            let deviation = wrap_phase((cur as f64 - prev as f64 - expected) as f32) as f64;
+            let hz = (k as f64 / fft_size + deviation / (std::f64::consts::TAU * hop))
+                * sample_rate as f64;
+            hz as f32
+        })
+        .collect()
+}
+
+/// Per-bin phase difference between two channels.
+///
+/// # What
+/// Transforms both channels and returns `angle(L) - angle(R)` wrapped to
+/// `(-pi, pi]` for bins `0..=n/2`. A pure delay of `d` samples in the right
+/// channel shows up as a phase difference of `2 * pi * k * d / n` at bin
+/// `k`.
+///
+/// # Why
+/// Stereo imaging and direction-of-arrival displays need the inter-channel
+/// phase per frequency rather than a single broadband correlation value.
+///
+/// # How
+/// Both channels go through the half-spectrum [`crate::rfft`]; the angle of
+/// `L * conj(R)` equals the difference of the angles but is wrapped by
+/// construction.
+///
+/// # Panics
+/// Panics when the channels differ in length or contain non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn interchannel_phase(left: &[f32], right: &[f32]) -> Vec<f32> {
+    assert_eq!(
+        left.len(),
+        right.len(),
+        "left and right channels must have equal length"
+    );
+    validate_finite(left);
+    validate_finite(right);
+    rfft_unchecked(left)
+        .iter()
+        .zip(rfft_unchecked(right))
+        .map(|(l, r)| wrap_phase((l * r.conj()).arg()))
+        .collect()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fft_real, half_spectrum_magnitudes, test_support::noise};
+
+    /// Tolerance for floating point comparisons in tests.
+    const TOLERANCE: f32 = 1e-3;
+
+    /// Regularization small enough not to bias well-conditioned bins.
+    const RATIO_EPSILON: f32 = 1e-9;
+
+    /// A spectrum divided by itself yields unity in every bin.
+    #[test]
+    fn complex_ratio_of_identical_spectra_is_unity() {
+        let data: Vec<f32> = (0..32).map(|i| (i as f32 * 0.37).sin() + 0.5).collect();
+        let spec = fft_real(&data);
+        let ratio = complex_ratio(&spec, &spec, RATIO_EPSILON);
+        assert_eq!(ratio.len(), spec.len());
+        for pair in ratio.chunks_exact(2) {
+            assert!((pair[0] - 1.0).abs() < TOLERANCE, "re {}", pair[0]);
+            assert!(pair[1].abs() < TOLERANCE, "im {}", pair[1]);
+        }
+    }
+
+    /// Mismatched spectrum lengths are rejected.
+    #[test]
+    #[should_panic(expected = "equal length")]
+    fn complex_ratio_rejects_mismatched_lengths() {
+        complex_ratio(&[1.0, 0.0], &[1.0, 0.0, 1.0, 0.0], RATIO_EPSILON);
+    }
+
+    /// Ratio of the mean non-tone bin magnitude to the tone bin magnitude.
+    fn floor_to_tone(mags: &[f32], tone_bin: usize) -> f32 {
+        let floor: f32 = mags
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != tone_bin)
+            .map(|(_, &m)| m)
+            .sum::<f32>()
+            / (mags.len() - 1) as f32;
+        floor / mags[tone_bin]
+    }
+
+    /// Coherent averaging lowers the noise floor of a phase-locked tone
+    /// much further than magnitude averaging does.
+    #[test]
+    fn coherent_average_beats_incoherent_for_stationary_tone() {
+        let len = 64;
+        let blocks = 64;
+        let tone_bin = 5;
+        let mut spectra = Vec::new();
+        let mut mags = Vec::new();
+        for b in 0..blocks {
+            let noise = noise(len, b as u64 + 1);
+            let block: Vec<f32> = (0..len)
+                .map(|i| (TWO_PI * tone_bin as f32 * i as f32 / len as f32).cos() + noise[i])
+                .collect();
+            let spec = fft_real(&block);
+            mags.extend(
+                spec.chunks_exact(2)
+                    .map(|c| (c[0] * c[0] + c[1] * c[1]).sqrt()),
+            );
+            spectra.extend(spec);
+        }
+
+        let coherent: Vec<f32> = average_complex(&spectra, blocks)
+            .chunks_exact(2)
+            .map(|c| (c[0] * c[0] + c[1] * c[1]).sqrt())
+            .collect();
+        let incoherent = average_magnitude(&mags, blocks);
+        assert_eq!(coherent.len(), len / 2 + 1);
+        assert_eq!(incoherent.len(), len / 2 + 1);
+
+        let coherent_ratio = floor_to_tone(&coherent[..len / 2], tone_bin);
+        let incoherent_ratio = floor_to_tone(&incoherent[..len / 2], tone_bin);
+        // Noise amplitude shrinks by roughly sqrt(blocks) = 8 coherently.
+        assert!(
+            coherent_ratio * 4.0 < incoherent_ratio,
+            "coherent {coherent_ratio} vs incoherent {incoherent_ratio}"
+        );
+    }
+
+    /// Time-domain and spectral energy agree for a random block.
+    #[test]
+    fn total_energy_matches_spectral_energy() {
+        let block: Vec<f32> = noise(128, 7).iter().map(|v| v + 0.25).collect();
+        let time = total_energy(&block);
+        let freq = spectral_energy(&half_spectrum_magnitudes(&block));
+        assert!((time - freq).abs() < TOLERANCE * time, "{time} vs {freq}");
+    }
+
+    /// A delayed right channel produces a linear phase difference.
+    #[test]
+    fn interchannel_phase_reflects_delay() {
+        let n = 64;
+        let delay = 3;
+        let left = noise(n, 11);
+        // Circular delay keeps the relation exact for the DFT.
+        let right: Vec<f32> = (0..n).map(|i| left[(i + n - delay) % n]).collect();
+        let phase = interchannel_phase(&left, &right);
+        assert_eq!(phase.len(), n / 2 + 1);
+        for (k, &p) in phase.iter().enumerate() {
+            let expected = wrap_phase(TWO_PI * k as f32 * delay as f32 / n as f32);
+            assert!(
+                wrap_phase(p - expected).abs() < TOLERANCE,
+                "bin {k}: {p} vs {expected}"
+            );
+        }
+    }
+
+    /// Two hann frames of a between-bins tone give its exact frequency in
+    /// the peak bin and its neighbours.
+    #[test]
+    fn instantaneous_frequency_refines_bin_frequency() {
+        let (n, hop, rate, hz) = (512, 128, 8000.0f32, 1234.5f32);
+        let signal: Vec<f32> = (0..n + hop)
+            .map(|i| (TWO_PI * hz * i as f32 / rate).sin())
+            .collect();
+        let phases = |start: usize| -> Vec<f32> {
+            crate::stft_frame_polar(&signal[start..start + n], "hann", 1.0)
+                .iter()
+                .skip(1)
+                .step_by(2)
+                .copied()
+                .collect()
+        };
+        let freqs = instantaneous_frequency(&phases(0), &phases(hop), hop, rate);
+        assert_eq!(freqs.len(), n / 2 + 1);
+        let bin = (hz * n as f32 / rate).round() as usize;
+        for f in &freqs[bin - 1..=bin + 1] {
+            assert!((f - hz).abs() < 0.5, "{f}");
+        }
+    }
+}
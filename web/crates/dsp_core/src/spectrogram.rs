@@ -0,0 +1,1287 @@
+//! Operations on whole spectrogram matrices.
+//!
+//! # What
+//! Functions that take `n_frames` magnitude frames of `n_bins` bins each,
+//! stored frame-major (`frames[frame * n_bins + bin]`), and transform them
+//! across time and frequency.
+//!
+//! # Why
+//! Display clean-up such as background removal touches every cell of the
+//! matrix; doing it in WASM avoids walking large arrays in JavaScript.
+
+use crate::{
+    half_spectrum_magnitudes, linear_to_dbfs_in_place, magnitude_dbfs_unchecked, parse_window,
+    validate_finite, window_samples, WindowType, EPSILON,
+};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A frame-major matrix returned together with its dimensions.
+///
+/// # What
+/// Holds `n_frames` rows of `n_bins` values each, stored as
+/// `data[frame * n_bins + bin]`.
+///
+/// # Why
+/// A bare `Vec<f32>` loses its shape across the JS boundary; returning the
+/// dimensions alongside the values saves callers from recomputing frame
+/// counts from hop sizes.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrogram {
+    n_frames: usize,
+    n_bins: usize,
+    data: Vec<f32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Spectrogram {
+    /// Number of frames (rows).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn n_frames(&self) -> usize {
+        self.n_frames
+    }
+
+    /// Number of bins per frame (columns).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn n_bins(&self) -> usize {
+        self.n_bins
+    }
+
+    /// Copy of the frame-major values.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn data(&self) -> Vec<f32> {
+        self.data.clone()
+    }
+}
+
+impl Spectrogram {
+    /// Wrap frame-major `data` of `n_frames` rows by `n_bins` columns.
+    ///
+    /// # Panics
+    /// Panics when `data.len() != n_frames * n_bins`.
+    pub(crate) fn new(n_frames: usize, n_bins: usize, data: Vec<f32>) -> Spectrogram {
+        assert_eq!(
+            data.len(),
+            n_frames * n_bins,
+            "data length must equal n_frames * n_bins"
+        );
+        Spectrogram {
+            n_frames,
+            n_bins,
+            data,
+        }
+    }
+
+    /// Borrow the frame-major values without copying.
+    pub fn values(&self) -> &[f32] {
+        &self.data
+    }
+}
+
+/// Assert that `frames` holds exactly `n_frames * n_bins` finite values.
+fn check_matrix(frames: &[f32], n_frames: usize, n_bins: usize) {
+    assert_eq!(
+        frames.len(),
+        n_frames * n_bins,
+        "frames length must equal n_frames * n_bins"
+    );
+    validate_finite(frames);
+}
+
+/// Kernel half-width in standard deviations for Gaussian smoothing.
+const GAUSSIAN_RADIUS_SIGMAS: f32 = 3.0;
+
+/// Normalized Gaussian kernel of standard deviation `sigma`, truncated at
+/// [`GAUSSIAN_RADIUS_SIGMAS`]. `sigma <= 0` gives the identity kernel.
+fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    if sigma <= 0.0 {
+        return vec![1.0];
+    }
+    let radius = (GAUSSIAN_RADIUS_SIGMAS * sigma).ceil() as isize;
+    let mut kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    for k in &mut kernel {
+        *k /= sum;
+    }
+    kernel
+}
+
+/// Convolve `len` values read through `get` with a centered `kernel`,
+/// clamping indices at the edges, and write the results through `set`.
+fn convolve_clamped(
+    len: usize,
+    kernel: &[f32],
+    get: impl Fn(usize) -> f32,
+    mut set: impl FnMut(usize, f32),
+) {
+    let radius = (kernel.len() / 2) as isize;
+    let last = len as isize - 1;
+    for i in 0..len {
+        let value = kernel
+            .iter()
+            .enumerate()
+            .map(|(k, &w)| {
+                let j = (i as isize + k as isize - radius).clamp(0, last);
+                w * get(j as usize)
+            })
+            .sum();
+        set(i, value);
+    }
+}
+
+/// Median of `values`, reordering the slice in the process.
+///
+/// Returns `0.0` for an empty slice; even lengths average the two middle
+/// values.
+fn median_in_place(values: &mut [f32]) -> f32 {
+    let n = values.len();
+    if n == 0 {
+        return 0.0;
+    }
+    let mid = n / 2;
+    let (lower, upper, _) = values.select_nth_unstable_by(mid, f32::total_cmp);
+    let upper = *upper;
+    if n % 2 == 1 {
+        upper
+    } else {
+        let lower_max = lower.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        0.5 * (lower_max + upper)
+    }
+}
+
+/// Median filter of `len` values read through `get` over windows reaching
+/// `radius` values to each side, clamping indices at the edges; results
+/// are written through `set`.
+fn median_filter_clamped(
+    len: usize,
+    radius: usize,
+    get: impl Fn(usize) -> f32,
+    mut set: impl FnMut(usize, f32),
+) {
+    let mut window = Vec::with_capacity(2 * radius + 1);
+    let last = len as isize - 1;
+    for i in 0..len {
+        window.clear();
+        window.extend(
+            (i as isize - radius as isize..=i as isize + radius as isize)
+                .map(|j| get(j.clamp(0, last) as usize)),
+        );
+        set(i, median_in_place(&mut window));
+    }
+}
+
+/// Number of complete `fft_size` frames spaced `hop` apart in `len` samples.
+pub(crate) fn frame_count(len: usize, fft_size: usize, hop: usize) -> usize {
+    if len < fft_size {
+        0
+    } else {
+        (len - fft_size) / hop + 1
+    }
+}
+
+/// Linear half-spectrum magnitudes of every complete frame of `input`.
+///
+/// Frames of `fft_size` samples start at multiples of `hop` and are
+/// multiplied by `window` before the FFT; trailing samples that do not fill
+/// a frame are ignored.
+pub(crate) fn half_spectrum_frames(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window: WindowType,
+) -> impl Iterator<Item = Vec<f32>> + '_ {
+    (0..frame_count(input.len(), fft_size, hop)).map(move |f| {
+        let start = f * hop;
+        half_spectrum_magnitudes(&window_samples(&input[start..start + fft_size], window))
+    })
+}
+
+/// Half-spectrum dBFS frames of a whole clip.
+///
+/// # What
+/// Windows every complete `fft_size` frame starting at multiples of `hop`
+/// and returns the dBFS magnitudes of bins `0..=fft_size/2`, frame-major.
+/// Trailing samples that do not fill a frame are ignored. Unknown window
+/// names panic, matching [`crate::stft_frame`].
+///
+/// With `drop_edges` the DC bin and the Nyquist bin are omitted from every
+/// frame, leaving `fft_size/2 - 1` bins per frame instead of
+/// `fft_size/2 + 1`. The total length is always `n_frames * bins_per_frame`.
+///
+/// # Why
+/// DC offset and the Nyquist bin carry little information and skew learned
+/// features; dropping them here keeps every frame consistently shaped.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, `hop` is zero, or
+/// `fft_size < 2`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_frames(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+    drop_edges: bool,
+) -> Vec<f32> {
+    validate_finite(input);
+    assert!(fft_size >= 2, "fft_size must be at least 2");
+    assert!(hop > 0, "hop must be positive");
+    let window = parse_window(window_type);
+    let n_frames = frame_count(input.len(), fft_size, hop);
+    let bins = fft_size / 2 + 1;
+    let kept = if drop_edges { 1..bins - 1 } else { 0..bins };
+
+    let mut output = Vec::with_capacity(n_frames * kept.len());
+    for mut mags in half_spectrum_frames(input, fft_size, hop, window) {
+        linear_to_dbfs_in_place(&mut mags, reference);
+        output.extend_from_slice(&mags[kept.clone()]);
+    }
+    output
+}
+
+/// [`stft_frames`] of every channel of an interleaved multichannel buffer.
+///
+/// # What
+/// `interleaved` holds frames of `channels` samples (`[l0, r0, l1, r1, ...]`
+/// for stereo). Each channel is de-interleaved and analysed like
+/// [`compute_spectrogram`]; the result is channel-major, i.e. all frames of
+/// channel 0, then all frames of channel 1, each frame holding
+/// `fft_size/2 + 1` dBFS bins. With `downmix` the channels are averaged to
+/// mono first and a single channel's frames are returned.
+///
+/// # Why
+/// Splitting channels in JavaScript costs one copy and one WASM call per
+/// channel; doing it here needs a single call per buffer.
+///
+/// # Panics
+/// Panics under the same conditions as [`stft_frames`], when `channels` is
+/// zero, or when `interleaved.len()` is not a multiple of `channels`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_multichannel(
+    interleaved: &[f32],
+    channels: usize,
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+    downmix: bool,
+) -> Vec<f32> {
+    assert!(channels > 0, "channels must be positive");
+    assert!(
+        interleaved.len().is_multiple_of(channels),
+        "interleaved length must be a multiple of channels"
+    );
+    if downmix {
+        let scale = 1.0 / channels as f32;
+        let mono: Vec<f32> = interleaved
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() * scale)
+            .collect();
+        return stft_frames(&mono, fft_size, hop, window_type, reference, false);
+    }
+    let mut output = Vec::new();
+    for channel in 0..channels {
+        let samples: Vec<f32> = interleaved
+            .iter()
+            .skip(channel)
+            .step_by(channels)
+            .copied()
+            .collect();
+        output.extend(stft_frames(
+            &samples,
+            fft_size,
+            hop,
+            window_type,
+            reference,
+            false,
+        ));
+    }
+    output
+}
+
+/// Full-length [`crate::stft_frame`] rows of a whole signal.
+///
+/// # What
+/// Frames of `frame_size` samples start every `hop` samples until the end
+/// of `samples` is covered; the last, partial frame is zero-padded rather
+/// than dropped. For `samples.len() >= frame_size` this yields
+/// `ceil((samples.len() - frame_size) / hop) + 1` rows, otherwise a single
+/// padded row (none for empty input). Each row holds the `frame_size`
+/// dBFS bins of [`crate::stft_frame`], rows concatenated.
+///
+/// # Why
+/// Analysing a whole recording in one call replaces thousands of JS-side
+/// slices and boundary crossings.
+///
+/// # Panics
+/// Panics when `samples` contains non-finite values or `frame_size` or
+/// `hop` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_signal(
+    samples: &[f32],
+    frame_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+) -> Vec<f32> {
+    validate_finite(samples);
+    assert!(frame_size > 0, "frame_size must be positive");
+    assert!(hop > 0, "hop must be positive");
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let window = parse_window(window_type);
+    let n_rows = samples.len().saturating_sub(frame_size).div_ceil(hop) + 1;
+    let mut frame = vec![0.0f32; frame_size];
+    let mut output = Vec::with_capacity(n_rows * frame_size);
+    for row in 0..n_rows {
+        let start = row * hop;
+        let end = (start + frame_size).min(samples.len());
+        frame.fill(0.0);
+        frame[..end - start].copy_from_slice(&samples[start..end]);
+        output.extend(magnitude_dbfs_unchecked(
+            &window_samples(&frame, window),
+            reference,
+        ));
+    }
+    output
+}
+
+/// Half-spectrum dBFS spectrogram of a whole clip, with its dimensions.
+///
+/// Equivalent to [`stft_frames`] without `drop_edges`: `n_frames` rows of
+/// `fft_size/2 + 1` bins.
+///
+/// # Panics
+/// Panics under the same conditions as [`stft_frames`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compute_spectrogram(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+) -> Spectrogram {
+    let data = stft_frames(input, fft_size, hop, window_type, reference, false);
+    let n_bins = fft_size / 2 + 1;
+    Spectrogram::new(data.len() / n_bins, n_bins, data)
+}
+
+/// [`compute_spectrogram`] with the frames split across worker threads.
+///
+/// # What
+/// Returns exactly what [`compute_spectrogram`] returns. With the `threads`
+/// cargo feature on a native target, the frames are divided into `threads`
+/// contiguous runs (`0` uses every available core) that are analyzed
+/// concurrently by [`compute_spectrogram_range`] and joined in order;
+/// otherwise, or for `threads == 1`, the call runs on the current thread.
+///
+/// `wasm32` has no native threads and this crate does not build the
+/// shared-memory module `wasm-bindgen-rayon` needs, so in the browser the
+/// call is single-threaded. To parallelize there, JavaScript splits the
+/// frame count the same way and has each Web Worker call
+/// [`compute_spectrogram_range`] on its own module instance.
+///
+/// # Why
+/// Analyzing a ten-minute recording at 75% overlap takes seconds on one
+/// core, while the frames are independent of each other.
+///
+/// # Panics
+/// Panics under the same conditions as [`stft_frames`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compute_spectrogram_parallel(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+    threads: usize,
+) -> Spectrogram {
+    validate_finite(input);
+    assert!(fft_size >= 2, "fft_size must be at least 2");
+    assert!(hop > 0, "hop must be positive");
+    parse_window(window_type);
+    let n_frames = frame_count(input.len(), fft_size, hop);
+    let workers = worker_count(threads).min(n_frames);
+    if workers <= 1 {
+        return compute_spectrogram(input, fft_size, hop, window_type, reference);
+    }
+    let per_worker = n_frames.div_ceil(workers);
+    let data: Vec<f32> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..n_frames)
+            .step_by(per_worker)
+            .map(|first| {
+                let count = per_worker.min(n_frames - first);
+                scope.spawn(move || {
+                    frame_range(input, fft_size, hop, window_type, reference, first, count)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    });
+    let n_bins = fft_size / 2 + 1;
+    Spectrogram::new(n_frames, n_bins, data)
+}
+
+/// Frames `first_frame..first_frame + n_frames` of [`compute_spectrogram`].
+///
+/// # What
+/// Returns a [`Spectrogram`] of `n_frames` rows, bit-identical to those
+/// rows of the whole-clip result, reading only the samples they cover.
+///
+/// # Why
+/// This is the unit of work [`compute_spectrogram_parallel`] hands to each
+/// thread, exported so browser code can hand the same ranges to Web
+/// Workers and concatenate the results in order.
+///
+/// # Panics
+/// Panics under the same conditions as [`stft_frames`], or when the range
+/// extends past the last complete frame.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compute_spectrogram_range(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+    first_frame: usize,
+    n_frames: usize,
+) -> Spectrogram {
+    validate_finite(input);
+    assert!(fft_size >= 2, "fft_size must be at least 2");
+    assert!(hop > 0, "hop must be positive");
+    parse_window(window_type);
+    assert!(
+        first_frame + n_frames <= frame_count(input.len(), fft_size, hop),
+        "frame range exceeds the frame count"
+    );
+    let data = frame_range(
+        input,
+        fft_size,
+        hop,
+        window_type,
+        reference,
+        first_frame,
+        n_frames,
+    );
+    Spectrogram::new(n_frames, fft_size / 2 + 1, data)
+}
+
+/// Rows `first..first + count` of [`compute_spectrogram`], without
+/// validation.
+fn frame_range(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+    first: usize,
+    count: usize,
+) -> Vec<f32> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let span = &input[first * hop..(first + count - 1) * hop + fft_size];
+    stft_frames(span, fft_size, hop, window_type, reference, false)
+}
+
+/// Threads [`compute_spectrogram_parallel`] uses for a `threads` request.
+#[cfg(all(feature = "threads", not(target_arch = "wasm32")))]
+fn worker_count(threads: usize) -> usize {
+    match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Threads [`compute_spectrogram_parallel`] uses: one without `threads`.
+#[cfg(not(all(feature = "threads", not(target_arch = "wasm32"))))]
+fn worker_count(_threads: usize) -> usize {
+    1
+}
+
+/// Full-length [`crate::stft_frame`] rows of every complete frame, with
+/// their dimensions.
+///
+/// # What
+/// Frames of `fft_size` samples start every `hop_size` samples; each row
+/// holds the `fft_size` dBFS bins [`crate::stft_frame`] returns for that
+/// slice. Trailing samples that do not fill a frame are ignored, unlike
+/// [`stft_signal`], which zero-pads them into a last frame.
+///
+/// # Why
+/// Replaces a JS loop that slices the buffer and crosses the WASM boundary
+/// once per hop with a single call.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, `fft_size < 2`, or
+/// `hop_size` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn stft_all(
+    input: &[f32],
+    fft_size: usize,
+    hop_size: usize,
+    window_type: &str,
+    reference: f32,
+) -> Spectrogram {
+    validate_finite(input);
+    assert!(fft_size >= 2, "fft_size must be at least 2");
+    assert!(hop_size > 0, "hop must be positive");
+    let window = parse_window(window_type);
+    let n_frames = frame_count(input.len(), fft_size, hop_size);
+    let mut data = Vec::with_capacity(n_frames * fft_size);
+    for f in 0..n_frames {
+        let frame = &input[f * hop_size..f * hop_size + fft_size];
+        data.extend(magnitude_dbfs_unchecked(
+            &window_samples(frame, window),
+            reference,
+        ));
+    }
+    Spectrogram::new(n_frames, fft_size, data)
+}
+
+/// Append the [`compute_spectrogram`] rows of `input` to a caller buffer.
+///
+/// # What
+/// Writes the `fft_size/2 + 1`-bin dBFS rows of every complete frame of
+/// `input` into `out` starting at `out_offset` and returns the number of
+/// values written. To continue a spectrogram with the next chunk of a long
+/// file, pass input starting at the next frame position (`n_frames * hop`
+/// samples after the previous chunk's start).
+///
+/// # Why
+/// Long files processed in chunks can fill one pre-allocated matrix
+/// without reallocating or concatenating intermediate vectors.
+///
+/// # Panics
+/// Panics under the same conditions as [`stft_frames`], or when `out` has
+/// fewer than `out_offset` plus the written values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn append_spectrogram(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+    out: &mut [f32],
+    out_offset: usize,
+) -> usize {
+    validate_finite(input);
+    assert!(fft_size >= 2, "fft_size must be at least 2");
+    assert!(hop > 0, "hop must be positive");
+    let n_bins = fft_size / 2 + 1;
+    let written = frame_count(input.len(), fft_size, hop) * n_bins;
+    assert!(
+        out_offset
+            .checked_add(written)
+            .is_some_and(|end| end <= out.len()),
+        "out lacks space for the spectrogram rows"
+    );
+    let window = parse_window(window_type);
+    let rows = out[out_offset..out_offset + written].chunks_exact_mut(n_bins);
+    for (mut mags, row) in half_spectrum_frames(input, fft_size, hop, window).zip(rows) {
+        linear_to_dbfs_in_place(&mut mags, reference);
+        row.copy_from_slice(&mags);
+    }
+    written
+}
+
+/// [`compute_spectrogram`] with runs of silent frames collapsed.
+///
+/// # What
+/// A frame is silent when none of its bins reaches `silence_db`. The output
+/// is a flat sequence of records, in frame order:
+///
+/// * active frame: `[index, v0, v1, ..., v(n_bins - 1)]`, a non-negative
+///   frame index followed by the frame's `fft_size/2 + 1` dB values exactly
+///   as [`compute_spectrogram`] returns them;
+/// * silent run: `[-count, start]`, a negative header holding the number of
+///   consecutive silent frames followed by the index of the first one.
+///
+/// The sign of each header tells a reader how many values follow. Frame
+/// indices are stored as `f32` and stay exact below `2^24` frames.
+///
+/// # Why
+/// Long recordings that are mostly silence would otherwise ship every
+/// floor-valued frame to JS in full.
+///
+/// # Panics
+/// Panics under the same conditions as [`stft_frames`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compute_spectrogram_sparse(
+    input: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+    silence_db: f32,
+) -> Vec<f32> {
+    let data = stft_frames(input, fft_size, hop, window_type, reference, false);
+    let n_bins = fft_size / 2 + 1;
+    let mut out = Vec::new();
+    let mut run: Option<(usize, usize)> = None;
+    for (index, frame) in data.chunks_exact(n_bins).enumerate() {
+        if frame.iter().all(|&db| db < silence_db) {
+            let (start, count) = run.unwrap_or((index, 0));
+            run = Some((start, count + 1));
+            continue;
+        }
+        if let Some((start, count)) = run.take() {
+            out.extend([-(count as f32), start as f32]);
+        }
+        out.push(index as f32);
+        out.extend_from_slice(frame);
+    }
+    if let Some((start, count)) = run {
+        out.extend([-(count as f32), start as f32]);
+    }
+    out
+}
+
+/// Per-cell dB difference between the spectrograms of two signals.
+///
+/// # What
+/// Computes the [`compute_spectrogram`] of `a` and of `b` and returns
+/// `a_db - b_db` for every frame and bin. Magnitudes are floored at a tiny
+/// epsilon before the dB conversion so silent cells subtract to finite
+/// values. The dimensions match [`compute_spectrogram`].
+///
+/// # Why
+/// A/B listening tests of a processing chain are easier to judge with a
+/// map of where, in time and frequency, the processed signal differs.
+///
+/// # Panics
+/// Panics when `a` and `b` differ in length or under the same conditions
+/// as [`stft_frames`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spectrogram_difference(
+    a: &[f32],
+    b: &[f32],
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+    reference: f32,
+) -> Spectrogram {
+    assert_eq!(a.len(), b.len(), "inputs must have equal length");
+    validate_finite(a);
+    validate_finite(b);
+    assert!(fft_size >= 2, "fft_size must be at least 2");
+    assert!(hop > 0, "hop must be positive");
+    let window = parse_window(window_type);
+    let n_bins = fft_size / 2 + 1;
+    let to_db = |mut mags: Vec<f32>| {
+        for m in &mut mags {
+            *m = m.max(EPSILON);
+        }
+        linear_to_dbfs_in_place(&mut mags, reference);
+        mags
+    };
+    let mut data = Vec::with_capacity(frame_count(a.len(), fft_size, hop) * n_bins);
+    for (fa, fb) in half_spectrum_frames(a, fft_size, hop, window)
+        .zip(half_spectrum_frames(b, fft_size, hop, window))
+    {
+        data.extend(to_db(fa).iter().zip(&to_db(fb)).map(|(x, y)| x - y));
+    }
+    Spectrogram::new(data.len() / n_bins, n_bins, data)
+}
+
+/// Subtract each bin's temporal median from every frame, flooring at zero.
+///
+/// # What
+/// For every bin the median magnitude over all frames is treated as the
+/// stationary background and removed. The output has the same frame-major
+/// layout as the input.
+///
+/// # Why
+/// Constant tones and steady hum dominate long spectrograms; removing the
+/// per-bin median leaves only the transient events worth inspecting.
+///
+/// # Panics
+/// Panics when `frames.len() != n_frames * n_bins` or the matrix contains
+/// non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn remove_stationary(frames: &[f32], n_frames: usize, n_bins: usize) -> Vec<f32> {
+    check_matrix(frames, n_frames, n_bins);
+    let mut output = frames.to_vec();
+    let mut column = Vec::with_capacity(n_frames);
+    for bin in 0..n_bins {
+        column.clear();
+        column.extend((0..n_frames).map(|f| frames[f * n_bins + bin]));
+        let median = median_in_place(&mut column);
+        for f in 0..n_frames {
+            let cell = &mut output[f * n_bins + bin];
+            *cell = (*cell - median).max(0.0);
+        }
+    }
+    output
+}
+
+/// Per-bin kurtosis of the magnitude across frames.
+///
+/// # What
+/// Returns `n_bins` values: for every bin, the fourth standardized moment
+/// `E[(x - mean)^4] / var^2` of its magnitudes over all frames. Gaussian
+/// fluctuation gives about `3`; occasional bursts give much larger values.
+/// Bins with zero variance (including single-frame input) report `0`.
+///
+/// # Why
+/// Spectral kurtosis flags bins carrying impulsive, non-stationary energy,
+/// such as bearing faults, which steady tones and broadband noise hide in
+/// an average spectrum.
+///
+/// # Panics
+/// Panics when `frames.len() != n_frames * n_bins` or the matrix contains
+/// non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spectral_kurtosis(frames: &[f32], n_frames: usize, n_bins: usize) -> Vec<f32> {
+    check_matrix(frames, n_frames, n_bins);
+    (0..n_bins)
+        .map(|bin| {
+            let column = || (0..n_frames).map(|f| frames[f * n_bins + bin] as f64);
+            let mean = column().sum::<f64>() / n_frames as f64;
+            let (mut m2, mut m4) = (0.0f64, 0.0f64);
+            for x in column() {
+                let d2 = (x - mean) * (x - mean);
+                m2 += d2;
+                m4 += d2 * d2;
+            }
+            m2 /= n_frames as f64;
+            m4 /= n_frames as f64;
+            if m2 <= 0.0 {
+                0.0
+            } else {
+                (m4 / (m2 * m2)) as f32
+            }
+        })
+        .collect()
+}
+
+/// Consistency of the frame-to-frame phase advance of one bin.
+///
+/// # What
+/// `phase_frames` holds phases in radians, frame-major. For `bin` the
+/// increments `phase[f + 1] - phase[f]` are treated as unit vectors and the
+/// length of their mean is returned, in `0..=1`: `1` when the phase
+/// advances by the same amount every frame, near `0` when the advance is
+/// random. Fewer than two frames give `0`.
+///
+/// # Why
+/// A stable partial advances its phase at a constant rate; vibrato and
+/// jitter spread the increments, so the resultant length is a compact
+/// voice-quality measure.
+///
+/// # Panics
+/// Panics when `phase_frames.len() != n_frames * n_bins`, the matrix
+/// contains non-finite values, or `bin >= n_bins`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn phase_coherence(phase_frames: &[f32], n_frames: usize, n_bins: usize, bin: usize) -> f32 {
+    check_matrix(phase_frames, n_frames, n_bins);
+    assert!(bin < n_bins, "bin must be less than n_bins");
+    if n_frames < 2 {
+        return 0.0;
+    }
+    let (mut re, mut im) = (0.0f64, 0.0f64);
+    for f in 1..n_frames {
+        let delta = (phase_frames[f * n_bins + bin] - phase_frames[(f - 1) * n_bins + bin]) as f64;
+        re += delta.cos();
+        im += delta.sin();
+    }
+    let count = (n_frames - 1) as f64;
+    ((re / count).hypot(im / count)) as f32
+}
+
+/// Separable Gaussian blur of a spectrogram across time and frequency.
+///
+/// # What
+/// Smooths along time with standard deviation `time_sigma` frames, then
+/// along frequency with `freq_sigma` bins. Kernels are truncated at three
+/// standard deviations and normalized, so energy away from the borders is
+/// preserved; at the borders indices are clamped (edge values repeat).
+/// A sigma of zero or less leaves that axis untouched.
+///
+/// # Panics
+/// Panics when `frames.len() != n_frames * n_bins`, the matrix contains
+/// non-finite values, or either sigma is non-finite.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn smooth_2d(
+    frames: &[f32],
+    n_frames: usize,
+    n_bins: usize,
+    time_sigma: f32,
+    freq_sigma: f32,
+) -> Vec<f32> {
+    check_matrix(frames, n_frames, n_bins);
+    assert!(
+        time_sigma.is_finite() && freq_sigma.is_finite(),
+        "sigmas must be finite"
+    );
+    let time_kernel = gaussian_kernel(time_sigma);
+    let freq_kernel = gaussian_kernel(freq_sigma);
+
+    let mut along_time = vec![0.0f32; frames.len()];
+    for bin in 0..n_bins {
+        convolve_clamped(
+            n_frames,
+            &time_kernel,
+            |f| frames[f * n_bins + bin],
+            |f, v| along_time[f * n_bins + bin] = v,
+        );
+    }
+    let mut output = vec![0.0f32; frames.len()];
+    for (src, dst) in along_time
+        .chunks_exact(n_bins.max(1))
+        .zip(output.chunks_exact_mut(n_bins.max(1)))
+    {
+        convolve_clamped(n_bins, &freq_kernel, |b| src[b], |b, v| dst[b] = v);
+    }
+    output
+}
+
+/// Harmonic-percussive source separation of a magnitude spectrogram.
+///
+/// # What
+/// Median-filters the matrix along time and along frequency with windows
+/// of `kernel_size` cells (even sizes act as the next odd size; edges
+/// repeat), giving the harmonic estimate `H` and the percussive estimate
+/// `P`. Every cell `x` is then split with soft Wiener masks:
+/// `x * H^2 / (H^2 + P^2)` goes to the harmonic part and the rest to the
+/// percussive part, evenly when both estimates are zero. Returns the
+/// harmonic matrix followed by the percussive matrix, each frame-major like
+/// the input, so the two halves sum back to `frames`.
+///
+/// # Why
+/// Sustained tones are smooth along time and drum hits along frequency;
+/// the viewer's harmonic-only and percussive-only toggles display one half.
+///
+/// # Panics
+/// Panics when `frames.len() != n_frames * n_bins`, the matrix contains
+/// non-finite values, or `kernel_size` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn hpss(frames: &[f32], n_frames: usize, n_bins: usize, kernel_size: usize) -> Vec<f32> {
+    check_matrix(frames, n_frames, n_bins);
+    assert!(kernel_size > 0, "kernel_size must be positive");
+    let radius = kernel_size / 2;
+    let mut harmonic = vec![0.0f32; frames.len()];
+    for bin in 0..n_bins {
+        median_filter_clamped(
+            n_frames,
+            radius,
+            |f| frames[f * n_bins + bin],
+            |f, v| harmonic[f * n_bins + bin] = v,
+        );
+    }
+    let mut percussive = vec![0.0f32; frames.len()];
+    for (src, dst) in frames
+        .chunks_exact(n_bins.max(1))
+        .zip(percussive.chunks_exact_mut(n_bins.max(1)))
+    {
+        median_filter_clamped(n_bins, radius, |b| src[b], |b, v| dst[b] = v);
+    }
+    for ((&x, h), p) in frames.iter().zip(&mut harmonic).zip(&mut percussive) {
+        let (h2, p2) = (*h * *h, *p * *p);
+        let share = if h2 + p2 > 0.0 { h2 / (h2 + p2) } else { 0.5 };
+        *h = x * share;
+        *p = x - *h;
+    }
+    harmonic.extend_from_slice(&percussive);
+    harmonic
+}
+
+/// Sparse set of prominent spectrogram peaks, e.g. for fingerprinting.
+///
+/// # What
+/// A cell is a peak when it is strictly greater than every other cell
+/// within `neighborhood` frames and bins of it (cells beyond the matrix are
+/// ignored), so flat regions produce no peaks. Per frame at most
+/// `max_per_frame` peaks are kept, the strongest first. Returns flattened
+/// `[frame, bin, magnitude]` triples ordered by frame.
+///
+/// # Why
+/// Landmark-based audio fingerprints hash constellations of local maxima;
+/// a few values per frame are far cheaper to ship than the full matrix.
+///
+/// # Panics
+/// Panics when `frames.len() != n_frames * n_bins` or the matrix contains
+/// non-finite values.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn spectrogram_peaks(
+    frames: &[f32],
+    n_frames: usize,
+    n_bins: usize,
+    neighborhood: usize,
+    max_per_frame: usize,
+) -> Vec<f32> {
+    check_matrix(frames, n_frames, n_bins);
+    let mut output = Vec::new();
+    let mut peaks = Vec::new();
+    for f in 0..n_frames {
+        peaks.clear();
+        for b in 0..n_bins {
+            let value = frames[f * n_bins + b];
+            let frame_range = f.saturating_sub(neighborhood)..(f + neighborhood + 1).min(n_frames);
+            let is_peak = frame_range.into_iter().all(|g| {
+                let bin_range = b.saturating_sub(neighborhood)..(b + neighborhood + 1).min(n_bins);
+                bin_range
+                    .into_iter()
+                    .all(|c| (g, c) == (f, b) || frames[g * n_bins + c] < value)
+            });
+            if is_peak {
+                peaks.push((b, value));
+            }
+        }
+        peaks.sort_by(|x, y| y.1.total_cmp(&x.1));
+        for &(b, value) in peaks.iter().take(max_per_frame) {
+            output.extend_from_slice(&[f as f32, b as f32, value]);
+        }
+    }
+    output
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stft_frame, test_support::noise};
+
+    /// Tolerance for floating point comparisons in tests.
+    const TOLERANCE: f32 = 1e-6;
+
+    /// A constant tone vanishes while a short burst survives.
+    #[test]
+    fn remove_stationary_keeps_transients() {
+        let (n_frames, n_bins) = (9, 4);
+        let (tone_bin, burst_bin, burst_frame) = (1, 3, 4);
+        let mut frames = vec![0.1f32; n_frames * n_bins];
+        for f in 0..n_frames {
+            frames[f * n_bins + tone_bin] = 5.0;
+        }
+        frames[burst_frame * n_bins + burst_bin] = 2.0;
+
+        let cleaned = remove_stationary(&frames, n_frames, n_bins);
+        for f in 0..n_frames {
+            assert!(cleaned[f * n_bins + tone_bin].abs() < TOLERANCE);
+        }
+        assert!((cleaned[burst_frame * n_bins + burst_bin] - 1.9).abs() < TOLERANCE);
+        assert!(cleaned.iter().all(|&v| v >= 0.0));
+    }
+
+    /// Dropping edges removes exactly the DC and Nyquist bin of each frame.
+    #[test]
+    fn stft_frames_drop_edges_omits_dc_and_nyquist() {
+        let (fft_size, hop) = (16, 8);
+        let input: Vec<f32> = (0..60).map(|i| (i as f32 * 0.7).sin() + 0.3).collect();
+        let n_frames = frame_count(input.len(), fft_size, hop);
+        assert_eq!(n_frames, 6);
+
+        let full = stft_frames(&input, fft_size, hop, "hann", 1.0, false);
+        let trimmed = stft_frames(&input, fft_size, hop, "hann", 1.0, true);
+        let bins = fft_size / 2 + 1;
+        assert_eq!(full.len(), n_frames * bins);
+        assert_eq!(trimmed.len(), n_frames * (bins - 2));
+
+        for f in 0..n_frames {
+            let row = &full[f * bins..(f + 1) * bins];
+            let expected = stft_frame(&input[f * hop..f * hop + fft_size], "hann", 1.0);
+            assert!((row[0] - expected[0]).abs() < 1e-3);
+            let inner = &trimmed[f * (bins - 2)..(f + 1) * (bins - 2)];
+            assert_eq!(inner, &row[1..bins - 1]);
+        }
+    }
+
+    /// Occasional bursts raise kurtosis far above a steady, noisy tone.
+    #[test]
+    fn spectral_kurtosis_flags_bursts() {
+        let (n_frames, n_bins) = (100, 3);
+        let (steady_bin, burst_bin, silent_bin) = (0, 1, 2);
+        let jitter = noise(n_frames, 5);
+        let mut frames = vec![0.0f32; n_frames * n_bins];
+        for f in 0..n_frames {
+            frames[f * n_bins + steady_bin] = 1.0 + 0.1 * jitter[f];
+            frames[f * n_bins + burst_bin] = if f % 25 == 3 { 10.0 } else { 0.1 };
+        }
+        let kurtosis = spectral_kurtosis(&frames, n_frames, n_bins);
+        assert!(kurtosis[burst_bin] > 10.0, "{kurtosis:?}");
+        assert!(kurtosis[steady_bin] < 3.0, "{kurtosis:?}");
+        assert_eq!(kurtosis[silent_bin], 0.0);
+    }
+
+    /// A constant phase advance is coherent; random jitter is not.
+    #[test]
+    fn phase_coherence_separates_steady_and_jittery() {
+        let (n_frames, n_bins, bin) = (64, 2, 1);
+        let jitter = noise(n_frames, 11);
+        let mut phases = vec![0.0f32; n_frames * n_bins];
+        for f in 0..n_frames {
+            let steady = crate::spectral::wrap_phase(0.7 * f as f32);
+            phases[f * n_bins] = steady;
+            phases[f * n_bins + bin] = crate::spectral::wrap_phase(steady + 2.5 * jitter[f]);
+        }
+        let coherent = phase_coherence(&phases, n_frames, n_bins, 0);
+        let jittery = phase_coherence(&phases, n_frames, n_bins, bin);
+        assert!((coherent - 1.0).abs() < 1e-4, "{coherent}");
+        assert!(jittery < 0.5, "{jittery}");
+    }
+
+    /// A single bright cell spreads into a Gaussian footprint of equal mass.
+    #[test]
+    fn smooth_2d_spreads_impulse_into_gaussian() {
+        let (n_frames, n_bins, center) = (31, 25, (15, 12));
+        let (time_sigma, freq_sigma) = (2.0f32, 1.0f32);
+        let mut frames = vec![0.0f32; n_frames * n_bins];
+        frames[center.0 * n_bins + center.1] = 1.0;
+
+        let smoothed = smooth_2d(&frames, n_frames, n_bins, time_sigma, freq_sigma);
+        let total: f32 = smoothed.iter().sum();
+        assert!((total - 1.0).abs() < 1e-4, "{total}");
+
+        let peak = smoothed[center.0 * n_bins + center.1];
+        for (dt, db) in [(1isize, 0isize), (3, 0), (0, 1), (0, 2), (2, 1)] {
+            let f = (center.0 as isize + dt) as usize;
+            let b = (center.1 as isize + db) as usize;
+            let expected = (-((dt * dt) as f32) / (2.0 * time_sigma * time_sigma)
+                - ((db * db) as f32) / (2.0 * freq_sigma * freq_sigma))
+                .exp();
+            let ratio = smoothed[f * n_bins + b] / peak;
+            assert!((ratio - expected).abs() < 1e-4, "({dt}, {db}): {ratio}");
+        }
+        assert_eq!(smooth_2d(&frames, n_frames, n_bins, 0.0, 0.0), frames);
+    }
+
+    /// Two appends of consecutive chunks match a single full computation.
+    #[test]
+    fn append_spectrogram_in_chunks_matches_full() {
+        let (fft_size, hop) = (32, 8);
+        let input: Vec<f32> = noise(300, 3);
+        let full = compute_spectrogram(&input, fft_size, hop, "hann", 1.0);
+        let n_bins = full.n_bins();
+        let mut out = vec![0.0f32; full.values().len()];
+
+        let first_frames = 10;
+        let first = &input[..(first_frames - 1) * hop + fft_size];
+        let written = append_spectrogram(first, fft_size, hop, "hann", 1.0, &mut out, 0);
+        assert_eq!(written, first_frames * n_bins);
+        let rest = &input[first_frames * hop..];
+        let more = append_spectrogram(rest, fft_size, hop, "hann", 1.0, &mut out, written);
+        assert_eq!(written + more, out.len());
+        assert_eq!(out, full.values());
+    }
+
+    /// Appending beyond the end of the buffer is rejected.
+    #[test]
+    #[should_panic(expected = "out lacks space")]
+    fn append_spectrogram_rejects_short_buffer() {
+        let mut out = vec![0.0f32; 10];
+        append_spectrogram(&noise(64, 1), 32, 16, "hann", 1.0, &mut out, 0);
+    }
+
+    /// A signal compared with itself differs nowhere, even in silent cells.
+    #[test]
+    fn spectrogram_difference_of_identical_signals_is_zero() {
+        let mut signal = noise(200, 8);
+        signal.extend(vec![0.0; 100]);
+        let diff = spectrogram_difference(&signal, &signal, 32, 16, "hann", 1.0);
+        let full = compute_spectrogram(&signal, 32, 16, "hann", 1.0);
+        assert_eq!(
+            (diff.n_frames(), diff.n_bins()),
+            (full.n_frames(), full.n_bins())
+        );
+        assert!(diff.values().iter().all(|&v| v == 0.0));
+
+        let louder: Vec<f32> = signal.iter().map(|v| 2.0 * v).collect();
+        let diff = spectrogram_difference(&louder, &signal, 32, 16, "hann", 1.0);
+        let gain_db = 20.0 * 2f32.log10();
+        assert!((diff.values()[5] - gain_db).abs() < 1e-3);
+    }
+
+    /// Exactly the planted strong points are reported, capped per frame.
+    #[test]
+    fn spectrogram_peaks_finds_planted_points() {
+        let (n_frames, n_bins) = (12, 16);
+        let mut frames = vec![0.0f32; n_frames * n_bins];
+        let planted = [
+            (2usize, 3usize, 4.0f32),
+            (2, 12, 5.0),
+            (7, 8, 6.0),
+            (10, 1, 3.0),
+        ];
+        for &(f, b, v) in &planted {
+            frames[f * n_bins + b] = v;
+        }
+        // A weaker neighbour of a planted point is not a peak.
+        frames[7 * n_bins + 9] = 2.0;
+
+        let triples = |peaks: Vec<f32>| -> Vec<(usize, usize, f32)> {
+            peaks
+                .chunks_exact(3)
+                .map(|t| (t[0] as usize, t[1] as usize, t[2]))
+                .collect()
+        };
+        let all = triples(spectrogram_peaks(&frames, n_frames, n_bins, 2, 4));
+        assert_eq!(all, vec![planted[1], planted[0], planted[2], planted[3]]);
+
+        let capped = triples(spectrogram_peaks(&frames, n_frames, n_bins, 2, 1));
+        assert_eq!(capped, vec![planted[1], planted[2], planted[3]]);
+    }
+
+    /// The parallel path reproduces the serial spectrogram bit for bit,
+    /// including frame counts that do not divide evenly among workers.
+    #[test]
+    fn parallel_spectrogram_matches_serial() {
+        let samples = noise(5000, 4);
+        let serial = compute_spectrogram(&samples, 256, 64, "hann", 1.0);
+        for threads in [0, 1, 3, 7] {
+            let parallel = compute_spectrogram_parallel(&samples, 256, 64, "hann", 1.0, threads);
+            assert_eq!(parallel, serial, "threads = {threads}");
+        }
+        let short = compute_spectrogram_parallel(&samples[..100], 256, 64, "hann", 1.0, 4);
+        assert_eq!(short.n_frames(), 0);
+    }
+
+    /// Frame ranges concatenate to the whole-clip spectrogram, as Web
+    /// Workers splitting one file would.
+    #[test]
+    fn spectrogram_ranges_concatenate_to_whole() {
+        let samples = noise(3000, 9);
+        let whole = compute_spectrogram(&samples, 128, 48, "hann", 1.0);
+        let mut joined = Vec::new();
+        for first in (0..whole.n_frames()).step_by(7) {
+            let count = 7.min(whole.n_frames() - first);
+            let range = compute_spectrogram_range(&samples, 128, 48, "hann", 1.0, first, count);
+            assert_eq!(range.n_frames(), count);
+            joined.extend(range.data());
+        }
+        assert_eq!(joined, whole.data());
+        let end = compute_spectrogram_range(&samples, 128, 48, "hann", 1.0, whole.n_frames(), 0);
+        assert_eq!(end.n_frames(), 0);
+    }
+
+    /// A range past the last complete frame is rejected.
+    #[test]
+    #[should_panic(expected = "frame range exceeds the frame count")]
+    fn spectrogram_range_rejects_overrun() {
+        compute_spectrogram_range(&[0.0; 300], 128, 64, "hann", 1.0, 2, 2);
+    }
+
+    /// A steady tone lands in the harmonic half, a click in the
+    /// percussive half, and the halves sum to the input.
+    #[test]
+    fn hpss_separates_tone_and_click() {
+        let (n_frames, n_bins) = (21, 32);
+        let mut frames = vec![0.01f32; n_frames * n_bins];
+        for f in 0..n_frames {
+            frames[f * n_bins + 5] = 1.0;
+        }
+        for cell in &mut frames[10 * n_bins..11 * n_bins] {
+            *cell = 0.8;
+        }
+        let split = hpss(&frames, n_frames, n_bins, 7);
+        let (harmonic, percussive) = split.split_at(frames.len());
+        for ((x, h), p) in frames.iter().zip(harmonic).zip(percussive) {
+            assert!((h + p - x).abs() < 1e-6);
+        }
+        assert!(harmonic[3 * n_bins + 5] > 0.95);
+        assert!(percussive[10 * n_bins + 20] > 0.75);
+        assert!(harmonic[10 * n_bins + 20] < 0.05);
+        assert!(percussive[3 * n_bins + 5] < 0.05);
+    }
+
+    /// Rows cover the signal including a zero-padded final frame.
+    #[test]
+    fn stft_signal_pads_last_frame() {
+        let (frame_size, hop) = (16, 6);
+        let samples = noise(50, 2);
+        let rows = stft_signal(&samples, frame_size, hop, "hann", 1.0);
+        let n_rows = (samples.len() - frame_size).div_ceil(hop) + 1;
+        assert_eq!(rows.len(), n_rows * frame_size);
+
+        let mut padded = samples.clone();
+        padded.resize((n_rows - 1) * hop + frame_size, 0.0);
+        for (r, row) in rows.chunks_exact(frame_size).enumerate() {
+            let expected = stft_frame(&padded[r * hop..r * hop + frame_size], "hann", 1.0);
+            assert_eq!(row, expected.as_slice());
+        }
+        assert_eq!(
+            stft_signal(&samples[..5], frame_size, hop, "hann", 1.0).len(),
+            frame_size
+        );
+    }
+
+    /// A long silent gap collapses to one run; active frames are verbatim.
+    #[test]
+    fn sparse_spectrogram_collapses_silent_run() {
+        let (fft_size, hop) = (64, 64);
+        let n_bins = fft_size / 2 + 1;
+        let mut input = noise(2 * fft_size, 5);
+        input.extend(vec![0.0f32; 10 * fft_size]);
+        input.extend(noise(fft_size, 6));
+
+        let dense = compute_spectrogram(&input, fft_size, hop, "hann", 1.0)
+            .values()
+            .to_vec();
+        let sparse = compute_spectrogram_sparse(&input, fft_size, hop, "hann", 1.0, -120.0);
+
+        let mut expected = Vec::new();
+        for index in [0, 1] {
+            expected.push(index as f32);
+            expected.extend_from_slice(&dense[index * n_bins..(index + 1) * n_bins]);
+        }
+        expected.extend([-10.0, 2.0, 12.0]);
+        expected.extend_from_slice(&dense[12 * n_bins..]);
+        assert_eq!(sparse, expected);
+    }
+
+    /// Every row of `stft_all` is the `stft_frame` of the matching slice.
+    #[test]
+    fn stft_all_rows_match_stft_frame() {
+        let (fft_size, hop) = (64, 24);
+        let input = noise(500, 12);
+        let all = stft_all(&input, fft_size, hop, "hann", 1.0);
+        assert_eq!((all.n_frames(), all.n_bins()), (19, fft_size));
+        for (f, row) in all.values().chunks_exact(fft_size).enumerate() {
+            let slice = &input[f * hop..f * hop + fft_size];
+            assert_eq!(row, stft_frame(slice, "hann", 1.0));
+        }
+    }
+
+    /// Stereo output is each channel's spectrogram in turn; downmixing
+    /// analyses the channel average.
+    #[test]
+    fn stft_multichannel_splits_and_downmixes() {
+        let (fft_size, hop) = (64, 32);
+        let (left, right) = (noise(400, 1), noise(400, 2));
+        let interleaved: Vec<f32> = left
+            .iter()
+            .zip(&right)
+            .flat_map(|(&l, &r)| [l, r])
+            .collect();
+
+        let split = stft_multichannel(&interleaved, 2, fft_size, hop, "hann", 1.0, false);
+        let mut expected = stft_frames(&left, fft_size, hop, "hann", 1.0, false);
+        expected.extend(stft_frames(&right, fft_size, hop, "hann", 1.0, false));
+        assert_eq!(split, expected);
+
+        let mono: Vec<f32> = left
+            .iter()
+            .zip(&right)
+            .map(|(l, r)| (l + r) * 0.5)
+            .collect();
+        assert_eq!(
+            stft_multichannel(&interleaved, 2, fft_size, hop, "hann", 1.0, true),
+            stft_frames(&mono, fft_size, hop, "hann", 1.0, false)
+        );
+    }
+}
@@ -0,0 +1,2045 @@
+//! Stateful processors for live, chunked audio input.
+//!
+//! # What
+//! Structs that keep sample history between calls so callers can feed audio
+//! in whatever chunk sizes their capture API delivers.
+//!
+//! # Why
+//! AudioWorklet and microphone callbacks rarely align with FFT frame
+//! boundaries. Buffering in WASM keeps frame alignment exact without
+//! re-slicing buffers in JavaScript.
+
+use crate::analysis::{interpolated_peak_bin, parabolic_offset};
+use crate::filters::{Biquad, Preprocessor, Resampler};
+use crate::{
+    check_finite, half_spectrum_magnitudes, linear_to_dbfs_in_place, magnitude_dbfs_unchecked,
+    magnitudes_unchecked, parse_window, plan_forward, rfft_twiddle, unpack_rfft_bin,
+    validate_finite, validate_not_nan, window_coefficients, window_samples, DspError, WindowType,
+    DB_SCALE, EPSILON, POWER_DB_SCALE,
+};
+use rustfft::{num_complex::Complex32, Fft};
+use std::collections::VecDeque;
+use std::sync::Arc;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Streaming STFT that turns arbitrary-length chunks into dBFS frames.
+///
+/// # What
+/// Accumulates pushed samples and emits the [`crate::stft_frame`] row of
+/// every complete frame, advancing by `hop` samples between frames. The
+/// rows match those of [`crate::stft_signal`] on the concatenated input,
+/// except that a trailing partial frame waits for more samples instead of
+/// being zero-padded.
+///
+/// # Why
+/// AudioWorklet and microphone callbacks deliver chunks (typically 128
+/// samples) that do not line up with the frame size or hop; the processor
+/// does that accounting so JavaScript can forward every chunk as-is.
+///
+/// # How
+/// Samples are kept in an internal buffer until `frame_size` are available.
+/// After each frame `hop` samples are discarded; when `hop` exceeds the
+/// buffered amount the remainder is skipped from future input.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct StftProcessor {
+    frame_size: usize,
+    hop: usize,
+    window: WindowType,
+    reference: f32,
+    /// Emit only every `emit_every`-th frame; `1` emits all frames.
+    emit_every: usize,
+    /// Number of frames passed since the processor was created.
+    frame_index: usize,
+    /// Samples still to discard before the next frame starts.
+    pending_skip: usize,
+    /// Cleanup applied to every pushed sample, if any.
+    preprocessor: Option<Preprocessor>,
+    buffer: Vec<f32>,
+}
+
+/// Name under which live-input code asks for [`StftProcessor`]: chunks go
+/// in through `push`, complete frames come out.
+pub type StftStream = StftProcessor;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl StftProcessor {
+    /// Create a processor for frames of `frame_size` samples spaced `hop`
+    /// samples apart. Unknown window names panic,
+    /// matching [`crate::stft_frame`].
+    ///
+    /// # Panics
+    /// Panics when `frame_size` or `hop` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(frame_size: usize, hop: usize, window_type: &str, reference: f32) -> StftProcessor {
+        assert!(frame_size > 0, "frame_size must be positive");
+        assert!(hop > 0, "hop must be positive");
+        StftProcessor {
+            frame_size,
+            hop,
+            window: parse_window(window_type),
+            reference,
+            emit_every: 1,
+            frame_index: 0,
+            pending_skip: 0,
+            preprocessor: None,
+            buffer: Vec::with_capacity(frame_size),
+        }
+    }
+
+    /// Filter all further input with `preprocessor` before framing, or stop
+    /// filtering with `None`.
+    ///
+    /// # Why
+    /// Low-frequency rumble otherwise dominates the color scale of speech
+    /// and bioacoustic recordings. Every sample passes the filter, including
+    /// those a hop larger than the frame skips, so its state stays
+    /// continuous across chunks.
+    pub fn set_preprocessor(&mut self, preprocessor: Option<Preprocessor>) {
+        self.preprocessor = preprocessor;
+    }
+
+    /// Only compute and return every `emit_every`-th frame.
+    ///
+    /// # Why
+    /// Background monitors that refresh a few times per second do not need
+    /// every frame. Skipped frames still advance the buffer by `hop`, so
+    /// emitted frames stay aligned with an undecimated processor, but no
+    /// FFT is spent on them. Values below one are treated as one.
+    pub fn set_emit_every(&mut self, emit_every: usize) {
+        self.emit_every = emit_every.max(1);
+    }
+
+    /// Current frame decimation factor.
+    pub fn emit_every(&self) -> usize {
+        self.emit_every
+    }
+
+    /// Append `samples` and return the concatenated rows of all frames
+    /// completed by them (empty when no frame was emitted).
+    ///
+    /// # Panics
+    /// Panics when `samples` contains non-finite values.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<f32> {
+        let reference = self.reference;
+        let mut output = Vec::new();
+        self.for_each_frame(samples, |windowed| {
+            output.extend(magnitude_dbfs_unchecked(windowed, reference));
+        });
+        output
+    }
+
+    /// Discard buffered samples, clear the preprocessor state and restart
+    /// frame counting, as if the processor had just been created.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.frame_index = 0;
+        self.pending_skip = 0;
+        if let Some(preprocessor) = &mut self.preprocessor {
+            preprocessor.reset();
+        }
+    }
+}
+
+impl StftProcessor {
+    /// Buffer `samples` and hand every emitted, already windowed frame to
+    /// `on_frame`.
+    fn for_each_frame(&mut self, samples: &[f32], mut on_frame: impl FnMut(&[f32])) {
+        validate_finite(samples);
+        let skipped = self.pending_skip.min(samples.len());
+        self.pending_skip -= skipped;
+        match &mut self.preprocessor {
+            Some(preprocessor) => {
+                let mut filtered = samples.to_vec();
+                preprocessor.process_in_place(&mut filtered);
+                self.buffer.extend_from_slice(&filtered[skipped..]);
+            }
+            None => self.buffer.extend_from_slice(&samples[skipped..]),
+        }
+
+        while self.pending_skip == 0 && self.buffer.len() >= self.frame_size {
+            if self.frame_index.is_multiple_of(self.emit_every) {
+                let windowed = window_samples(&self.buffer[..self.frame_size], self.window);
+                on_frame(&windowed);
+            }
+            self.frame_index += 1;
+            let drop = self.hop.min(self.buffer.len());
+            self.buffer.drain(..drop);
+            self.pending_skip = self.hop - drop;
+        }
+    }
+}
+
+/// Reusable single-frame analyzer for the hottest per-frame path.
+///
+/// # What
+/// Holds the window coefficients, the FFT plan and all working buffers for
+/// one `fft_size`, so [`FrameAnalyzer::analyze`] allocates nothing but its
+/// output. Returns the `fft_size/2 + 1` half-spectrum bins in dB relative
+/// to `reference`. Unknown window names panic.
+///
+/// # Why
+/// [`crate::stft_frame`] recomputes the window and allocates intermediate
+/// vectors on every call; at thousands of frames per second that overhead
+/// dominates the FFT itself.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FrameAnalyzer {
+    window: Vec<f32>,
+    fft: Arc<dyn Fft<f32>>,
+    buffer: Vec<Complex32>,
+    scratch: Vec<Complex32>,
+    /// Recombination twiddles for even sizes; empty when the full-length
+    /// FFT is used.
+    twiddles: Vec<Complex32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FrameAnalyzer {
+    /// Prepare window, plan and buffers for frames of `fft_size` samples.
+    ///
+    /// # Panics
+    /// Panics when `fft_size` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(fft_size: usize, window_type: &str) -> FrameAnalyzer {
+        assert!(fft_size > 0, "fft_size must be positive");
+        let window = parse_window(window_type);
+        // Even sizes pack sample pairs into a half-length FFT, like `rfft`.
+        let (fft_len, twiddles) = if fft_size.is_multiple_of(2) {
+            let twiddles = (0..=fft_size / 2)
+                .map(|k| rfft_twiddle(k, fft_size))
+                .collect();
+            (fft_size / 2, twiddles)
+        } else {
+            (fft_size, Vec::new())
+        };
+        let fft = plan_forward(fft_len);
+        let scratch = vec![Complex32::new(0.0, 0.0); fft.get_inplace_scratch_len()];
+        FrameAnalyzer {
+            window: window_coefficients(window, fft_size),
+            fft,
+            buffer: vec![Complex32::new(0.0, 0.0); fft_len],
+            scratch,
+            twiddles,
+        }
+    }
+
+    /// Window and transform one frame, returning half-spectrum dB values.
+    ///
+    /// # Panics
+    /// Panics when `input` contains non-finite values or its length differs
+    /// from `fft_size`.
+    pub fn analyze(&mut self, input: &[f32], reference: f32) -> Vec<f32> {
+        let mut output = vec![0.0f32; self.window.len() / 2 + 1];
+        self.analyze_into(input, reference, &mut output);
+        output
+    }
+
+    /// [`FrameAnalyzer::analyze`] that reports invalid input instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// Returns [`DspError::LengthMismatch`] when `input.len()` differs from
+    /// `fft_size` and [`DspError::NonFinite`] for NaN or infinite samples.
+    pub fn try_analyze(&mut self, input: &[f32], reference: f32) -> Result<Vec<f32>, DspError> {
+        if input.len() != self.window.len() {
+            return Err(DspError::LengthMismatch {
+                expected: self.window.len(),
+                actual: input.len(),
+            });
+        }
+        check_finite(input)?;
+        Ok(self.analyze(input, reference))
+    }
+}
+
+impl FrameAnalyzer {
+    /// Write the [`FrameAnalyzer::analyze`] bins of `input` into `output`,
+    /// which must hold `fft_size/2 + 1` values.
+    fn analyze_into(&mut self, input: &[f32], reference: f32, output: &mut [f32]) {
+        validate_finite(input);
+        assert_eq!(
+            input.len(),
+            self.window.len(),
+            "input length must equal fft_size"
+        );
+        if self.twiddles.is_empty() {
+            for ((b, &x), &w) in self.buffer.iter_mut().zip(input).zip(&self.window) {
+                *b = Complex32::new(x * w, 0.0);
+            }
+            self.fft
+                .process_with_scratch(&mut self.buffer, &mut self.scratch);
+            for (m, c) in output.iter_mut().zip(&self.buffer) {
+                *m = c.norm();
+            }
+        } else {
+            let pairs = input.chunks_exact(2).zip(self.window.chunks_exact(2));
+            for (b, (x, w)) in self.buffer.iter_mut().zip(pairs) {
+                *b = Complex32::new(x[0] * w[0], x[1] * w[1]);
+            }
+            self.fft
+                .process_with_scratch(&mut self.buffer, &mut self.scratch);
+            for (k, (m, &t)) in output.iter_mut().zip(&self.twiddles).enumerate() {
+                *m = unpack_rfft_bin(&self.buffer, k, t).norm();
+            }
+        }
+        linear_to_dbfs_in_place(output, reference);
+    }
+}
+
+/// [`FrameAnalyzer`] with persistent input and output buffers in WASM
+/// memory.
+///
+/// # What
+/// JavaScript writes a frame into the `fft_size` samples at
+/// [`FrameProcessor::input_ptr`], calls [`FrameProcessor::process`] and
+/// reads the `fft_size/2 + 1` half-spectrum dB values at
+/// [`FrameProcessor::output_ptr`], both through `Float32Array` views on the
+/// module's memory. Views must be recreated after the memory grows.
+///
+/// # Why
+/// Passing slices copies the input in and allocates a fresh output on every
+/// call. With views on fixed buffers a frame costs no allocation and no
+/// copy across the boundary.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FrameProcessor {
+    analyzer: FrameAnalyzer,
+    reference: f32,
+    input: Vec<f32>,
+    output: Vec<f32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FrameProcessor {
+    /// Allocate the buffers for frames of `fft_size` samples.
+    ///
+    /// # Panics
+    /// Panics when `fft_size` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(fft_size: usize, window_type: &str, reference: f32) -> FrameProcessor {
+        FrameProcessor {
+            analyzer: FrameAnalyzer::new(fft_size, window_type),
+            reference,
+            input: vec![0.0; fft_size],
+            output: vec![0.0; fft_size / 2 + 1],
+        }
+    }
+
+    /// Address of the `fft_size` input samples.
+    pub fn input_ptr(&mut self) -> *mut f32 {
+        self.input.as_mut_ptr()
+    }
+
+    /// Address of the `fft_size/2 + 1` output values.
+    pub fn output_ptr(&self) -> *const f32 {
+        self.output.as_ptr()
+    }
+
+    /// Number of input samples per frame.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn input_len(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Number of output values per frame.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn output_len(&self) -> usize {
+        self.output.len()
+    }
+
+    /// Analyze the input buffer into the output buffer.
+    ///
+    /// # Panics
+    /// Panics when the input buffer contains non-finite values.
+    pub fn process(&mut self) {
+        self.analyzer
+            .analyze_into(&self.input, self.reference, &mut self.output);
+    }
+}
+
+impl FrameProcessor {
+    /// The input buffer, for Rust callers.
+    pub fn input_mut(&mut self) -> &mut [f32] {
+        &mut self.input
+    }
+
+    /// The output buffer, for Rust callers.
+    pub fn output(&self) -> &[f32] {
+        &self.output
+    }
+}
+
+/// Exponential moving average over successive spectra.
+///
+/// # What
+/// Each call blends the new frame into the running state as
+/// `state = alpha * state + (1 - alpha) * frame` and returns the state. The
+/// first frame (or a frame of a different length) re-initializes the state.
+///
+/// # Why
+/// Raw per-frame spectra flicker; a single-pole average trades a little
+/// latency for a steady, low-variance display.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SpectrumSmoother {
+    alpha: f32,
+    state: Vec<f32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SpectrumSmoother {
+    /// Create a smoother; `alpha` is clamped to `0..=1`, where `0` disables
+    /// smoothing and values close to `1` smooth heavily.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(alpha: f32) -> SpectrumSmoother {
+        SpectrumSmoother {
+            alpha: if alpha.is_finite() {
+                alpha.clamp(0.0, 1.0)
+            } else {
+                0.0
+            },
+            state: Vec::new(),
+        }
+    }
+
+    /// Blend `frame` into the running average and return the result.
+    ///
+    /// # Panics
+    /// Panics when `frame` contains non-finite values.
+    pub fn process(&mut self, frame: &[f32]) -> Vec<f32> {
+        validate_finite(frame);
+        if self.state.len() != frame.len() {
+            self.state = frame.to_vec();
+        } else {
+            let keep = self.alpha;
+            for (s, &x) in self.state.iter_mut().zip(frame) {
+                *s = keep * *s + (1.0 - keep) * x;
+            }
+        }
+        self.state.clone()
+    }
+
+    /// Forget the running average.
+    pub fn reset(&mut self) {
+        self.state.clear();
+    }
+}
+
+/// Real-time analyzer: streaming STFT with a smoothed magnitude output.
+///
+/// # What
+/// Buffers pushed audio like [`StftProcessor`], smooths the linear
+/// magnitudes of each completed frame with a [`SpectrumSmoother`] and
+/// returns the smoothed spectrum in dB relative to `reference`, using the
+/// same `frame_size` bins as [`crate::stft_frame`].
+///
+/// # Why
+/// Packages the common live-display pipeline into a single call per audio
+/// callback. Smoothing linear magnitudes rather than dB values keeps the
+/// average unbiased.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct LiveAnalyzer {
+    processor: StftProcessor,
+    smoother: SpectrumSmoother,
+    reference: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl LiveAnalyzer {
+    /// Create an analyzer; `smoothing` is the [`SpectrumSmoother`] alpha.
+    ///
+    /// # Panics
+    /// Panics when `frame_size` or `hop` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        frame_size: usize,
+        hop: usize,
+        window_type: &str,
+        reference: f32,
+        smoothing: f32,
+    ) -> LiveAnalyzer {
+        LiveAnalyzer {
+            processor: StftProcessor::new(frame_size, hop, window_type, reference),
+            smoother: SpectrumSmoother::new(smoothing),
+            reference,
+        }
+    }
+
+    /// Append `samples` and return the smoothed spectrum after the last
+    /// frame they completed, or an empty vector if none completed.
+    ///
+    /// # Panics
+    /// Panics when `samples` contains non-finite values.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<f32> {
+        let smoother = &mut self.smoother;
+        let mut latest = None;
+        self.processor.for_each_frame(samples, |windowed| {
+            latest = Some(smoother.process(&magnitudes_unchecked(windowed)));
+        });
+        let mut spectrum = latest.unwrap_or_default();
+        linear_to_dbfs_in_place(&mut spectrum, self.reference);
+        spectrum
+    }
+}
+
+/// Sliding-window stereo phase-correlation meter.
+///
+/// # What
+/// Keeps the most recent `window_ms` of both channels and reports their
+/// Pearson correlation coefficient in `[-1, 1]`: `+1` for identical
+/// channels, `0` for unrelated ones, `-1` for polarity-inverted ones.
+///
+/// # How
+/// Running sums of the samples, their squares and their products are
+/// updated as samples enter and leave a ring buffer, so each sample costs
+/// `O(1)` regardless of the window length. Sums use `f64` to keep the
+/// incremental updates from drifting.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct CorrelationMeter {
+    left: Vec<f32>,
+    right: Vec<f32>,
+    /// Next ring slot to overwrite.
+    pos: usize,
+    /// Number of valid samples in the ring (saturates at its capacity).
+    filled: usize,
+    sum_l: f64,
+    sum_r: f64,
+    sum_ll: f64,
+    sum_rr: f64,
+    sum_lr: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CorrelationMeter {
+    /// Create a meter over a window of `window_ms` milliseconds.
+    ///
+    /// # Panics
+    /// Panics when `sample_rate` or `window_ms` is not a positive finite
+    /// number.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(sample_rate: f32, window_ms: f32) -> CorrelationMeter {
+        assert!(
+            sample_rate.is_finite() && sample_rate > 0.0,
+            "sample_rate must be a positive finite number"
+        );
+        assert!(
+            window_ms.is_finite() && window_ms > 0.0,
+            "window_ms must be a positive finite number"
+        );
+        let len = ((sample_rate * window_ms / 1000.0).round() as usize).max(1);
+        CorrelationMeter {
+            left: vec![0.0; len],
+            right: vec![0.0; len],
+            pos: 0,
+            filled: 0,
+            sum_l: 0.0,
+            sum_r: 0.0,
+            sum_ll: 0.0,
+            sum_rr: 0.0,
+            sum_lr: 0.0,
+        }
+    }
+
+    /// Feed a stereo block and return the correlation over the window.
+    ///
+    /// Returns `0.0` while either channel is silent (zero variance).
+    ///
+    /// # Panics
+    /// Panics when the channels differ in length or contain non-finite
+    /// values.
+    pub fn process(&mut self, left: &[f32], right: &[f32]) -> f32 {
+        assert_eq!(
+            left.len(),
+            right.len(),
+            "left and right channels must have equal length"
+        );
+        validate_finite(left);
+        validate_finite(right);
+        let capacity = self.left.len();
+        for (&l, &r) in left.iter().zip(right) {
+            if self.filled == capacity {
+                let (ol, or) = (self.left[self.pos] as f64, self.right[self.pos] as f64);
+                self.sum_l -= ol;
+                self.sum_r -= or;
+                self.sum_ll -= ol * ol;
+                self.sum_rr -= or * or;
+                self.sum_lr -= ol * or;
+            } else {
+                self.filled += 1;
+            }
+            self.left[self.pos] = l;
+            self.right[self.pos] = r;
+            let (l, r) = (l as f64, r as f64);
+            self.sum_l += l;
+            self.sum_r += r;
+            self.sum_ll += l * l;
+            self.sum_rr += r * r;
+            self.sum_lr += l * r;
+            self.pos = (self.pos + 1) % capacity;
+        }
+        self.correlation()
+    }
+
+    /// Clear the window.
+    pub fn reset(&mut self) {
+        let capacity = self.left.len();
+        *self = CorrelationMeter {
+            left: vec![0.0; capacity],
+            right: vec![0.0; capacity],
+            pos: 0,
+            filled: 0,
+            sum_l: 0.0,
+            sum_r: 0.0,
+            sum_ll: 0.0,
+            sum_rr: 0.0,
+            sum_lr: 0.0,
+        };
+    }
+}
+
+impl CorrelationMeter {
+    /// Pearson correlation of the samples currently in the window.
+    fn correlation(&self) -> f32 {
+        if self.filled == 0 {
+            return 0.0;
+        }
+        let n = self.filled as f64;
+        let cov = self.sum_lr - self.sum_l * self.sum_r / n;
+        let var_l = self.sum_ll - self.sum_l * self.sum_l / n;
+        let var_r = self.sum_rr - self.sum_r * self.sum_r / n;
+        let denom = (var_l * var_r).sqrt();
+        if denom <= f64::EPSILON {
+            return 0.0;
+        }
+        (cov / denom).clamp(-1.0, 1.0) as f32
+    }
+}
+
+/// Per-bin noise-floor estimate from a leaky running minimum.
+///
+/// # What
+/// For every bin the floor drops immediately to any lower magnitude and
+/// otherwise rises towards the current magnitude by `adapt_rate` of the
+/// gap per frame. The first frame initializes the floor.
+///
+/// # Why
+/// The asymmetry makes the floor hug the lower envelope of each bin:
+/// tones and transients sit above it for many frames before the leak
+/// catches up, while a genuinely rising noise level is followed slowly.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct NoiseFloorTracker {
+    adapt_rate: f32,
+    floor: Vec<f32>,
+    primed: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl NoiseFloorTracker {
+    /// Create a tracker for frames of `n_bins` magnitudes. `adapt_rate` is
+    /// clamped to `0..=1`; small values (around `0.01`) suit frame rates
+    /// of tens of frames per second.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(n_bins: usize, adapt_rate: f32) -> NoiseFloorTracker {
+        NoiseFloorTracker {
+            adapt_rate: if adapt_rate.is_finite() {
+                adapt_rate.clamp(0.0, 1.0)
+            } else {
+                0.0
+            },
+            floor: vec![0.0; n_bins],
+            primed: false,
+        }
+    }
+
+    /// Fold one magnitude frame into the estimate and return the floor.
+    ///
+    /// # Panics
+    /// Panics when `magnitudes` contains non-finite values or its length
+    /// differs from `n_bins`.
+    pub fn update(&mut self, magnitudes: &[f32]) -> Vec<f32> {
+        validate_finite(magnitudes);
+        assert_eq!(
+            magnitudes.len(),
+            self.floor.len(),
+            "frame length must equal n_bins"
+        );
+        if !self.primed {
+            self.floor.copy_from_slice(magnitudes);
+            self.primed = true;
+            return self.floor.clone();
+        }
+        for (f, &m) in self.floor.iter_mut().zip(magnitudes) {
+            if m < *f {
+                *f = m;
+            } else {
+                *f += self.adapt_rate * (m - *f);
+            }
+        }
+        self.floor.clone()
+    }
+
+    /// Forget the estimate; the next frame initializes it again.
+    pub fn reset(&mut self) {
+        self.floor.fill(0.0);
+        self.primed = false;
+    }
+}
+
+/// Relative frequency change within which a new estimate counts as the
+/// same pitch (about half a semitone).
+const PITCH_TOLERANCE: f32 = 0.03;
+
+/// Consecutive frames a different pitch must persist before it is adopted.
+const PITCH_HOLD_FRAMES: usize = 3;
+
+/// Streaming dominant-frequency tracker with hysteresis.
+///
+/// # What
+/// Buffers pushed audio into consecutive, non-overlapping Hann-windowed
+/// frames of `fft_size` samples and estimates each frame's strongest
+/// frequency by parabolic peak interpolation. Estimates within
+/// [`PITCH_TOLERANCE`] of the tracked value refine it immediately; a
+/// different frequency is only adopted after it persisted for
+/// [`PITCH_HOLD_FRAMES`] consecutive frames. Silent frames leave the
+/// tracked value unchanged.
+///
+/// # Why
+/// Per-frame peak picking jumps between octaves and harmonics whenever a
+/// single frame is ambiguous, which makes tuner readouts unreadable.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PitchTracker {
+    sample_rate: f32,
+    processor: StftProcessor,
+    /// Tracked frequency in Hz, `0` before the first voiced frame.
+    current: f32,
+    /// Competing frequency and the number of consecutive frames it lasted.
+    candidate: f32,
+    candidate_frames: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PitchTracker {
+    /// Create a tracker analysing frames of `fft_size` samples.
+    ///
+    /// # Panics
+    /// Panics when `sample_rate` is not positive or `fft_size` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(sample_rate: f32, fft_size: usize) -> PitchTracker {
+        assert!(
+            sample_rate.is_finite() && sample_rate > 0.0,
+            "sample_rate must be a positive finite number"
+        );
+        PitchTracker {
+            sample_rate,
+            processor: StftProcessor::new(fft_size, fft_size, "hann", 1.0),
+            current: 0.0,
+            candidate: 0.0,
+            candidate_frames: 0,
+        }
+    }
+
+    /// Feed `block` and return the tracked frequency in Hz (`0` until a
+    /// voiced frame has been seen).
+    ///
+    /// # Panics
+    /// Panics when `block` contains non-finite values.
+    pub fn push(&mut self, block: &[f32]) -> f32 {
+        let bin_hz = self.sample_rate / self.processor.frame_size as f32;
+        let mut estimates = Vec::new();
+        self.processor.for_each_frame(block, |windowed| {
+            if let Some(bin) = interpolated_peak_bin(&half_spectrum_magnitudes(windowed)) {
+                estimates.push(bin * bin_hz);
+            }
+        });
+        for hz in estimates {
+            self.update(hz);
+        }
+        self.current
+    }
+
+    /// Forget the tracked frequency and any buffered audio.
+    pub fn reset(&mut self) {
+        let fft_size = self.processor.frame_size;
+        self.processor = StftProcessor::new(fft_size, fft_size, "hann", 1.0);
+        self.current = 0.0;
+        self.candidate = 0.0;
+        self.candidate_frames = 0;
+    }
+}
+
+impl PitchTracker {
+    /// Apply the hysteresis rule to one frame's estimate.
+    fn update(&mut self, hz: f32) {
+        let close = |a: f32, b: f32| (a - b).abs() <= PITCH_TOLERANCE * b;
+        if self.current == 0.0 || close(hz, self.current) {
+            self.current = hz;
+            self.candidate_frames = 0;
+            return;
+        }
+        if self.candidate_frames > 0 && close(hz, self.candidate) {
+            self.candidate_frames += 1;
+        } else {
+            self.candidate_frames = 1;
+        }
+        self.candidate = hz;
+        if self.candidate_frames >= PITCH_HOLD_FRAMES {
+            self.current = hz;
+            self.candidate_frames = 0;
+        }
+    }
+}
+
+/// One-shot spectral-subtraction denoiser that learns its noise profile.
+///
+/// # What
+/// The first `learn_frames` magnitude frames passed to
+/// [`AutoDenoiser::process`] are assumed to be noise only: they are averaged
+/// into the noise profile and returned as silence. Every later frame has
+/// `over_subtract` times the profile subtracted per bin, floored at zero.
+///
+/// # Why
+/// Recordings usually start with a moment of room tone. Learning from it
+/// avoids asking the user to mark a noise region by hand.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct AutoDenoiser {
+    learn_frames: usize,
+    over_subtract: f32,
+    /// Frames averaged into `profile` so far.
+    learned: usize,
+    profile: Vec<f32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl AutoDenoiser {
+    /// Create a denoiser that learns from `learn_frames` frames and then
+    /// subtracts `over_subtract` times the learned profile. Values of
+    /// `over_subtract` above one remove more residual noise at the cost of
+    /// attenuating quiet signal components.
+    ///
+    /// # Panics
+    /// Panics when `over_subtract` is negative or non-finite.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(learn_frames: usize, over_subtract: f32) -> AutoDenoiser {
+        assert!(
+            over_subtract.is_finite() && over_subtract >= 0.0,
+            "over_subtract must be a non-negative finite number"
+        );
+        AutoDenoiser {
+            learn_frames,
+            over_subtract,
+            learned: 0,
+            profile: Vec::new(),
+        }
+    }
+
+    /// Whether the noise profile is complete and frames are being denoised.
+    pub fn is_ready(&self) -> bool {
+        self.learned >= self.learn_frames
+    }
+
+    /// Learn from or denoise one linear magnitude frame.
+    ///
+    /// # Panics
+    /// Panics when `magnitudes` contains non-finite values or its length
+    /// differs from the first frame's once learning has started.
+    pub fn process(&mut self, magnitudes: &[f32]) -> Vec<f32> {
+        validate_finite(magnitudes);
+        if self.profile.is_empty() {
+            self.profile = vec![0.0; magnitudes.len()];
+        }
+        assert_eq!(
+            magnitudes.len(),
+            self.profile.len(),
+            "frame length must stay constant"
+        );
+        if !self.is_ready() {
+            self.learned += 1;
+            let weight = 1.0 / self.learned as f32;
+            for (p, &m) in self.profile.iter_mut().zip(magnitudes) {
+                *p += (m - *p) * weight;
+            }
+            return vec![0.0; magnitudes.len()];
+        }
+        magnitudes
+            .iter()
+            .zip(&self.profile)
+            .map(|(&m, &p)| (m - self.over_subtract * p).max(0.0))
+            .collect()
+    }
+
+    /// Forget the learned profile and start learning again.
+    pub fn reset(&mut self) {
+        self.learned = 0;
+        self.profile.clear();
+    }
+}
+
+/// Noise spectrum averaged over an explicit calibration period.
+///
+/// # What
+/// Linear magnitude frames passed to [`NoiseProfile::accumulate`] are
+/// averaged into a per-bin noise estimate `N`. [`NoiseProfile::subtract`]
+/// then applies spectral subtraction to any frame `X`:
+/// `max(X - over_subtract * N, floor * N)` per bin. The floor keeps a faint,
+/// even residue instead of the fluctuating "musical noise" that zeroing
+/// leaves behind.
+///
+/// # Why
+/// Unlike [`AutoDenoiser`], which assumes the recording starts with room
+/// tone, field recordists pick the noise-only stretch themselves and may
+/// recalibrate at any time.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct NoiseProfile {
+    profile: Vec<f32>,
+    frames: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl NoiseProfile {
+    /// Create an empty profile for frames of `n_bins` magnitudes.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(n_bins: usize) -> NoiseProfile {
+        NoiseProfile {
+            profile: vec![0.0; n_bins],
+            frames: 0,
+        }
+    }
+
+    /// Add one noise-only frame to the running average.
+    ///
+    /// # Panics
+    /// Panics when `magnitudes` contains non-finite values or its length
+    /// differs from `n_bins`.
+    pub fn accumulate(&mut self, magnitudes: &[f32]) {
+        self.check(magnitudes);
+        self.frames += 1;
+        let weight = 1.0 / self.frames as f32;
+        for (p, &m) in self.profile.iter_mut().zip(magnitudes) {
+            *p += (m - *p) * weight;
+        }
+    }
+
+    /// Number of frames averaged so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Copy of the current noise estimate.
+    pub fn profile(&self) -> Vec<f32> {
+        self.profile.clone()
+    }
+
+    /// Spectral subtraction of the profile from `magnitudes`.
+    ///
+    /// Before any calibration frame the profile is zero and frames pass
+    /// through unchanged.
+    ///
+    /// # Panics
+    /// Panics when `magnitudes` contains non-finite values, its length
+    /// differs from `n_bins`, or `over_subtract` or `floor` is negative or
+    /// non-finite.
+    pub fn subtract(&self, magnitudes: &[f32], over_subtract: f32, floor: f32) -> Vec<f32> {
+        self.check(magnitudes);
+        for (name, value) in [("over_subtract", over_subtract), ("floor", floor)] {
+            assert!(
+                value.is_finite() && value >= 0.0,
+                "{name} must be a non-negative finite number"
+            );
+        }
+        if self.frames == 0 {
+            return magnitudes.to_vec();
+        }
+        magnitudes
+            .iter()
+            .zip(&self.profile)
+            .map(|(&m, &n)| (m - over_subtract * n).max(floor * n))
+            .collect()
+    }
+
+    /// Discard the estimate to start a new calibration.
+    pub fn reset(&mut self) {
+        self.profile.fill(0.0);
+        self.frames = 0;
+    }
+}
+
+impl NoiseProfile {
+    /// Validate a frame against the profile's bin count.
+    fn check(&self, magnitudes: &[f32]) {
+        validate_finite(magnitudes);
+        assert_eq!(
+            magnitudes.len(),
+            self.profile.len(),
+            "frame length must equal n_bins"
+        );
+    }
+}
+
+/// Oversampling factor used for true-peak measurement (BS.1770 asks for at
+/// least 4x at 48 kHz).
+const TRUE_PEAK_OVERSAMPLING: usize = 4;
+
+/// Integration window of momentary loudness in seconds.
+const MOMENTARY_SECONDS: f32 = 0.4;
+
+/// Integration window of short-term loudness in seconds.
+const SHORT_TERM_SECONDS: f32 = 3.0;
+
+/// Offset of the BS.1770 loudness formula, in LU.
+const LUFS_OFFSET: f32 = -0.691;
+
+/// Level meter producing one reading per STFT frame.
+///
+/// # What
+/// Fed the same chunks as a [`StftProcessor`] with equal `frame_size` and
+/// `hop`, [`LevelMeter::push`] returns one reading per frame that processor
+/// would emit, as flattened `[rms_db, true_peak_db, momentary_lufs,
+/// short_term_lufs]` quadruples:
+///
+/// * RMS and true peak cover the frame's samples, in dB relative to full
+///   scale `1.0`. The true peak is the largest magnitude after
+///   [`TRUE_PEAK_OVERSAMPLING`]-times oversampling; the interpolator needs
+///   16 samples of look-ahead, so the last samples of a frame ending near
+///   the end of a chunk contribute their sample peak only.
+/// * Momentary and short-term loudness follow ITU-R BS.1770: the
+///   K-weighted mean square over the last [`MOMENTARY_SECONDS`] or
+///   [`SHORT_TERM_SECONDS`], counting not-yet-filled time as silence,
+///   plus [`LUFS_OFFSET`].
+///
+/// Silence reads as the dB value of [`crate::EPSILON`].
+///
+/// # Why
+/// A level strip synced to the spectrogram must be sample-accurate with its
+/// frames, which is easiest when both are computed from the same chunks.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct LevelMeter {
+    sample_rate: f32,
+    frame_size: usize,
+    hop: usize,
+    k_filter: [Biquad; 2],
+    oversampler: Resampler,
+    /// Oversampled outputs received so far.
+    oversampled: usize,
+    /// Squares and peak magnitudes of the last `frame_size` samples.
+    squares: VecDeque<f32>,
+    peaks: VecDeque<f32>,
+    /// K-weighted squares of the last short-term window, oldest first.
+    weighted: VecDeque<f64>,
+    momentary_len: usize,
+    short_len: usize,
+    /// Running sums of the last `momentary_len` and `short_len` entries of
+    /// `weighted`, recomputed every `short_len` samples so rounding cannot
+    /// accumulate.
+    momentary_sum: f64,
+    short_sum: f64,
+    /// Samples since the sums were last recomputed.
+    since_resum: usize,
+    /// Samples pushed so far and the sample count that completes the next
+    /// frame.
+    total: usize,
+    next_end: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl LevelMeter {
+    /// Create a meter for frames of `frame_size` samples every `hop`.
+    ///
+    /// # Panics
+    /// Panics when `sample_rate` is not positive or `frame_size` or `hop`
+    /// is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(sample_rate: f32, frame_size: usize, hop: usize) -> LevelMeter {
+        assert!(
+            sample_rate.is_finite() && sample_rate > 0.0,
+            "sample_rate must be a positive finite number"
+        );
+        assert!(frame_size > 0, "frame_size must be positive");
+        assert!(hop > 0, "hop must be positive");
+        let oversampled_rate = sample_rate * TRUE_PEAK_OVERSAMPLING as f32;
+        let seconds = |s: f32| ((s * sample_rate).round() as usize).max(1);
+        LevelMeter {
+            sample_rate,
+            frame_size,
+            hop,
+            k_filter: Biquad::k_weighting(sample_rate),
+            oversampler: Resampler::new(sample_rate, oversampled_rate),
+            oversampled: 0,
+            squares: VecDeque::with_capacity(frame_size + 1),
+            peaks: VecDeque::with_capacity(frame_size + 1),
+            weighted: VecDeque::new(),
+            momentary_len: seconds(MOMENTARY_SECONDS),
+            short_len: seconds(SHORT_TERM_SECONDS),
+            momentary_sum: 0.0,
+            short_sum: 0.0,
+            since_resum: 0,
+            total: 0,
+            next_end: frame_size,
+        }
+    }
+
+    /// Meter `samples` and return the readings of every frame they complete.
+    ///
+    /// # Panics
+    /// Panics when `samples` contains non-finite values.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<f32> {
+        validate_finite(samples);
+        let mut pending = self.oversampler.process(samples).into_iter().map(f32::abs);
+        // Oversampled output `j` lies at input sample `j / factor`.
+        let mut next_peak = pending.next();
+        let mut readings = Vec::new();
+        for &x in samples {
+            self.total += 1;
+            self.push_sample(x);
+            while let Some(peak) = next_peak {
+                let index = self.oversampled / TRUE_PEAK_OVERSAMPLING;
+                if index >= self.total {
+                    break;
+                }
+                let oldest = self.total - self.peaks.len();
+                if index >= oldest {
+                    let slot = &mut self.peaks[index - oldest];
+                    *slot = slot.max(peak);
+                }
+                self.oversampled += 1;
+                next_peak = pending.next();
+            }
+            if self.total == self.next_end {
+                self.next_end += self.hop;
+                readings.extend_from_slice(&self.reading());
+            }
+        }
+        readings
+    }
+
+    /// Forget all buffered audio and filter state.
+    pub fn reset(&mut self) {
+        *self = LevelMeter::new(self.sample_rate, self.frame_size, self.hop);
+    }
+}
+
+impl LevelMeter {
+    /// Fold one input sample into the frame and loudness windows.
+    fn push_sample(&mut self, x: f32) {
+        self.squares.push_back(x * x);
+        self.peaks.push_back(x.abs());
+        if self.squares.len() > self.frame_size {
+            self.squares.pop_front();
+            self.peaks.pop_front();
+        }
+        let y = self
+            .k_filter
+            .iter_mut()
+            .fold(x, |acc, stage| stage.process_sample(acc));
+        let power = (y as f64) * (y as f64);
+        self.weighted.push_back(power);
+        self.momentary_sum += power;
+        self.short_sum += power;
+        let len = self.weighted.len();
+        if len > self.momentary_len {
+            self.momentary_sum -= self.weighted[len - 1 - self.momentary_len];
+        }
+        if len > self.short_len {
+            self.short_sum -= self.weighted.pop_front().unwrap_or(0.0);
+        }
+        self.since_resum += 1;
+        if self.since_resum == self.short_len {
+            self.since_resum = 0;
+            let skip = self.weighted.len().saturating_sub(self.momentary_len);
+            self.momentary_sum = self.weighted.iter().skip(skip).sum();
+            self.short_sum = self.weighted.iter().sum();
+        }
+    }
+
+    /// `[rms_db, true_peak_db, momentary_lufs, short_term_lufs]` now.
+    fn reading(&self) -> [f32; 4] {
+        let db = |power: f32| POWER_DB_SCALE * power.max(EPSILON).log10();
+        let mean_square = self.squares.iter().sum::<f32>() / self.frame_size as f32;
+        let peak = self.peaks.iter().copied().fold(0.0, f32::max);
+        let momentary = (self.momentary_sum / self.momentary_len as f64) as f32;
+        let short = (self.short_sum / self.short_len as f64) as f32;
+        [
+            db(mean_square),
+            db(peak * peak),
+            LUFS_OFFSET + db(momentary),
+            LUFS_OFFSET + db(short),
+        ]
+    }
+}
+
+/// Streaming Goertzel filters at a fixed set of frequencies.
+///
+/// # What
+/// Splits the pushed samples into consecutive blocks of `block_size`,
+/// regardless of how they are chunked, and for every completed block
+/// returns the level at each of `freqs_hz` as `20 * log10(2 |X| / N)`: a
+/// sine of amplitude `A` at one of the frequencies reads `20 * log10(A)`
+/// dB, so a full-scale tone reads 0 dB. Frequencies outside
+/// `0..=sample_rate / 2` read as silence, the dB value of
+/// [`crate::EPSILON`].
+///
+/// # Why
+/// Overlay traces of a few frequencies (DTMF tones, one harmonic) need
+/// one multiply-add per frequency and sample instead of a full FFT per
+/// frame.
+///
+/// # How
+/// Each frequency runs the Goertzel recurrence of
+/// [`crate::goertzel`] in `f64`, carrying its two states across chunks and
+/// clearing them after every block.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct GoertzelBank {
+    freqs_hz: Vec<f32>,
+    /// `2 cos(omega)` per frequency; `None` outside the valid range.
+    coeffs: Vec<Option<f64>>,
+    block_size: usize,
+    /// Samples of the current block seen so far.
+    filled: usize,
+    /// `(s[n-1], s[n-2])` per frequency.
+    states: Vec<(f64, f64)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GoertzelBank {
+    /// Create a bank tracking `freqs_hz` over blocks of `block_size`.
+    ///
+    /// # Panics
+    /// Panics when `sample_rate` is not positive, `freqs_hz` contains
+    /// non-finite values or `block_size` is zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(sample_rate: f32, freqs_hz: &[f32], block_size: usize) -> GoertzelBank {
+        assert!(
+            sample_rate.is_finite() && sample_rate > 0.0,
+            "sample_rate must be a positive finite number"
+        );
+        validate_finite(freqs_hz);
+        assert!(block_size > 0, "block_size must be positive");
+        let coeffs = freqs_hz
+            .iter()
+            .map(|&f| {
+                (0.0..=sample_rate / 2.0)
+                    .contains(&f)
+                    .then(|| 2.0 * (std::f64::consts::TAU * f as f64 / sample_rate as f64).cos())
+            })
+            .collect();
+        GoertzelBank {
+            freqs_hz: freqs_hz.to_vec(),
+            coeffs,
+            block_size,
+            filled: 0,
+            states: vec![(0.0, 0.0); freqs_hz.len()],
+        }
+    }
+
+    /// The tracked frequencies in Hz, in output order.
+    pub fn frequencies(&self) -> Vec<f32> {
+        self.freqs_hz.clone()
+    }
+
+    /// Samples per block.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Run `samples` through the bank and return the levels of every block
+    /// they complete, one value per frequency per block.
+    ///
+    /// # Panics
+    /// Panics when `samples` contains non-finite values.
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        validate_finite(samples);
+        let mut levels = Vec::new();
+        let mut rest = samples;
+        while !rest.is_empty() {
+            let take = (self.block_size - self.filled).min(rest.len());
+            let (block, tail) = rest.split_at(take);
+            for (state, coeff) in self.states.iter_mut().zip(&self.coeffs) {
+                if let Some(coeff) = *coeff {
+                    let (mut s1, mut s2) = *state;
+                    for &x in block {
+                        let s0 = x as f64 + coeff * s1 - s2;
+                        s2 = s1;
+                        s1 = s0;
+                    }
+                    *state = (s1, s2);
+                }
+            }
+            self.filled += take;
+            rest = tail;
+            if self.filled == self.block_size {
+                levels.extend(self.block_levels());
+                self.reset();
+            }
+        }
+        levels
+    }
+
+    /// Discard the partial block.
+    pub fn reset(&mut self) {
+        self.filled = 0;
+        self.states.fill((0.0, 0.0));
+    }
+}
+
+impl GoertzelBank {
+    /// Levels in dB of the block that has just been completed.
+    fn block_levels(&self) -> impl Iterator<Item = f32> + '_ {
+        let scale = 2.0 / self.block_size as f64;
+        self.states
+            .iter()
+            .zip(&self.coeffs)
+            .map(move |(&(s1, s2), coeff)| {
+                let power = coeff.map_or(0.0, |c| (s1 * s1 + s2 * s2 - c * s1 * s2).max(0.0));
+                let amplitude = (power.sqrt() * scale) as f32;
+                DB_SCALE * amplitude.max(EPSILON).log10()
+            })
+    }
+}
+
+/// Spectral-flux onset detector over successive magnitude frames.
+///
+/// # What
+/// [`OnsetDetector::process`] returns each frame's onset strength, the
+/// half-wave rectified spectral flux `sum max(m[k] - prev[k], 0)` per bin,
+/// and [`OnsetDetector::is_onset`] flags frames where it rises above
+/// `threshold` times the mean strength of the preceding `history` frames.
+/// Only the first frame of such a run is flagged. The high-frequency
+/// content `sum k * m[k]^2` per bin is computed alongside for
+/// percussive material.
+///
+/// # Why
+/// Onset markers on the spectrogram need the previous frame for
+/// differencing; keeping it here saves shipping every frame back to
+/// JavaScript.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct OnsetDetector {
+    threshold: f32,
+    history_len: usize,
+    /// Strengths of the most recent frames, oldest first.
+    history: VecDeque<f32>,
+    previous: Vec<f32>,
+    onset: bool,
+    above: bool,
+    hfc: f32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OnsetDetector {
+    /// Create a detector comparing each frame against the mean strength of
+    /// the last `history` frames; `threshold` around `1.5` to `3` suits
+    /// most material.
+    ///
+    /// # Panics
+    /// Panics when `threshold` is negative or non-finite, or `history` is
+    /// zero.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(threshold: f32, history: usize) -> OnsetDetector {
+        assert!(
+            threshold.is_finite() && threshold >= 0.0,
+            "threshold must be a non-negative finite number"
+        );
+        assert!(history > 0, "history must be positive");
+        OnsetDetector {
+            threshold,
+            history_len: history,
+            history: VecDeque::with_capacity(history),
+            previous: Vec::new(),
+            onset: false,
+            above: false,
+            hfc: 0.0,
+        }
+    }
+
+    /// Fold in one linear magnitude frame and return its onset strength.
+    /// The first frame after creation or [`OnsetDetector::reset`] has
+    /// strength `0`.
+    ///
+    /// # Panics
+    /// Panics when `magnitudes` contains non-finite values or its length
+    /// differs from the first frame's.
+    pub fn process(&mut self, magnitudes: &[f32]) -> f32 {
+        validate_finite(magnitudes);
+        let n = magnitudes.len().max(1) as f32;
+        self.hfc = magnitudes
+            .iter()
+            .enumerate()
+            .map(|(k, &m)| k as f32 * m * m)
+            .sum::<f32>()
+            / n;
+        if self.previous.is_empty() {
+            self.previous = magnitudes.to_vec();
+            self.history.push_back(0.0);
+            return 0.0;
+        }
+        assert_eq!(
+            magnitudes.len(),
+            self.previous.len(),
+            "frame length must stay constant"
+        );
+        let strength = magnitudes
+            .iter()
+            .zip(&self.previous)
+            .map(|(&m, &p)| (m - p).max(0.0))
+            .sum::<f32>()
+            / n;
+        self.previous.copy_from_slice(magnitudes);
+
+        let mean = self.history.iter().sum::<f32>() / self.history.len() as f32;
+        let above = strength > 0.0 && strength > self.threshold * mean;
+        self.onset = above && !self.above;
+        self.above = above;
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(strength);
+        strength
+    }
+
+    /// Whether the last processed frame starts an onset.
+    pub fn is_onset(&self) -> bool {
+        self.onset
+    }
+
+    /// High-frequency content of the last processed frame.
+    pub fn high_frequency_content(&self) -> f32 {
+        self.hfc
+    }
+
+    /// Forget the previous frame and the strength history.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.previous.clear();
+        self.onset = false;
+        self.above = false;
+        self.hfc = 0.0;
+    }
+}
+
+/// Spectral peak picker that links peaks across frames into tracks.
+///
+/// # What
+/// [`PeakTracker::process`] finds the local maxima of a half-spectrum dB
+/// frame (as from [`crate::FrameAnalyzer::analyze`]) above `threshold_db`,
+/// keeps the `max_peaks` strongest and refines each by fitting a parabola
+/// through the peak bin and its neighbours, giving sub-bin frequency and
+/// amplitude. Each peak then continues the previous frame's track closest
+/// in frequency, within `max_jump_hz`; closer pairs are linked first and a
+/// track continues at most one peak. Unmatched peaks start new tracks and
+/// tracks without a peak end.
+///
+/// # Why
+/// Partial-tracking overlays and sinusoidal models need peaks identified
+/// across frames, not just per frame.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PeakTracker {
+    bin_hz: f32,
+    max_peaks: usize,
+    threshold_db: f32,
+    max_jump_hz: f32,
+    /// Id and frequency of every track alive after the last frame.
+    tracks: Vec<(u32, f32)>,
+    next_id: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PeakTracker {
+    /// Create a tracker for frames of an `fft_size`-point FFT at
+    /// `sample_rate`.
+    ///
+    /// # Panics
+    /// Panics when `sample_rate` is not positive, `fft_size` is zero,
+    /// `threshold_db` is NaN or `max_jump_hz` is negative or non-finite.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        sample_rate: f32,
+        fft_size: usize,
+        max_peaks: usize,
+        threshold_db: f32,
+        max_jump_hz: f32,
+    ) -> PeakTracker {
+        assert!(
+            sample_rate.is_finite() && sample_rate > 0.0,
+            "sample_rate must be a positive finite number"
+        );
+        assert!(fft_size > 0, "fft_size must be positive");
+        assert!(!threshold_db.is_nan(), "threshold_db must not be NaN");
+        assert!(
+            max_jump_hz.is_finite() && max_jump_hz >= 0.0,
+            "max_jump_hz must be a non-negative finite number"
+        );
+        PeakTracker {
+            bin_hz: sample_rate / fft_size as f32,
+            max_peaks,
+            threshold_db,
+            max_jump_hz,
+            tracks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Pick the peaks of `frame_db` and link them to the running tracks.
+    ///
+    /// Returns flattened `[track_id, freq_hz, amplitude_db]` triples in
+    /// ascending frequency. The `-inf` bins of digital silence count as
+    /// below the threshold.
+    ///
+    /// # Panics
+    /// Panics when `frame_db` contains NaN.
+    pub fn process(&mut self, frame_db: &[f32]) -> Vec<f32> {
+        validate_not_nan(frame_db);
+        let peaks = self.pick_peaks(frame_db);
+
+        let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+        for (t, &(_, track_hz)) in self.tracks.iter().enumerate() {
+            for (p, &(hz, _)) in peaks.iter().enumerate() {
+                let distance = (hz - track_hz).abs();
+                if distance <= self.max_jump_hz {
+                    pairs.push((distance, t, p));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut ids: Vec<Option<u32>> = vec![None; peaks.len()];
+        let mut continued = vec![false; self.tracks.len()];
+        for (_, t, p) in pairs {
+            if !continued[t] && ids[p].is_none() {
+                continued[t] = true;
+                ids[p] = Some(self.tracks[t].0);
+            }
+        }
+
+        self.tracks.clear();
+        let mut output = Vec::with_capacity(3 * peaks.len());
+        for (&(hz, db), id) in peaks.iter().zip(ids) {
+            let id = id.unwrap_or_else(|| {
+                self.next_id += 1;
+                self.next_id - 1
+            });
+            self.tracks.push((id, hz));
+            output.extend_from_slice(&[id as f32, hz, db]);
+        }
+        output
+    }
+
+    /// End every track; ids keep increasing so old ones are not reused.
+    pub fn reset(&mut self) {
+        self.tracks.clear();
+    }
+}
+
+impl PeakTracker {
+    /// Interpolated `(freq_hz, amplitude_db)` of the strongest local maxima,
+    /// in ascending frequency. Peaks next to an infinite bin are not
+    /// interpolated, since no parabola fits through them.
+    fn pick_peaks(&self, frame_db: &[f32]) -> Vec<(f32, f32)> {
+        let mut peaks: Vec<(f32, f32)> = (1..frame_db.len().saturating_sub(1))
+            .filter(|&k| {
+                let v = frame_db[k];
+                v > self.threshold_db && v > frame_db[k - 1] && v >= frame_db[k + 1]
+            })
+            .map(|k| {
+                let (left, center, right) = (frame_db[k - 1], frame_db[k], frame_db[k + 1]);
+                if !(left.is_finite() && center.is_finite() && right.is_finite()) {
+                    return (k as f32 * self.bin_hz, center);
+                }
+                let offset = parabolic_offset(left, center, right);
+                let amplitude = center - 0.25 * (left - right) * offset;
+                ((k as f32 + offset) * self.bin_hz, amplitude)
+            })
+            .collect();
+        peaks.sort_by(|a, b| b.1.total_cmp(&a.1));
+        peaks.truncate(self.max_peaks);
+        peaks.sort_by(|a, b| a.0.total_cmp(&b.0));
+        peaks
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::noise;
+
+    /// Frame length used by the streaming tests.
+    const FRAME: usize = 32;
+
+    /// Hop length used by the streaming tests.
+    const HOP: usize = 8;
+
+    /// Feed `signal` to `processor` in irregular chunk sizes.
+    fn push_chunked(processor: &mut StftProcessor, signal: &[f32]) -> Vec<f32> {
+        let chunk_sizes = [3usize, 17, 1, 40, 9];
+        let mut output = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while start < signal.len() {
+            let end = (start + chunk_sizes[i % chunk_sizes.len()]).min(signal.len());
+            output.extend(processor.push(&signal[start..end]));
+            start = end;
+            i += 1;
+        }
+        output
+    }
+
+    /// Irregular chunks yield exactly the rows of one `stft_signal` call,
+    /// `reset` starts over from a clean buffer, and `StftStream` is the
+    /// same processor.
+    #[test]
+    fn chunked_push_matches_stft_signal() {
+        // Whole frames only, so `stft_signal` pads nothing.
+        let signal = noise(FRAME + 50 * HOP, 21);
+        let expected = crate::stft_signal(&signal, FRAME, HOP, "hann", 1.0);
+
+        let mut processor = StftProcessor::new(FRAME, HOP, "hann", 1.0);
+        assert_eq!(push_chunked(&mut processor, &signal), expected);
+
+        processor.push(&signal[..FRAME / 2]);
+        processor.reset();
+        assert_eq!(push_chunked(&mut processor, &signal), expected);
+
+        let mut stream: StftStream = StftStream::new(FRAME, HOP, "hann", 1.0);
+        assert_eq!(push_chunked(&mut stream, &signal), expected);
+    }
+
+    /// The buffer-backed processor matches the analyzer without allocating.
+    #[test]
+    fn frame_processor_reuses_buffers() {
+        let n = 256;
+        let frame = noise(n, 31);
+        let expected = FrameAnalyzer::new(n, "hann").analyze(&frame, 1.0);
+
+        let mut processor = FrameProcessor::new(n, "hann", 1.0);
+        assert_eq!(
+            (processor.input_len(), processor.output_len()),
+            (n, n / 2 + 1)
+        );
+        processor.input_mut().copy_from_slice(&frame);
+        let allocations = crate::test_support::allocations_during(|| processor.process());
+        assert_eq!(allocations, 0);
+        assert_eq!(processor.output(), &expected[..]);
+    }
+
+    /// Worklet-sized chunks emit a frame as soon as each one completes.
+    #[test]
+    fn frames_are_emitted_as_soon_as_complete() {
+        let (frame, hop, chunk) = (1024, 256, 128);
+        let mut processor = StftProcessor::new(frame, hop, "hann", 1.0);
+        let signal = noise(4096, 2);
+        for (i, block) in signal.chunks_exact(chunk).enumerate() {
+            let received = (i + 1) * chunk;
+            let completed_now = received >= frame && (received - frame).is_multiple_of(hop);
+            let expected = if completed_now { frame } else { 0 };
+            assert_eq!(processor.push(block).len(), expected, "chunk {i}");
+        }
+    }
+
+    /// With `emit_every = 4` only every fourth frame is returned, and those
+    /// frames are identical to the matching frames of a full-rate processor.
+    #[test]
+    fn emit_every_decimates_frames() {
+        let signal: Vec<f32> = (0..400).map(|i| (i as f32 * 0.21).sin()).collect();
+
+        let mut full = StftProcessor::new(FRAME, HOP, "hann", 1.0);
+        let all_frames = push_chunked(&mut full, &signal);
+
+        let mut decimated = StftProcessor::new(FRAME, HOP, "hann", 1.0);
+        decimated.set_emit_every(4);
+        let some_frames = push_chunked(&mut decimated, &signal);
+
+        let expected: Vec<f32> = all_frames
+            .chunks_exact(FRAME)
+            .step_by(4)
+            .flatten()
+            .copied()
+            .collect();
+        assert_eq!(all_frames.len() / FRAME, (signal.len() - FRAME) / HOP + 1);
+        assert_eq!(some_frames, expected);
+    }
+
+    /// A preprocessed stream yields the frames of the pre-filtered signal,
+    /// also after `reset`.
+    #[test]
+    fn preprocessor_filters_before_framing() {
+        let signal = noise(FRAME + 40 * HOP, 8);
+        let preprocessor = Preprocessor::new(8000.0, true, 0.9, 50.0);
+        let filtered = preprocessor.clone().process(&signal);
+        let mut plain = StftProcessor::new(FRAME, HOP, "hann", 1.0);
+        let expected = push_chunked(&mut plain, &filtered);
+
+        let mut processor = StftProcessor::new(FRAME, HOP, "hann", 1.0);
+        processor.set_preprocessor(Some(preprocessor));
+        assert_eq!(push_chunked(&mut processor, &signal), expected);
+        processor.reset();
+        assert_eq!(push_chunked(&mut processor, &signal), expected);
+    }
+
+    /// A steady tone drives the smoothed spectrum to the tone's spectrum.
+    #[test]
+    fn live_analyzer_converges_to_steady_spectrum() {
+        let frame = 64;
+        // Bin-centered tone: every frame has the same magnitude spectrum.
+        let signal: Vec<f32> = (0..4096)
+            .map(|i| (crate::TWO_PI * 4.0 * i as f32 / frame as f32).sin())
+            .collect();
+        let expected = crate::stft_frame(&signal[..frame], "hann", 1.0);
+
+        let mut analyzer = LiveAnalyzer::new(frame, 16, "hann", 1.0, 0.9);
+        let mut latest = Vec::new();
+        for chunk in signal.chunks(100) {
+            let out = analyzer.push(chunk);
+            if !out.is_empty() {
+                latest = out;
+            }
+        }
+        assert_eq!(latest.len(), frame);
+        for (bin, (&got, &want)) in latest.iter().zip(&expected).enumerate() {
+            if want > -60.0 {
+                assert!((got - want).abs() < 0.1, "bin {bin}: {got} vs {want}");
+            }
+        }
+        assert!(analyzer.push(&[0.0]).is_empty());
+    }
+
+    /// The smoother moves geometrically towards a new constant input.
+    #[test]
+    fn spectrum_smoother_blends_frames() {
+        let mut smoother = SpectrumSmoother::new(0.5);
+        assert_eq!(smoother.process(&[0.0, 4.0]), vec![0.0, 4.0]);
+        assert_eq!(smoother.process(&[4.0, 0.0]), vec![2.0, 2.0]);
+        assert_eq!(smoother.process(&[4.0, 0.0]), vec![3.0, 1.0]);
+        smoother.reset();
+        assert_eq!(smoother.process(&[1.0]), vec![1.0]);
+    }
+
+    /// Identical channels read +1 and inverted channels read -1.
+    #[test]
+    fn correlation_meter_detects_polarity() {
+        let signal: Vec<f32> = crate::test_support::noise(2000, 5);
+        let inverted: Vec<f32> = signal.iter().map(|v| -v).collect();
+
+        let mut meter = CorrelationMeter::new(8000.0, 50.0);
+        let mut reading = 0.0;
+        for chunk in signal.chunks(128) {
+            reading = meter.process(chunk, chunk);
+        }
+        assert!((reading - 1.0).abs() < 1e-4, "{reading}");
+
+        meter.reset();
+        for (l, r) in signal.chunks(128).zip(inverted.chunks(128)) {
+            reading = meter.process(l, r);
+        }
+        assert!((reading + 1.0).abs() < 1e-4, "{reading}");
+    }
+
+    /// Noise learned from leading frames is removed while a tone survives.
+    #[test]
+    fn auto_denoiser_learns_leading_noise() {
+        let bins = 64;
+        let tone_bin = 20;
+        let noise_frame =
+            |seed: u64| -> Vec<f32> { noise(bins, seed).iter().map(|v| 0.5 + 0.1 * v).collect() };
+        let mut denoiser = AutoDenoiser::new(8, 1.5);
+        for seed in 0..8 {
+            assert!(!denoiser.is_ready());
+            assert!(denoiser
+                .process(&noise_frame(seed))
+                .iter()
+                .all(|&v| v == 0.0));
+        }
+        assert!(denoiser.is_ready());
+
+        let mut noisy = noise_frame(99);
+        noisy[tone_bin] += 5.0;
+        let cleaned = denoiser.process(&noisy);
+        assert!(cleaned[tone_bin] > 4.5, "{}", cleaned[tone_bin]);
+        let residual: f32 = cleaned
+            .iter()
+            .enumerate()
+            .filter(|&(k, _)| k != tone_bin)
+            .map(|(_, v)| v)
+            .sum();
+        let before: f32 = noisy
+            .iter()
+            .enumerate()
+            .filter(|&(k, _)| k != tone_bin)
+            .map(|(_, v)| v)
+            .sum();
+        assert!(residual < 0.05 * before, "{residual} vs {before}");
+    }
+
+    /// Calibrated noise is reduced to the floor while a tone stays above it.
+    #[test]
+    fn noise_profile_subtracts_calibrated_floor() {
+        let bins = 33;
+        let mut profile = NoiseProfile::new(bins);
+        let frame = vec![0.5f32; bins];
+        assert_eq!(profile.subtract(&frame, 2.0, 0.1), frame);
+
+        for seed in 0..8 {
+            let noisy: Vec<f32> = noise(bins, seed).iter().map(|v| 0.2 + 0.05 * v).collect();
+            profile.accumulate(&noisy);
+        }
+        assert_eq!(profile.frames(), 8);
+        let noise_level = profile.profile();
+        assert!(noise_level.iter().all(|&n| (n - 0.2).abs() < 0.05));
+
+        let mut signal: Vec<f32> = noise(bins, 99).iter().map(|v| 0.2 + 0.05 * v).collect();
+        signal[10] = 3.0;
+        let cleaned = profile.subtract(&signal, 1.5, 0.1);
+        assert!(cleaned[10] > 2.5);
+        for (k, (&c, &n)) in cleaned.iter().zip(&noise_level).enumerate() {
+            if k != 10 {
+                assert!((c - 0.1 * n).abs() < 1e-6, "bin {k}: {c}");
+            }
+        }
+    }
+
+    /// A burst after steady frames is flagged once; steady frames are not.
+    #[test]
+    fn onset_detector_flags_bursts() {
+        let steady = vec![0.1f32; 64];
+        let mut burst = steady.clone();
+        burst[40..48].fill(1.0);
+        let mut detector = OnsetDetector::new(2.0, 8);
+        let mut flags = Vec::new();
+        for frame in [&steady; 6]
+            .into_iter()
+            .chain([&burst, &burst, &steady, &burst])
+        {
+            detector.process(frame);
+            flags.push(detector.is_onset());
+        }
+        let expected = [false; 6].into_iter().chain([true, false, false, true]);
+        assert!(flags.into_iter().eq(expected));
+
+        let hfc_burst = detector.high_frequency_content();
+        detector.process(&steady);
+        assert!(hfc_burst > detector.high_frequency_content());
+    }
+
+    /// A gliding tone keeps its track id; a tone that appears later gets a
+    /// new one.
+    #[test]
+    fn peak_tracker_links_gliding_partial() {
+        let (rate, n) = (8000.0f32, 512);
+        let mut analyzer = FrameAnalyzer::new(n, "hann");
+        let mut tracker = PeakTracker::new(rate, n, 4, -40.0, 50.0);
+        let tone = |hz: f32| -> Vec<f32> {
+            (0..n)
+                .map(|i| (crate::TWO_PI * hz * i as f32 / rate).sin())
+                .collect()
+        };
+        for f in 0..6 {
+            let hz = 1000.0 + 10.3 * f as f32;
+            let mut frame = tone(hz);
+            if f >= 3 {
+                frame
+                    .iter_mut()
+                    .zip(tone(2500.0))
+                    .for_each(|(x, y)| *x += y);
+            }
+            let peaks = tracker.process(&analyzer.analyze(&frame, 1.0));
+            let triples: Vec<&[f32]> = peaks.chunks_exact(3).collect();
+            assert_eq!(triples.len(), if f >= 3 { 2 } else { 1 }, "{peaks:?}");
+            assert_eq!(triples[0][0], 0.0);
+            assert!((triples[0][1] - hz).abs() < 0.5, "{peaks:?}");
+            if f >= 3 {
+                assert_eq!(triples[1][0], 1.0);
+                assert!((triples[1][1] - 2500.0).abs() < 0.5, "{peaks:?}");
+            }
+        }
+    }
+
+    /// Silent `-inf` bins neither form peaks nor break the interpolation of
+    /// their neighbours, and an all-silent frame ends every track.
+    #[test]
+    fn peak_tracker_accepts_silent_bins() {
+        let silent = f32::NEG_INFINITY;
+        let mut tracker = PeakTracker::new(8000.0, 8, 4, -60.0, 50.0);
+        let frame = [silent, silent, -10.0, silent, -30.0, -20.0, -25.0, silent];
+        let peaks = tracker.process(&frame);
+        assert_eq!(peaks.len(), 6, "{peaks:?}");
+        assert_eq!(&peaks[..3], &[0.0, 2000.0, -10.0]);
+        assert!(peaks[3] == 1.0 && peaks[4] > 5000.0 && peaks[4] < 5500.0);
+        assert!(tracker.process(&[silent; 8]).is_empty());
+        assert_eq!(tracker.process(&frame)[0], 2.0);
+    }
+
+    /// A single outlier frame does not move the tracker; a lasting change does.
+    #[test]
+    fn pitch_tracker_ignores_single_outliers() {
+        let (rate, fft_size) = (8000.0f32, 1024);
+        let frame = |hz: f32| -> Vec<f32> {
+            (0..fft_size)
+                .map(|i| (crate::TWO_PI * hz * i as f32 / rate).sin())
+                .collect()
+        };
+        let (low, high) = (440.0f32, 660.0f32);
+        let near = |a: f32, b: f32| (a - b).abs() < 2.0;
+
+        let mut tracker = PitchTracker::new(rate, fft_size);
+        for _ in 0..4 {
+            assert!(near(tracker.push(&frame(low)), low));
+        }
+        assert!(
+            near(tracker.push(&frame(high)), low),
+            "outlier must not flip"
+        );
+        assert!(near(tracker.push(&frame(low)), low));
+
+        let mut tracked = 0.0;
+        for _ in 0..PITCH_HOLD_FRAMES {
+            tracked = tracker.push(&frame(high));
+        }
+        assert!(near(tracked, high), "{tracked}");
+    }
+
+    /// A sustained tone stays above the floor; noise is tracked from below.
+    #[test]
+    fn noise_floor_tracks_lower_envelope() {
+        let mut tracker = NoiseFloorTracker::new(2, 0.005);
+        let jitter = noise(400, 4);
+        let mut floor = Vec::new();
+        for (f, &j) in jitter.iter().enumerate() {
+            let tone = if (200..250).contains(&f) { 5.0 } else { 0.1 };
+            floor = tracker.update(&[tone, 1.0 + 0.5 * j]);
+            if (200..250).contains(&f) {
+                assert!(floor[0] < 0.25 * tone, "frame {f}: {floor:?}");
+            }
+        }
+        assert!(floor[0] < 0.15, "{floor:?}");
+        // The fluctuating bin sits between its minimum and its mean.
+        assert!(floor[1] >= 0.5 && floor[1] < 0.9, "{floor:?}");
+    }
+
+    /// The reusable analyzer matches and outpaces repeated `stft_frame` calls.
+    #[test]
+    fn frame_analyzer_beats_stft_frame() {
+        use std::time::Instant;
+        let (n, runs) = (1024, 200);
+        let frame = noise(n, 13);
+        let mut analyzer = FrameAnalyzer::new(n, "hann");
+        let fast = analyzer.analyze(&frame, 1.0);
+        let slow = crate::stft_frame(&frame, "hann", 1.0);
+        assert_eq!(fast.len(), n / 2 + 1);
+        for (a, b) in fast.iter().zip(&slow) {
+            assert!((a - b).abs() < 1e-3, "{a} vs {b}");
+        }
+
+        let start = Instant::now();
+        for _ in 0..runs {
+            std::hint::black_box(crate::stft_frame(&frame, "hann", 1.0));
+        }
+        let repeated = start.elapsed();
+        let start = Instant::now();
+        for _ in 0..runs {
+            std::hint::black_box(analyzer.analyze(&frame, 1.0));
+        }
+        let reused = start.elapsed();
+        assert!(
+            reused < repeated,
+            "analyzer {reused:?} >= stft_frame {repeated:?}"
+        );
+    }
+
+    /// A half-scale 1 kHz sine reads -9.03 dB RMS, -6.02 dB true peak and,
+    /// once the short-term window is full, about -9 LUFS (K-weighting is
+    /// close to 0 dB at 1 kHz, and BS.1770 calibrates so a full-scale sine
+    /// reads -3 LUFS).
+    #[test]
+    fn level_meter_reads_sine_levels() {
+        let sr = 48_000.0;
+        let signal: Vec<f32> = (0..192_000)
+            .map(|i| 0.5 * (std::f32::consts::TAU * 1000.0 * i as f32 / sr).sin())
+            .collect();
+        let mut meter = LevelMeter::new(sr, 2048, 512);
+        let mut readings = Vec::new();
+        for chunk in signal.chunks(700) {
+            readings.extend(meter.push(chunk));
+        }
+        let last = &readings[readings.len() - 4..];
+        assert!((last[0] + 9.03).abs() < 0.05, "rms {}", last[0]);
+        assert!((last[1] + 6.02).abs() < 0.05, "true peak {}", last[1]);
+        assert!((last[2] - last[3]).abs() < 0.05);
+        assert!((last[3] + 9.0).abs() < 0.2, "short-term {}", last[3]);
+
+        meter.reset();
+        let quiet = meter.push(&vec![0.0; 2048]);
+        assert!(quiet[0] < -100.0 && quiet[3] < -100.0);
+    }
+
+    /// Silence after loud input reads the floor: the loudness windows
+    /// release exactly what they accumulated.
+    #[test]
+    fn level_meter_returns_to_floor_after_loud_input() {
+        let sr = 8000.0;
+        let mut signal: Vec<f32> = noise(10 * 8000, 12).iter().map(|x| x.signum()).collect();
+        signal.extend(vec![0.0; 4 * 8000]);
+        let mut meter = LevelMeter::new(sr, 1024, 1024);
+        let mut readings = Vec::new();
+        for chunk in signal.chunks(128) {
+            readings.extend(meter.push(chunk));
+        }
+        let floor = LUFS_OFFSET + POWER_DB_SCALE * EPSILON.log10();
+        let last = &readings[readings.len() - 4..];
+        assert!((last[2] - floor).abs() < 1e-3, "momentary {}", last[2]);
+        assert!((last[3] - floor).abs() < 1e-3, "short-term {}", last[3]);
+    }
+
+    /// Readings line up with the processor's frames, and a sine at a
+    /// quarter of the rate sampled 45 degrees off its peaks shows true peak
+    /// 3 dB above sample peak.
+    #[test]
+    fn level_meter_matches_frames_and_finds_true_peak() {
+        let signal: Vec<f32> = (0..5000)
+            .map(|i| (std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4).sin())
+            .collect();
+        let mut meter = LevelMeter::new(48_000.0, 256, 100);
+        let mut processor = StftProcessor::new(256, 100, "hann", 1.0);
+        let (mut readings, mut frames) = (0, 0);
+        for chunk in signal.chunks(333) {
+            readings += meter.push(chunk).len() / 4;
+            frames += processor.push(chunk).len() / 256;
+        }
+        assert_eq!(readings, frames);
+        let reading = meter.push(&signal[..100]);
+        let sample_peak_db = DB_SCALE * std::f32::consts::FRAC_1_SQRT_2.log10();
+        assert!(
+            reading[1] - sample_peak_db > 2.5,
+            "true peak {}",
+            reading[1]
+        );
+    }
+
+    /// A DTMF pair reads its amplitudes at its two tones and little
+    /// elsewhere, identically for one call and for worklet-sized chunks.
+    #[test]
+    fn goertzel_bank_tracks_selected_tones() {
+        let rate = 8000.0;
+        let signal: Vec<f32> = (0..1600)
+            .map(|i| {
+                let t = i as f32 / rate;
+                0.5 * (crate::TWO_PI * 770.0 * t).sin() + 0.25 * (crate::TWO_PI * 1336.0 * t).sin()
+            })
+            .collect();
+        let freqs = [697.0, 770.0, 1336.0, 1477.0, 5000.0];
+        let mut bank = GoertzelBank::new(rate, &freqs, 400);
+        let levels = bank.process(&signal);
+        assert_eq!(levels.len(), 4 * freqs.len());
+        let first = &levels[..freqs.len()];
+        assert!((first[1] + 6.02).abs() < 0.3, "770 Hz {}", first[1]);
+        assert!((first[2] + 12.04).abs() < 0.3, "1336 Hz {}", first[2]);
+        assert!(first[0] < first[1] - 20.0 && first[3] < first[2] - 10.0);
+        assert!(first[4] < -200.0);
+
+        let mut chunked = GoertzelBank::new(rate, &freqs, 400);
+        let mut streamed = Vec::new();
+        for chunk in signal.chunks(128) {
+            streamed.extend(chunked.process(chunk));
+        }
+        for (a, b) in streamed.iter().zip(&levels) {
+            assert!((a - b).abs() < 1e-4);
+        }
+        assert_eq!(streamed.len(), levels.len());
+    }
+}
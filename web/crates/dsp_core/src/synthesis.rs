@@ -0,0 +1,570 @@
+//! Overlap-add resynthesis from short-time frames.
+//!
+//! # What
+//! Helpers that turn windowed frames back into a continuous signal and
+//! describe the gain that overlap-adding introduces.
+//!
+//! # Why
+//! Spectral effects (denoising, stretching) analyse with an STFT and then
+//! overlap-add the modified frames. The summed windows scale the output by
+//! a window- and hop-dependent factor that must be compensated.
+
+use crate::filters::{fir_filter_centered, lowpass_taps};
+use crate::spectral::wrap_phase;
+use crate::{
+    half_spectrum_len, mirror_half_spectrum, parse_window, plan_forward, plan_inverse,
+    validate_finite, window_coefficients, WindowType, HAMMING_ALPHA, HANN_A0, TWO_PI,
+};
+use rustfft::num_complex::Complex32;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Steady-state gain of overlap-adding `window_type` frames every `hop`.
+///
+/// # What
+/// Returns the mean of the periodic window sum `sum_m w[i - m * hop]`,
+/// which equals `sum(w) / hop`. Dividing overlap-added output by this value
+/// restores unity gain. For Hann at 50% overlap the gain is close to `1`;
+/// at 75% overlap it is close to `2`. Unknown window names panic, matching
+/// [`crate::apply_window`].
+///
+/// # Why
+/// The mean is well defined for every hop, including hops where the window
+/// sum ripples instead of being constant.
+///
+/// # Panics
+/// Panics when `fft_size` or `hop` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn overlap_add_gain(window_type: &str, fft_size: usize, hop: usize) -> f32 {
+    assert!(fft_size > 0, "fft_size must be positive");
+    assert!(hop > 0, "hop must be positive");
+    let window = parse_window(window_type);
+    let sum: f32 = window_coefficients(window, fft_size).iter().sum();
+    sum / hop as f32
+}
+
+/// Overlap-added squared-window sums below this are treated as silence
+/// instead of being divided by.
+const NORM_FLOOR: f32 = 1e-3;
+
+/// Closed-form overlap-add constant of `window_type` frames every `hop`.
+///
+/// # What
+/// Returns `Some` when the periodic window sum is a known constant:
+/// rectangular at any hop dividing `fft_size` (`fft_size / hop`), and Hann
+/// (`R / 2`) or Hamming (`0.54 R`) when `R = fft_size / hop` is an integer
+/// of at least two. Returns `None` otherwise; [`cola_constant`] then sums
+/// the window numerically. The constants describe the periodic window, so
+/// they differ from the crate's symmetric windows by `O(1 / fft_size)`.
+///
+/// # Panics
+/// Panics when `fft_size` or `hop` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cola_closed_form(window_type: &str, fft_size: usize, hop: usize) -> Option<f32> {
+    assert!(fft_size > 0, "fft_size must be positive");
+    assert!(hop > 0, "hop must be positive");
+    if !fft_size.is_multiple_of(hop) {
+        return None;
+    }
+    let ratio = (fft_size / hop) as f32;
+    match parse_window(window_type) {
+        WindowType::Rectangular => Some(ratio),
+        WindowType::Hann if ratio >= 2.0 => Some(HANN_A0 * ratio),
+        WindowType::Hamming if ratio >= 2.0 => Some(HAMMING_ALPHA * ratio),
+        _ => None,
+    }
+}
+
+/// Overlap-add normalization constant for `window_type` frames every `hop`.
+///
+/// # What
+/// Uses [`cola_closed_form`] where it applies; otherwise returns the
+/// maximum of the numerically overlap-added window sum over one hop. Call
+/// [`cola_closed_form`] directly to learn which path applies. Unknown
+/// window names panic.
+///
+/// # Panics
+/// Panics when `fft_size` or `hop` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cola_constant(window_type: &str, fft_size: usize, hop: usize) -> f32 {
+    if let Some(constant) = cola_closed_form(window_type, fft_size, hop) {
+        return constant;
+    }
+    let window = window_coefficients(parse_window(window_type), fft_size);
+    window_sum_max(&window, hop)
+}
+
+/// Maximum over one hop of the overlap-added sum of `window`.
+fn window_sum_max(window: &[f32], hop: usize) -> f32 {
+    window_sums(window, hop).fold(0.0, f32::max)
+}
+
+/// Overlap-added sum of `window` at each of the `hop` positions of one
+/// period. When `hop` exceeds the window, the gap positions past its end
+/// all sum to `0` and are reported once, so huge hops stay cheap.
+fn window_sums(window: &[f32], hop: usize) -> impl Iterator<Item = f32> + '_ {
+    let covered = hop.min(window.len());
+    (0..covered)
+        .map(move |i| window.iter().skip(i).step_by(hop).sum::<f32>())
+        .chain((hop > covered).then_some(0.0))
+}
+
+/// Relative ripple below which [`is_cola`] accepts a window and hop. The
+/// crate's symmetric windows miss exact COLA by `O(1 / fft_size)`.
+const COLA_TOLERANCE: f32 = 1e-2;
+
+/// Relative ripple of the overlap-added `window_type` sum at `hop`.
+///
+/// # What
+/// Returns `(max - min) / max` of `sum_m w[i - m * hop]` over one hop: `0`
+/// for a constant sum (the COLA condition), `1` when the frames leave gaps.
+/// Unknown window names panic.
+///
+/// # Panics
+/// Panics when `fft_size` or `hop` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn cola_ripple(window_type: &str, fft_size: usize, hop: usize) -> f32 {
+    assert!(fft_size > 0, "fft_size must be positive");
+    assert!(hop > 0, "hop must be positive");
+    let window = window_coefficients(parse_window(window_type), fft_size);
+    let (min, max) =
+        window_sums(&window, hop).fold((f32::MAX, 0.0f32), |(lo, hi), s| (lo.min(s), hi.max(s)));
+    if max > 0.0 {
+        (max - min) / max
+    } else {
+        1.0
+    }
+}
+
+/// Whether `window_type` frames every `hop` satisfy constant overlap-add.
+///
+/// # What
+/// True when [`cola_ripple`] is at most [`COLA_TOLERANCE`], so plain
+/// overlap-add of the analysis frames reproduces the input up to
+/// [`cola_constant`]. [`istft_signal`] divides by the squared-window sum
+/// instead and works for non-COLA hops too, as long as frames overlap.
+///
+/// # Why
+/// Lets a UI reject window and hop combinations that would leave amplitude
+/// ripple in resynthesized audio before any processing runs.
+///
+/// # Panics
+/// Panics when `fft_size` or `hop` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn is_cola(window_type: &str, fft_size: usize, hop: usize) -> bool {
+    cola_ripple(window_type, fft_size, hop) <= COLA_TOLERANCE
+}
+
+/// Length of the anti-aliasing FIR applied before downsampling.
+const RESAMPLE_TAPS: usize = 63;
+
+/// Semitones per octave in equal temperament.
+const SEMITONES_PER_OCTAVE: f32 = 12.0;
+
+/// Resample `input` to exactly `out_len` samples by linear interpolation.
+///
+/// # How
+/// When shrinking, the signal is first low-pass filtered at the new Nyquist
+/// frequency so content that no longer fits does not alias.
+fn resample_to_length(input: &[f32], out_len: usize) -> Vec<f32> {
+    if input.is_empty() || out_len == 0 {
+        return vec![0.0; out_len];
+    }
+    let ratio = input.len() as f32 / out_len as f32;
+    let source = if ratio > 1.0 {
+        fir_filter_centered(input, &lowpass_taps(0.5 / ratio, RESAMPLE_TAPS))
+    } else {
+        input.to_vec()
+    };
+    let last = source.len() - 1;
+    (0..out_len)
+        .map(|j| {
+            let pos = j as f32 * ratio;
+            let i = (pos.floor() as usize).min(last);
+            let frac = pos - i as f32;
+            let next = source[(i + 1).min(last)];
+            source[i] + (next - source[i]) * frac
+        })
+        .collect()
+}
+
+/// True frequency in radians per sample of every half-spectrum bin.
+///
+/// # How
+/// The phase advance between two frames `hop` samples apart is compared
+/// with the advance expected at the bin center; the wrapped deviation is
+/// the offset of the true frequency from that center.
+fn instantaneous_frequency(phase: &[f32], prev_phase: &[f32], n: usize, hop: usize) -> Vec<f32> {
+    phase
+        .iter()
+        .zip(prev_phase)
+        .enumerate()
+        .map(|(k, (&p, &q))| {
+            let center = TWO_PI * k as f32 / n as f32;
+            if hop == 0 {
+                return center;
+            }
+            let advance = center * hop as f32;
+            center + wrap_phase(p - q - advance) / hop as f32
+        })
+        .collect()
+}
+
+/// Indices of local magnitude maxima, used as phase-locking anchors.
+fn spectral_peaks(mags: &[f32]) -> Vec<usize> {
+    (0..mags.len())
+        .filter(|&k| {
+            let left = k == 0 || mags[k] > mags[k - 1];
+            let right = k + 1 == mags.len() || mags[k] >= mags[k + 1];
+            left && right && mags[k] > 0.0
+        })
+        .collect()
+}
+
+/// Stretch `input` in time by `factor` without changing its pitch.
+///
+/// # What
+/// A phase vocoder: analysis frames of `fft_size` samples are taken every
+/// `hop / factor` input samples and resynthesized every `hop` output
+/// samples, so the output is `round(input.len() * factor)` samples long.
+/// `factor > 1` slows down, `factor < 1` speeds up. Unknown window names
+/// panic, matching [`crate::apply_window`].
+///
+/// # How
+/// Each bin's instantaneous frequency is estimated from the phase advance
+/// between analysis frames and integrated over the synthesis hop. Only
+/// spectral peaks are integrated; the other bins keep their analysis phase
+/// offset from the nearest peak (identity phase locking), which preserves
+/// the phase relations within each partial and reduces phasiness. Frames
+/// are windowed again, overlap-added and divided by the summed squared
+/// window.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values, `factor` is not a
+/// positive finite number, `fft_size < 2`, or `hop` is zero or larger than
+/// `fft_size`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn time_stretch(
+    input: &[f32],
+    factor: f32,
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+) -> Vec<f32> {
+    validate_finite(input);
+    assert!(
+        factor.is_finite() && factor > 0.0,
+        "factor must be a positive finite number"
+    );
+    assert!(fft_size >= 2, "fft_size must be at least 2");
+    assert!(hop > 0 && hop <= fft_size, "hop must be in 1..=fft_size");
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let n = fft_size;
+    let bins = n / 2 + 1;
+    let window = window_coefficients(parse_window(window_type), n);
+    let forward = plan_forward(n);
+    let inverse = plan_inverse(n);
+
+    // Pad by a whole frame on both sides so the edges are fully covered.
+    let pad = n;
+    let mut padded = vec![0.0f32; pad];
+    padded.extend_from_slice(input);
+    padded.resize(padded.len() + pad, 0.0);
+    let step = hop as f64 / factor as f64;
+    let positions: Vec<usize> = (0..)
+        .map(|m| (m as f64 * step).round() as usize)
+        .take_while(|&p| p + n <= padded.len())
+        .collect();
+
+    let out_len = (input.len() as f32 * factor).round() as usize;
+    let start = (pad as f32 * factor).round() as usize;
+    let total = ((positions.len() - 1) * hop + n).max(start + out_len);
+    let mut output = vec![0.0f32; total];
+    let mut norm = vec![0.0f32; total];
+
+    let mut prev_phase = vec![0.0f32; bins];
+    let mut synth_phase = vec![0.0f32; bins];
+    let mut buffer = vec![Complex32::new(0.0, 0.0); n];
+    for (m, &pos) in positions.iter().enumerate() {
+        for ((b, &x), &w) in buffer.iter_mut().zip(&padded[pos..pos + n]).zip(&window) {
+            *b = Complex32::new(x * w, 0.0);
+        }
+        forward.process(&mut buffer);
+        let mags: Vec<f32> = buffer[..bins].iter().map(|c| c.norm()).collect();
+        let phase: Vec<f32> = buffer[..bins].iter().map(|c| c.arg()).collect();
+
+        if m == 0 {
+            synth_phase.copy_from_slice(&phase);
+        } else {
+            let freq = instantaneous_frequency(&phase, &prev_phase, n, pos - positions[m - 1]);
+            let peaks = spectral_peaks(&mags);
+            let mut next = vec![0.0f32; bins];
+            if peaks.is_empty() {
+                for k in 0..bins {
+                    next[k] = wrap_phase(synth_phase[k] + hop as f32 * freq[k]);
+                }
+            } else {
+                for &p in &peaks {
+                    next[p] = wrap_phase(synth_phase[p] + hop as f32 * freq[p]);
+                }
+                let mut nearest = 0;
+                for k in 0..bins {
+                    while nearest + 1 < peaks.len()
+                        && peaks[nearest + 1].abs_diff(k) <= peaks[nearest].abs_diff(k)
+                    {
+                        nearest += 1;
+                    }
+                    let p = peaks[nearest];
+                    if k != p {
+                        next[k] = wrap_phase(next[p] + phase[k] - phase[p]);
+                    }
+                }
+            }
+            synth_phase = next;
+        }
+        prev_phase = phase;
+
+        for k in 0..bins {
+            buffer[k] = Complex32::from_polar(mags[k], synth_phase[k]);
+        }
+        for k in bins..n {
+            buffer[k] = buffer[n - k].conj();
+        }
+        inverse.process(&mut buffer);
+        let offset = m * hop;
+        let scale = 1.0 / n as f32;
+        for (i, (c, &w)) in buffer.iter().zip(&window).enumerate() {
+            output[offset + i] += c.re * scale * w;
+            norm[offset + i] += w * w;
+        }
+    }
+
+    output[start..start + out_len]
+        .iter()
+        .zip(&norm[start..start + out_len])
+        .map(|(&y, &w)| if w > NORM_FLOOR { y / w } else { 0.0 })
+        .collect()
+}
+
+/// Shift the pitch of `input` by `semitones` while keeping its duration.
+///
+/// # What
+/// Time-stretches by the pitch ratio `2^(semitones / 12)` with
+/// [`time_stretch`] and resamples the result back to `input.len()` samples,
+/// which scales every frequency by the ratio. Positive values shift up.
+/// The remaining parameters are passed to [`time_stretch`].
+///
+/// # Panics
+/// Panics when `semitones` is not finite or [`time_stretch`] rejects its
+/// parameters.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn pitch_shift(
+    input: &[f32],
+    semitones: f32,
+    fft_size: usize,
+    hop: usize,
+    window_type: &str,
+) -> Vec<f32> {
+    assert!(semitones.is_finite(), "semitones must be finite");
+    let ratio = 2f32.powf(semitones / SEMITONES_PER_OCTAVE);
+    let stretched = time_stretch(input, ratio, fft_size, hop, window_type);
+    resample_to_length(&stretched, input.len())
+}
+
+/// Overlap-add a sequence of complex STFT frames back into a signal.
+///
+/// # What
+/// `frames` holds consecutive half spectra of `frame_size / 2 + 1` complex
+/// bins, each in the interleaved [`crate::fft_real`] layout. Every frame is
+/// inverse transformed as in [`crate::ifft_real`], multiplied by the synthesis window and added at multiples
+/// of `hop`; the sum is divided by the overlap-added squared window. For
+/// frames analysed with the same window this reconstructs the input
+/// wherever the squared windows overlap; samples whose squared-window sum
+/// is below [`NORM_FLOOR`] (the very edges) are returned as `0`. The output
+/// holds `(n_frames - 1) * hop + frame_size` samples, or none for empty
+/// `frames`.
+///
+/// # Why
+/// Spectral editors modify STFT frames and need one continuous signal
+/// back, with the overlap gain compensated.
+///
+/// # Panics
+/// Panics when `frames` contains non-finite values, `frame_size` or `hop`
+/// is zero, or `frames.len()` is not a multiple of `2 * (frame_size / 2 + 1)`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn istft_signal(frames: &[f32], frame_size: usize, hop: usize, window_type: &str) -> Vec<f32> {
+    validate_finite(frames);
+    assert!(frame_size > 0, "frame_size must be positive");
+    assert!(hop > 0, "hop must be positive");
+    let frame_len = half_spectrum_len(frame_size);
+    assert!(
+        frames.len().is_multiple_of(frame_len),
+        "frames must hold whole frames of interleaved re/im bins"
+    );
+    if frames.is_empty() {
+        return Vec::new();
+    }
+    let window = window_coefficients(parse_window(window_type), frame_size);
+    let inverse = plan_inverse(frame_size);
+    let n_frames = frames.len() / frame_len;
+    let total = (n_frames - 1) * hop + frame_size;
+    let mut output = vec![0.0f32; total];
+    let mut norm = vec![0.0f32; total];
+
+    let scale = 1.0 / frame_size as f32;
+    let mut buffer = vec![Complex32::new(0.0, 0.0); frame_size];
+    for (m, frame) in frames.chunks_exact(frame_len).enumerate() {
+        mirror_half_spectrum(frame, &mut buffer);
+        inverse.process(&mut buffer);
+        let offset = m * hop;
+        for (i, (c, &w)) in buffer.iter().zip(&window).enumerate() {
+            output[offset + i] += c.re * scale * w;
+            norm[offset + i] += w * w;
+        }
+    }
+    output
+        .iter()
+        .zip(&norm)
+        .map(|(&y, &w)| if w > NORM_FLOOR { y / w } else { 0.0 })
+        .collect()
+}
+
+/// Inverse STFT: overlap-add `fft_size`-point frames spaced `hop_size`
+/// apart back into a signal.
+///
+/// # What
+/// The same reconstruction as [`istft_signal`], under the conventional
+/// name. Pair it with [`is_cola`] to check a window and hop before
+/// editing.
+///
+/// # Panics
+/// Panics under the same conditions as [`istft_signal`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn istft(frames: &[f32], fft_size: usize, hop_size: usize, window_type: &str) -> Vec<f32> {
+    istft_signal(frames, fft_size, hop_size, window_type)
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tolerance for floating point comparisons in tests.
+    const TOLERANCE: f32 = 1e-2;
+
+    /// Hann at 50% overlap has unit gain; other hops scale it.
+    #[test]
+    fn overlap_add_gain_depends_on_hop() {
+        let n = 1024;
+        assert!((overlap_add_gain("hann", n, n / 2) - 1.0).abs() < TOLERANCE);
+        assert!((overlap_add_gain("hann", n, n / 4) - 2.0).abs() < TOLERANCE);
+        let odd = overlap_add_gain("hann", n, 384);
+        assert!((odd - 4.0 / 3.0).abs() < TOLERANCE, "{odd}");
+        assert!((overlap_add_gain("rect", n, n) - 1.0).abs() < 1e-6);
+    }
+
+    /// Zero crossings per sample over the middle half of `signal`.
+    fn zero_crossing_rate(signal: &[f32]) -> f32 {
+        let mid = &signal[signal.len() / 4..3 * signal.len() / 4];
+        let crossings = mid
+            .windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count();
+        crossings as f32 / mid.len() as f32
+    }
+
+    /// Stretching a tone by 2x doubles its length but keeps its frequency.
+    #[test]
+    fn time_stretch_preserves_pitch() {
+        let (rate, tone_hz) = (8000.0f32, 500.0f32);
+        let input: Vec<f32> = (0..4000)
+            .map(|i| (TWO_PI * tone_hz * i as f32 / rate).sin())
+            .collect();
+        let stretched = time_stretch(&input, 2.0, 512, 128, "hann");
+        assert_eq!(stretched.len(), 2 * input.len());
+
+        let expected = zero_crossing_rate(&input);
+        let actual = zero_crossing_rate(&stretched);
+        assert!(
+            (actual - expected).abs() / expected < 0.02,
+            "{actual} vs {expected}"
+        );
+        let mid = &stretched[2000..6000];
+        let peak = mid.iter().fold(0.0f32, |a, &b| a.max(b.abs()));
+        assert!((peak - 1.0).abs() < 0.1, "peak {peak}");
+    }
+
+    /// Shifting up an octave doubles the frequency and keeps the length.
+    #[test]
+    fn pitch_shift_octave_doubles_frequency() {
+        let (rate, tone_hz) = (8000.0f32, 500.0f32);
+        let input: Vec<f32> = (0..4000)
+            .map(|i| (TWO_PI * tone_hz * i as f32 / rate).sin())
+            .collect();
+        let shifted = pitch_shift(&input, 12.0, 512, 128, "hann");
+        assert_eq!(shifted.len(), input.len());
+
+        let expected = 2.0 * zero_crossing_rate(&input);
+        let actual = zero_crossing_rate(&shifted);
+        assert!(
+            (actual - expected).abs() / expected < 0.02,
+            "{actual} vs {expected}"
+        );
+    }
+
+    /// Closed-form Hann constants agree with the numerical window sums.
+    #[test]
+    fn cola_constant_closed_form_matches_numeric_sum() {
+        let n = 1024;
+        let window = window_coefficients(WindowType::Hann, n);
+        for (hop, expected) in [(n / 2, 1.0), (n / 4, 2.0)] {
+            let closed = cola_closed_form("hann", n, hop).expect("closed form");
+            assert_eq!(cola_constant("hann", n, hop), closed);
+            assert!((closed - expected).abs() < 1e-6);
+            assert!((window_sum_max(&window, hop) - closed).abs() < TOLERANCE);
+        }
+        assert!(cola_closed_form("hann", n, 384).is_none());
+        let numeric = cola_constant("hann", n, 384);
+        assert!((numeric - window_sum_max(&window, 384)).abs() < 1e-6);
+    }
+
+    /// Standard COLA hops pass and gapped or mismatched hops fail.
+    #[test]
+    fn is_cola_accepts_standard_hops() {
+        let n = 1024;
+        assert!(is_cola("hann", n, n / 2));
+        assert!(is_cola("hann", n, n / 4));
+        assert!(is_cola("rect", n, n));
+        assert!(!is_cola("hann", n, 600));
+        assert!(!is_cola("rect", n, 3 * n / 4));
+        assert_eq!(cola_ripple("hann", n, 2 * n), 1.0);
+        // Hops far beyond the window are gapped without scanning every gap.
+        assert!(!is_cola("hann", 64, 200_000_000));
+        assert_eq!(cola_constant("rect", 64, 200_000_000), 1.0);
+    }
+
+    /// Forward Hann frames at 50% overlap reconstruct a sine away from the
+    /// edges, through `istft` as well.
+    #[test]
+    fn istft_signal_reconstructs_sine() {
+        let (n, hop, len) = (256, 128, 4096);
+        let sine: Vec<f32> = (0..len).map(|i| (0.05 * i as f32).sin()).collect();
+        let frames: Vec<f32> = (0..=(len - n) / hop)
+            .flat_map(|m| {
+                crate::fft_real(&crate::apply_window(&sine[m * hop..m * hop + n], "hann"))
+            })
+            .collect();
+
+        let output = istft_signal(&frames, n, hop, "hann");
+        assert_eq!(output.len(), len);
+        for i in n..len - n {
+            assert!((output[i] - sine[i]).abs() < 1e-3, "sample {i}");
+        }
+        assert_eq!(istft(&frames, n, hop, "hann"), output);
+    }
+}
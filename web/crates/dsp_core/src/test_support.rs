@@ -0,0 +1,63 @@
+//! Shared helpers for the crate's unit tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Multiplier of the 64-bit linear congruential generator (Knuth MMIX).
+const LCG_MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+/// Increment of the 64-bit linear congruential generator (Knuth MMIX).
+const LCG_INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+/// Deterministic uniform noise in `[-1, 1)`.
+///
+/// # Why
+/// Tests need reproducible pseudo-random signals without pulling in an RNG
+/// dependency; the same `seed` always yields the same samples.
+pub(crate) fn noise(len: usize, seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(LCG_MULTIPLIER)
+                .wrapping_add(LCG_INCREMENT);
+            // Use the high 24 bits, which have the best statistical quality.
+            let unit = (state >> 40) as f32 / (1u64 << 24) as f32;
+            2.0 * unit - 1.0
+        })
+        .collect()
+}
+
+thread_local! {
+    /// Heap allocations made by the current thread.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// System allocator that counts allocations per thread.
+///
+/// # Why
+/// Tests run in parallel threads; a per-thread counter lets one test
+/// measure its own allocations without interference.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // `try_with` fails harmlessly while the thread is being torn down.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Number of heap allocations `f` performs on the current thread.
+pub(crate) fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
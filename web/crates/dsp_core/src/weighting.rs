@@ -0,0 +1,290 @@
+//! Perceptual frequency weighting of spectra.
+//!
+//! # What
+//! Standard weighting curves evaluated at FFT bin frequencies and helpers
+//! that apply them to dBFS magnitudes.
+//!
+//! # Why
+//! Sound level meters report weighted levels that follow the ear's
+//! sensitivity. Evaluating the curve per bin in WASM saves JavaScript from
+//! re-deriving it for every frame.
+
+use crate::display::piecewise_linear;
+use crate::{
+    half_spectrum_magnitudes, linear_to_dbfs_in_place, validate_finite, validate_not_nan, DB_SCALE,
+    EPSILON, POWER_DB_SCALE,
+};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Pole frequencies in Hz of the IEC 61672 A-weighting curve.
+const A_POLES_HZ: [f64; 4] = [20.598_997, 107.652_65, 737.862_23, 12_194.217];
+
+/// Gain in dB that normalizes the A-weighting curve to 0 dB at 1 kHz.
+const A_NORMALIZATION_DB: f64 = 2.0;
+
+/// Gain in dB that normalizes the C-weighting curve to 0 dB at 1 kHz.
+const C_NORMALIZATION_DB: f64 = 0.062;
+
+/// A-weighting gain in dB at `hz`, floored at the dB of [`EPSILON`] at DC.
+fn a_weighting_db(hz: f64) -> f32 {
+    let [p1, p2, p3, p4] = A_POLES_HZ.map(|p| p * p);
+    let f2 = hz * hz;
+    let response = p4 * f2 * f2 / ((f2 + p1) * ((f2 + p2) * (f2 + p3)).sqrt() * (f2 + p4));
+    (DB_SCALE as f64 * response.max(EPSILON as f64).log10() + A_NORMALIZATION_DB) as f32
+}
+
+/// C-weighting gain in dB at `hz`, floored at the dB of [`EPSILON`] at DC.
+///
+/// Uses the outer poles of the A-weighting curve only.
+fn c_weighting_db(hz: f64) -> f32 {
+    let (p1, p4) = (A_POLES_HZ[0] * A_POLES_HZ[0], A_POLES_HZ[3] * A_POLES_HZ[3]);
+    let f2 = hz * hz;
+    let response = p4 * f2 / ((f2 + p1) * (f2 + p4));
+    (DB_SCALE as f64 * response.max(EPSILON as f64).log10() + C_NORMALIZATION_DB) as f32
+}
+
+/// Gain in dB of the named curve at `hz`: `"a"`, `"c"` or `"z"` (flat).
+///
+/// # Panics
+/// Panics on an unknown curve name.
+fn weighting_db(curve: &str) -> fn(f64) -> f32 {
+    match curve {
+        "a" => a_weighting_db,
+        "c" => c_weighting_db,
+        "z" => |_| 0.0,
+        _ => panic!("unknown weighting curve: {curve}"),
+    }
+}
+
+/// A-weighting gain in dB for every bin of an `fft_size`-point spectrum.
+///
+/// # What
+/// Returns the IEC 61672 A-weighting curve at the frequencies of bins
+/// `0..=fft_size/2`, bin `k` lying at `k * sample_rate / fft_size` Hz. The
+/// curve is 0 dB at 1 kHz, about -19 dB at 100 Hz and falls without bound
+/// towards DC, where it is floored at the dB value of [`EPSILON`].
+///
+/// # Panics
+/// Panics when `sample_rate` is not positive or `fft_size` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn a_weighting(sample_rate: f32, fft_size: usize) -> Vec<f32> {
+    curve_at_bins(sample_rate, fft_size, a_weighting_db)
+}
+
+/// A-weighted dBFS magnitudes of bins `0..=n/2` of a real block.
+///
+/// # What
+/// The half-spectrum dBFS magnitudes of `input` (windowing is up to the
+/// caller, as for [`crate::magnitude_dbfs`]) plus the [`a_weighting`] gain
+/// of each bin. Empty input yields an empty result.
+///
+/// # Panics
+/// Panics when `input` contains non-finite values or `sample_rate` is not
+/// positive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn magnitude_dba(input: &[f32], reference: f32, sample_rate: f32) -> Vec<f32> {
+    validate_finite(input);
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let mut mags = half_spectrum_magnitudes(input);
+    linear_to_dbfs_in_place(&mut mags, reference);
+    for (m, w) in mags.iter_mut().zip(a_weighting(sample_rate, input.len())) {
+        *m += w;
+    }
+    mags
+}
+
+/// C-weighting gain in dB for every bin of an `fft_size`-point spectrum.
+///
+/// # What
+/// The IEC 61672 C-weighting curve at bins `0..=fft_size/2`, laid out as
+/// [`a_weighting`]. It is flat within a dB from about 50 Hz to 5 kHz and
+/// rolls off 3 dB at 31.5 Hz and 8 kHz.
+///
+/// # Panics
+/// Panics when `sample_rate` is not positive or `fft_size` is zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn c_weighting(sample_rate: f32, fft_size: usize) -> Vec<f32> {
+    curve_at_bins(sample_rate, fft_size, c_weighting_db)
+}
+
+/// `weight` evaluated at the frequencies of bins `0..=fft_size/2`.
+fn curve_at_bins(sample_rate: f32, fft_size: usize, weight: impl Fn(f64) -> f32) -> Vec<f32> {
+    assert!(
+        sample_rate.is_finite() && sample_rate > 0.0,
+        "sample_rate must be a positive finite number"
+    );
+    assert!(fft_size > 0, "fft_size must be positive");
+    let bin_hz = sample_rate as f64 / fft_size as f64;
+    (0..=fft_size / 2)
+        .map(|k| weight(k as f64 * bin_hz))
+        .collect()
+}
+
+/// Add a standard weighting curve to a half-spectrum dB frame.
+///
+/// # What
+/// `db_frame` holds bins `0..=fft_size/2` of an even `fft_size`-point
+/// spectrum in dB (e.g. [`crate::FrameAnalyzer::analyze`] output), so
+/// `fft_size = 2 * (len - 1)`. `curve` is `"a"`, `"c"` or `"z"` (zero, i.e.
+/// unweighted). Returns the frame with each bin's gain added; silent `-inf`
+/// bins stay `-inf`.
+///
+/// # Panics
+/// Panics when `db_frame` contains NaN, `sample_rate` is not positive or
+/// `curve` is unknown.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_weighting(db_frame: &[f32], sample_rate: f32, curve: &str) -> Vec<f32> {
+    validate_not_nan(db_frame);
+    let weight = weighting_db(curve);
+    if db_frame.is_empty() {
+        return Vec::new();
+    }
+    let fft_size = (2 * (db_frame.len() - 1)).max(1);
+    let gains = curve_at_bins(sample_rate, fft_size, weight);
+    db_frame.iter().zip(gains).map(|(&d, g)| d + g).collect()
+}
+
+/// Add a user-supplied weighting curve to a half-spectrum dB frame.
+///
+/// # What
+/// The curve passes through `(freqs_hz[i], gains_db[i])`, with `freqs_hz`
+/// ascending; each bin's gain is interpolated linearly in frequency and
+/// held at the end values outside the given range. The frame layout is
+/// that of [`apply_weighting`].
+///
+/// # Why
+/// Microphone corrections and other standards (ITU-R 468, G-weighting)
+/// come as tables of points rather than formulas.
+///
+/// # Panics
+/// Panics when `db_frame` contains NaN, the curve points are non-finite,
+/// `sample_rate` is not positive, `freqs_hz` and `gains_db` differ in length
+/// or are empty, or `freqs_hz` is not ascending.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn apply_custom_weighting(
+    db_frame: &[f32],
+    sample_rate: f32,
+    freqs_hz: &[f32],
+    gains_db: &[f32],
+) -> Vec<f32> {
+    validate_not_nan(db_frame);
+    validate_finite(freqs_hz);
+    validate_finite(gains_db);
+    assert_eq!(
+        freqs_hz.len(),
+        gains_db.len(),
+        "freqs_hz and gains_db must have the same length"
+    );
+    assert!(!freqs_hz.is_empty(), "the curve needs at least one point");
+    assert!(
+        freqs_hz.windows(2).all(|w| w[0] < w[1]),
+        "freqs_hz must be ascending"
+    );
+    if db_frame.is_empty() {
+        return Vec::new();
+    }
+    let fft_size = (2 * (db_frame.len() - 1)).max(1);
+    let gains = curve_at_bins(sample_rate, fft_size, |hz| {
+        piecewise_linear(freqs_hz, gains_db, hz as f32)
+    });
+    db_frame.iter().zip(gains).map(|(&d, g)| d + g).collect()
+}
+
+/// Total level of a dB frame, e.g. a weighted one.
+///
+/// # What
+/// Sums the bins as powers and returns `10 * log10` of the sum, so a frame
+/// from [`apply_weighting`] yields the frame's weighted level. Silent
+/// `-inf` bins add no power; empty or all-silent frames return the dB value
+/// of [`EPSILON`].
+///
+/// # Panics
+/// Panics when `db_frame` contains NaN.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn frame_level_db(db_frame: &[f32]) -> f32 {
+    validate_not_nan(db_frame);
+    let power: f64 = db_frame
+        .iter()
+        .map(|&d| 10f64.powf(d as f64 / POWER_DB_SCALE as f64))
+        .sum();
+    POWER_DB_SCALE * (power as f32).max(EPSILON).log10()
+}
+
+// -----------------------------------------------------------------------------
+// Tests
+// -----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The curve is flat at 1 kHz and about -19.1 dB at 100 Hz.
+    #[test]
+    fn a_weighting_matches_reference_points() {
+        // 1 Hz bins, so bin k lies at k Hz.
+        let curve = a_weighting(4000.0, 4000);
+        assert_eq!(curve.len(), 2001);
+        assert!(curve[1000].abs() < 0.05, "{}", curve[1000]);
+        assert!((curve[100] + 19.1).abs() < 0.1, "{}", curve[100]);
+        assert!(curve[10] < -60.0);
+        assert!(curve.iter().all(|w| w.is_finite()));
+    }
+
+    /// Weighting shifts each dBFS bin by the curve.
+    #[test]
+    fn magnitude_dba_adds_curve_to_dbfs() {
+        let input = crate::test_support::noise(256, 8);
+        let weighted = magnitude_dba(&input, 1.0, 8000.0);
+        let plain = crate::magnitude_dbfs(&input, 1.0);
+        let curve = a_weighting(8000.0, 256);
+        assert_eq!(weighted.len(), 129);
+        for k in 0..weighted.len() {
+            assert!((weighted[k] - plain[k] - curve[k]).abs() < 1e-3);
+        }
+    }
+
+    /// C-weighting hits its reference points and the named and custom curves
+    /// are added bin by bin.
+    #[test]
+    fn weighting_curves_apply_to_db_frames() {
+        let curve = c_weighting(4000.0, 4000);
+        assert!(curve[1000].abs() < 0.05, "{}", curve[1000]);
+        assert!((curve[31] + 3.0).abs() < 0.2, "{}", curve[31]);
+        assert!((curve[100] + 0.3).abs() < 0.1, "{}", curve[100]);
+
+        let frame = vec![-20.0f32; 129];
+        assert_eq!(apply_weighting(&frame, 8000.0, "z"), frame);
+        let a = apply_weighting(&frame, 8000.0, "a");
+        for (w, g) in a.iter().zip(a_weighting(8000.0, 256)) {
+            assert!((w - (g - 20.0)).abs() < 1e-4);
+        }
+
+        let custom = apply_custom_weighting(&frame, 8000.0, &[1000.0, 3000.0], &[0.0, -10.0]);
+        // Bin k lies at 31.25 * k Hz.
+        assert_eq!(custom[16], -20.0);
+        assert!((custom[64] + 25.0).abs() < 1e-4);
+        assert_eq!(custom[128], -30.0);
+
+        let level = frame_level_db(&[-3.0103, -3.0103]);
+        assert!(level.abs() < 1e-3, "{level}");
+    }
+
+    /// Silent `-inf` bins pass through weighting and add no power.
+    #[test]
+    fn weighting_accepts_silent_bins() {
+        let mut frame = vec![f32::NEG_INFINITY; 129];
+        frame[32] = -20.0;
+        let weighted = apply_weighting(&frame, 8000.0, "a");
+        assert_eq!(weighted[0], f32::NEG_INFINITY);
+        assert_eq!(weighted[128], f32::NEG_INFINITY);
+        let gain = a_weighting(8000.0, 256)[32];
+        assert!((frame_level_db(&weighted) - (gain - 20.0)).abs() < 1e-3);
+        assert_eq!(
+            frame_level_db(&[f32::NEG_INFINITY; 4]),
+            POWER_DB_SCALE * EPSILON.log10()
+        );
+    }
+}
@@ -108,7 +108,7 @@ export async function initWasm(): Promise<WasmModule> {
 }
 
 /**
- * Compute FFT (real→complex interleaved) via WASM.
+ * Compute FFT (real→complex interleaved, bins 0..=n/2) via WASM.
  * What: forwards to the Rust implementation without copying by default.
  * Why: callers may reuse the returned buffer directly to avoid allocations.
  * How: optionally copies into `output` when provided to retain ownership.
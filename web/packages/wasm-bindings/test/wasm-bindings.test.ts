@@ -137,7 +137,8 @@ test('WASM bindings execute and validate inputs', async () => {
   WebAssembly.instantiateStreaming = realInstantiateStreaming;
 
   const fft = await fftReal(makeInput());
-  assert.equal(fft.length, 8);
+  // Half spectrum: bins 0..=n/2 as interleaved re/im pairs.
+  assert.equal(fft.length, 6);
 
   const win = await applyWindow(makeInput(), 'hann');
   assert.equal(win.length, 4);